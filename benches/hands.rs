@@ -0,0 +1,154 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use riichi_rust::{
+    RiichiHand, RiichiOptions, Tiles, WinKind, calc_shanten, calc_win, find_all_agari_patterns,
+    hairi, parse_mpsz,
+};
+
+// Shanten benchmarks are named by implementation: run them with and without the shanten-table feature to compare
+const SHANTEN: &str = if cfg!(feature = "shanten-table") {
+    "table"
+} else {
+    "search"
+};
+
+// Complete closed hands, winning tile is the last one
+const AGARI_HANDS: [&str; 12] = [
+    "123m456p789s1122z2z",
+    "11223344556677m",
+    "1112345678999m9m",
+    "234m234p234s55678s",
+    "19m19p19s1234567z1m",
+    "112233m445566p77s",
+    "111222333m789p11s",
+    "222333444m567p88s",
+    "123456789m12399p",
+    "345m345p345678s55s",
+    "22334455667788p",
+    "111333555z22z777z",
+];
+
+fn options() -> RiichiOptions {
+    RiichiOptions {
+        dora: vec![Tiles::M3 as i8],
+        aka_count: 0,
+        first_take: false,
+        riichi: true,
+        ippatsu: false,
+        double_riichi: false,
+        open_riichi: false,
+        open_riichi_deal_in: false,
+        last_tile: false,
+        after_kan: false,
+        tile_discarded_by_someone: -1,
+        bakaze: Tiles::E as i8,
+        jikaze: Tiles::S as i8,
+        allow_aka: true,
+        allow_kuitan: true,
+        with_kiriage: false,
+        disabled_yaku: vec![],
+        local_yaku_enabled: vec![],
+        all_local_yaku_enabled: false,
+        allow_double_yakuman: false,
+    }
+}
+
+fn counts(tiles: &[i8]) -> Vec<i8> {
+    let mut haipai34 = vec![0; 34];
+    for tile in tiles {
+        haipai34[*tile as usize - 1] += 1;
+    }
+    haipai34
+}
+
+// Hands dealt from a shuffled wall, same for every run
+fn dealt_hands(count: usize, size: usize) -> Vec<Vec<i8>> {
+    let mut seed: u64 = 0x9e3779b97f4a7c15;
+    let mut hands = Vec::new();
+    for _ in 0..count {
+        let mut wall: Vec<i8> = (0..136).map(|i: i32| (i / 4 + 1) as i8).collect();
+        for i in (1..wall.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            wall.swap(i, (seed % (i as u64 + 1)) as usize);
+        }
+        hands.push(counts(&wall[..size]));
+    }
+    hands
+}
+
+fn bench_hands(c: &mut Criterion) {
+    let agari: Vec<(Vec<i8>, i8)> = AGARI_HANDS
+        .iter()
+        .map(|notation| {
+            let tiles = parse_mpsz(notation).unwrap().0;
+            let tile = *tiles.last().unwrap();
+            (tiles, tile)
+        })
+        .collect();
+    let agari34: Vec<Vec<i8>> = agari.iter().map(|(tiles, _)| counts(tiles)).collect();
+    assert!(agari34.iter().all(|haipai34| calc_shanten(haipai34) == -1));
+    let dealt14 = dealt_hands(1000, 14);
+    let dealt13 = dealt_hands(1000, 13);
+
+    let options = options();
+    c.bench_function("calc_win ron", |b| {
+        b.iter(|| {
+            for (tiles, tile) in &agari {
+                let hand = RiichiHand {
+                    closed_part: tiles[..13].to_vec(),
+                    open_part: vec![],
+                };
+                let win = WinKind::Ron {
+                    tile: *tile,
+                    from_seat: 2,
+                };
+                black_box(calc_win(&hand, &options, win, false).unwrap());
+            }
+        })
+    });
+    c.bench_function("calc_win tsumo with hairi", |b| {
+        b.iter(|| {
+            for (tiles, tile) in &agari {
+                let hand = RiichiHand {
+                    closed_part: tiles.clone(),
+                    open_part: vec![],
+                };
+                let win = WinKind::Tsumo(*tile);
+                black_box(calc_win(&hand, &options, win, true).unwrap());
+            }
+        })
+    });
+    c.bench_function("find_all_agari_patterns", |b| {
+        b.iter(|| {
+            for haipai34 in &agari34 {
+                black_box(find_all_agari_patterns(haipai34));
+            }
+        })
+    });
+
+    #[cfg(feature = "shanten-table")]
+    riichi_rust::init_shanten_table();
+    let mut group = c.benchmark_group("shanten");
+    group.bench_function(
+        BenchmarkId::new("calc_shanten 1000 dealt hands", SHANTEN),
+        |b| {
+            b.iter(|| {
+                for haipai34 in &dealt14 {
+                    black_box(calc_shanten(haipai34));
+                }
+            })
+        },
+    );
+    group.bench_function(BenchmarkId::new("hairi 1000 dealt hands", SHANTEN), |b| {
+        b.iter(|| {
+            for haipai34 in &dealt13 {
+                black_box(hairi(&mut haipai34.clone()));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_hands);
+criterion_main!(benches);
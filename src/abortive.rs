@@ -0,0 +1,150 @@
+use crate::constants::{TERMINALS_AND_HONORS, WINDS, tiles_to_34};
+use crate::interfaces::{AbortiveDrawRules, RiichiHand};
+use crate::riichi::split_hand;
+
+pub(crate) fn is_kyuushu_kyuuhai(haipai34: &[i8]) -> bool {
+    TERMINALS_AND_HONORS
+        .iter()
+        .filter(|t| haipai34[(**t - 1) as usize] > 0)
+        .count()
+        >= 9
+}
+
+// Check the hand on the first draw, including the drawn tile.
+// First take means no calls were made before the draw.
+pub fn check_kyuushu_kyuuhai(
+    hand: &RiichiHand,
+    first_take: bool,
+    rules: &AbortiveDrawRules,
+) -> Result<bool, String> {
+    let (haipai, furo) = split_hand(hand);
+    if haipai.len() + furo.len() * 3 != 14 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+
+    Ok(rules.kyuushu_kyuuhai
+        && first_take
+        && furo.is_empty()
+        && is_kyuushu_kyuuhai(&tiles_to_34(&haipai)))
+}
+
+// First discards of all four players, made without any calls in between
+pub fn check_suufon_renda(first_discards: &[i8], rules: &AbortiveDrawRules) -> bool {
+    rules.suufon_renda
+        && first_discards.len() == 4
+        && WINDS.contains(&first_discards[0])
+        && first_discards.iter().all(|t| *t == first_discards[0])
+}
+
+// Riichi status of every player at the table
+pub fn check_suucha_riichi(riichi: &[bool], rules: &AbortiveDrawRules) -> bool {
+    rules.suucha_riichi && riichi.len() == 4 && riichi.iter().all(|r| *r)
+}
+
+// Count of kans declared by every player at the table.
+// Four kans by one player is not an abortive draw: the game goes on for suukantsu.
+pub fn check_suukaikan(kans: &[i8], rules: &AbortiveDrawRules) -> bool {
+    let total: i8 = kans.iter().sum();
+    let declared_by = kans.iter().filter(|k| **k > 0).count();
+    rules.suukaikan && total >= 4 && declared_by > 1
+}
+
+// Count of players calling ron on the same discard
+pub fn check_sanchahou(ron_count: i8, rules: &AbortiveDrawRules) -> bool {
+    rules.sanchahou && ron_count >= 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn rules() -> AbortiveDrawRules {
+        AbortiveDrawRules {
+            kyuushu_kyuuhai: true,
+            suufon_renda: true,
+            suucha_riichi: true,
+            suukaikan: true,
+            sanchahou: true,
+        }
+    }
+
+    fn kyuushu_hand() -> RiichiHand {
+        RiichiHand {
+            open_part: vec![],
+            closed_part: vec![
+                Tiles::M1 as i8,
+                Tiles::M2 as i8,
+                Tiles::M9 as i8,
+                Tiles::P1 as i8,
+                Tiles::P9 as i8,
+                Tiles::P5 as i8,
+                Tiles::S9 as i8,
+                Tiles::S3 as i8,
+                Tiles::E as i8,
+                Tiles::E as i8,
+                Tiles::W as i8,
+                Tiles::N as i8,
+                Tiles::RD as i8,
+                Tiles::S4 as i8,
+            ],
+        }
+    }
+
+    #[test]
+    pub fn should_detect_kyuushu_kyuuhai() {
+        let res = check_kyuushu_kyuuhai(&kyuushu_hand(), true, &rules());
+        assert!(res.unwrap());
+
+        let res = check_kyuushu_kyuuhai(&kyuushu_hand(), false, &rules());
+        assert!(!res.unwrap());
+
+        let mut disabled = rules();
+        disabled.kyuushu_kyuuhai = false;
+        let res = check_kyuushu_kyuuhai(&kyuushu_hand(), true, &disabled);
+        assert!(!res.unwrap());
+
+        // only eight kinds
+        let mut hand = kyuushu_hand();
+        hand.closed_part[12] = Tiles::M5 as i8;
+        let res = check_kyuushu_kyuuhai(&hand, true, &rules());
+        assert!(!res.unwrap());
+
+        hand.closed_part.pop();
+        assert!(check_kyuushu_kyuuhai(&hand, true, &rules()).is_err());
+    }
+
+    #[test]
+    pub fn should_detect_suufon_renda() {
+        let east = Tiles::E as i8;
+        assert!(check_suufon_renda(&[east, east, east, east], &rules()));
+        assert!(!check_suufon_renda(&[east, east, east], &rules()));
+        assert!(!check_suufon_renda(
+            &[east, east, east, Tiles::S as i8],
+            &rules()
+        ));
+        let red = Tiles::RD as i8;
+        assert!(!check_suufon_renda(&[red, red, red, red], &rules()));
+    }
+
+    #[test]
+    pub fn should_detect_riichi_kan_and_ron_draws() {
+        assert!(check_suucha_riichi(&[true, true, true, true], &rules()));
+        assert!(!check_suucha_riichi(&[true, true, false, true], &rules()));
+
+        assert!(check_suukaikan(&[1, 0, 3, 0], &rules()));
+        assert!(!check_suukaikan(&[4, 0, 0, 0], &rules()));
+        assert!(!check_suukaikan(&[1, 1, 1, 0], &rules()));
+
+        assert!(check_sanchahou(3, &rules()));
+        assert!(!check_sanchahou(2, &rules()));
+
+        let mut disabled = rules();
+        disabled.suucha_riichi = false;
+        disabled.suukaikan = false;
+        disabled.sanchahou = false;
+        assert!(!check_suucha_riichi(&[true, true, true, true], &disabled));
+        assert!(!check_suukaikan(&[1, 0, 3, 0], &disabled));
+        assert!(!check_sanchahou(3, &disabled));
+    }
+}
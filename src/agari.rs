@@ -1,39 +1,40 @@
-use crate::constants::{Suit, Val, digest, kokushi_idx, slice_by_suit, sum};
+use crate::constants::{Suit, Val, kokushi_idx};
+use crate::hand34::Hand34;
+use std::ops::Deref;
 
-pub fn check7(haipai: &Vec<i8>) -> bool {
+pub fn check7(hand: &Hand34) -> bool {
     let mut s = 0;
-    for i in 0..haipai.len() {
-        if haipai[i] > 0 && haipai[i] != 2 {
+    for i in 0..34 {
+        let count = hand.get(i);
+        if count > 0 && count != 2 {
             return false;
         }
-        s += haipai[i];
+        s += count;
     }
     s == 14
 }
 
-pub fn check13(haipai: &Vec<i8>) -> bool {
-    let arr = kokushi_idx().map(|i| haipai[i as usize - 1]).to_vec();
-    !arr.contains(&0) && sum(&arr) == 14
+pub fn check13(hand: &Hand34) -> bool {
+    let counts = kokushi_idx().map(|i| hand.get(i as usize - 1));
+    !counts.contains(&0) && counts.iter().sum::<i8>() == 14
 }
 
-fn check_internal(haipai: &Vec<i8>, is_jihai: bool) -> bool {
-    let mut tmp = haipai.clone();
-    let haipai_c: &mut Vec<i8> = tmp.as_mut();
-    let s = sum(haipai_c);
+fn check_internal(mut suit: [i8; 9], is_jihai: bool) -> bool {
+    let s: i8 = suit.iter().sum();
     if s == 0 {
         return true;
     }
 
     if s % 3 == 2 {
-        for i in 0..haipai_c.len() {
-            if haipai_c[i] >= 2 {
-                haipai_c[i] -= 2;
+        for i in 0..9 {
+            if suit[i] >= 2 {
+                suit[i] -= 2;
             } else {
                 continue;
             }
 
-            if !check_internal(haipai_c, is_jihai) {
-                haipai_c[i] += 2;
+            if !check_internal(suit, is_jihai) {
+                suit[i] += 2;
             } else {
                 return true;
             }
@@ -41,42 +42,42 @@ fn check_internal(haipai: &Vec<i8>, is_jihai: bool) -> bool {
         return false;
     }
 
-    for i in 0..haipai_c.len() {
-        if haipai_c[i] == 0 {
+    for i in 0..9 {
+        if suit[i] == 0 {
             continue;
         }
 
-        if haipai_c[i] == 3 {
-            haipai_c[i] = 0;
+        if suit[i] == 3 {
+            suit[i] = 0;
         } else {
             if is_jihai || i >= 7 {
                 return false;
             }
 
-            if haipai_c[i] == 4 {
-                haipai_c[i] -= 3;
+            if suit[i] == 4 {
+                suit[i] -= 3;
             }
 
-            haipai_c[i + 1] -= haipai_c[i];
-            haipai_c[i + 2] -= haipai_c[i];
+            suit[i + 1] -= suit[i];
+            suit[i + 2] -= suit[i];
 
-            if haipai_c[i + 1] < 0 || haipai_c[i + 2] < 0 {
+            if suit[i + 1] < 0 || suit[i + 2] < 0 {
                 return false;
             }
 
-            haipai_c[i] = 0;
+            suit[i] = 0;
         }
     }
 
     true
 }
 
-pub fn check(haipai: &Vec<i8>) -> bool {
+pub fn check(hand: &Hand34) -> bool {
     let mut j = 0;
 
-    for i in 0..3 {
-        // Summing by suit except honors
-        let slice_sum = sum(&Vec::from(&haipai[i * 9..(i + 1) * 9]));
+    // Summing by suit, honors included
+    for i in 0..4 {
+        let slice_sum = hand.suit_count(i);
 
         if slice_sum % 3 == 1 {
             return false;
@@ -85,53 +86,207 @@ pub fn check(haipai: &Vec<i8>) -> bool {
         j += if slice_sum % 3 == 2 { 1 } else { 0 };
     }
 
-    // Adding honors as well
-    let slice_sum = sum(&Vec::from(&haipai[3 * 9..3 * 9 + 7]));
+    j == 1
+        && check_internal(hand.suit(0), false)
+        && check_internal(hand.suit(1), false)
+        && check_internal(hand.suit(2), false)
+        && check_internal(hand.suit(3), true)
+}
 
-    if slice_sum % 3 == 1 {
-        return false;
+pub fn check_all(hand: &Hand34) -> bool {
+    check7(hand) || check13(hand) || check(hand)
+}
+
+// Finds tiles (not indices) which complete the hand.
+// Tiles already used four times in closed part and melds can't be waited on.
+pub fn find_waits(haipai: &[i8], furo: &[Vec<i8>]) -> Vec<i8> {
+    let mut hand = Hand34::from_counts(haipai);
+    let mut used = [0; 34];
+    used.copy_from_slice(haipai);
+    for meld in furo {
+        for tile in meld {
+            used[(tile.abs() - 1) as usize] += 1;
+        }
     }
 
-    j += if slice_sum % 3 == 2 { 1 } else { 0 };
+    let mut waits: Vec<i8> = Vec::new();
+    for (i, count) in used.iter().enumerate() {
+        if *count >= 4 {
+            continue;
+        }
+        hand.add(i, 1);
+        if check_all(&hand) {
+            waits.push(i as i8 + 1);
+        }
+        hand.add(i, -1);
+    }
+    waits
+}
 
-    let slices = slice_by_suit(haipai);
-    j == 1
-        && check_internal(&slices[0], false)
-        && check_internal(&slices[1], false)
-        && check_internal(&slices[2], false)
-        && check_internal(&slices[3], true)
+// Set of a hand decomposition with its first tile
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Set {
+    Pair(i8),
+    Kotsu(i8),
+    Shuntsu(i8),
+    // All tiles of a kokushi hand
+    Kokushi,
 }
 
-pub fn check_all(haipai: &Vec<i8>) -> bool {
-    check7(haipai) || check13(haipai) || check(haipai)
+// Hand decomposition without allocations: chiitoitsu has most sets
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Decomposition {
+    sets: [Set; 7],
+    len: usize,
 }
 
-// Finds indices in hand where kotsu is detected
+impl Decomposition {
+    fn new() -> Self {
+        Decomposition {
+            sets: [Set::Kokushi; 7],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, set: Set) {
+        self.sets[self.len] = set;
+        self.len += 1;
+    }
+
+    fn extend(&mut self, other: &Decomposition) {
+        for set in other.sets() {
+            self.push(*set);
+        }
+    }
+
+    pub(crate) fn sets(&self) -> &[Set] {
+        &self.sets[..self.len]
+    }
+
+    // Same sets in any order
+    fn is_same(&self, other: &Decomposition) -> bool {
+        let mut sets = self.sets;
+        let mut other_sets = other.sets;
+        sets[..self.len].sort();
+        other_sets[..other.len].sort();
+        sets[..self.len] == other_sets[..other.len]
+    }
+
+    // Sets as lists of tiles, kokushi is taken from the hand
+    pub(crate) fn pattern(&self, hand: &Hand34) -> Pattern {
+        let mut pattern = Pattern::new();
+        for set in self.sets() {
+            pattern.push(match *set {
+                Set::Pair(tile) => SetTiles::new(&[tile, tile]),
+                Set::Kotsu(tile) => SetTiles::new(&[tile, tile, tile]),
+                Set::Shuntsu(tile) => SetTiles::new(&[tile, tile + 1, tile + 2]),
+                Set::Kokushi => {
+                    let mut tiles = SetTiles::new(&[]);
+                    for i in 0..34 {
+                        let count = hand.get(i);
+                        if count > 0 {
+                            tiles.push(i as i8 + 1);
+                            if count > 1 {
+                                tiles.push(i as i8 + 1);
+                            }
+                        }
+                    }
+                    tiles
+                }
+            });
+        }
+        pattern
+    }
+
+    pub(crate) fn tiles(&self, hand: &Hand34) -> Vec<Vec<i8>> {
+        self.pattern(hand).iter().map(|set| set.to_vec()).collect()
+    }
+}
+
+// Tiles of one set or meld, kokushi has all of its 14 tiles in one set
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SetTiles {
+    tiles: [i8; 14],
+    len: usize,
+}
+
+impl SetTiles {
+    pub(crate) fn new(tiles: &[i8]) -> Self {
+        let mut set = SetTiles {
+            tiles: [0; 14],
+            len: 0,
+        };
+        for tile in tiles {
+            set.push(*tile);
+        }
+        set
+    }
+
+    fn push(&mut self, tile: i8) {
+        self.tiles[self.len] = tile;
+        self.len += 1;
+    }
+}
+
+impl Deref for SetTiles {
+    type Target = [i8];
+
+    fn deref(&self) -> &[i8] {
+        &self.tiles[..self.len]
+    }
+}
+
+// Sets of a decomposition with melds, copied instead of allocated:
+// chiitoitsu has seven sets, other hands five with melds
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Pattern {
+    sets: [SetTiles; 7],
+    len: usize,
+}
+
+impl Pattern {
+    fn new() -> Self {
+        Pattern {
+            sets: [SetTiles::new(&[]); 7],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, set: SetTiles) {
+        self.sets[self.len] = set;
+        self.len += 1;
+    }
+}
+
+impl Deref for Pattern {
+    type Target = [SetTiles];
+
+    fn deref(&self) -> &[SetTiles] {
+        &self.sets[..self.len]
+    }
+}
+
+// Finds kotsu in hand and adds them to decomposition
 // Doesn't find kantsu.
-// Mutates original array!
-pub fn find_kotsu(haipai: &mut Vec<i8>) -> Vec<Vec<i8>> {
-    let mut res: Vec<Vec<i8>> = Vec::new();
-    for i in 0..haipai.len() {
-        if haipai[i] >= 3 {
-            haipai[i] -= 3;
-            if check(haipai) {
-                res.push(vec![i as i8 + 1, i as i8 + 1, i as i8 + 1]);
+// Mutates original hand!
+pub(crate) fn find_kotsu(hand: &mut Hand34, res: &mut Decomposition) {
+    for i in 0..34 {
+        if hand.get(i) >= 3 {
+            hand.add(i, -3);
+            if check(hand) {
+                res.push(Set::Kotsu(i as i8 + 1));
             } else {
-                haipai[i] += 3;
+                hand.add(i, 3);
             }
         }
     }
-
-    res
 }
 
-// Finds arrays of indices in hand where shuntsu is detected
-// Mutates original array!
-pub fn find_shuntsu(haipai: &mut Vec<i8>) -> Vec<Vec<i8>> {
-    let mut res: Vec<Vec<i8>> = Vec::new();
-
+// Finds shuntsu in hand and adds them to decomposition
+// Mutates original hand!
+pub(crate) fn find_shuntsu(hand: &mut Hand34, res: &mut Decomposition) {
     // Don't consider honors (last 7).
-    for i in 0..haipai.len() - 7 {
+    for i in 0..27 {
         // Also skip last two suit tiles, because there can't be any shuntsu starting from 8 or 9.
         if i + 1 == Suit::Man as usize * 9 + Val::N8 as usize
             || i + 1 == Suit::Man as usize * 9 + Val::N9 as usize
@@ -143,96 +298,88 @@ pub fn find_shuntsu(haipai: &mut Vec<i8>) -> Vec<Vec<i8>> {
             continue;
         }
 
-        while haipai[i] >= 1 && haipai[i + 1] >= 1 && haipai[i + 2] >= 1 {
-            haipai[i] -= 1;
-            haipai[i + 1] -= 1;
-            haipai[i + 2] -= 1;
+        while hand.get(i) >= 1 && hand.get(i + 1) >= 1 && hand.get(i + 2) >= 1 {
+            hand.add(i, -1);
+            hand.add(i + 1, -1);
+            hand.add(i + 2, -1);
 
-            if check(haipai) {
-                res.push(vec![i as i8 + 1, (i + 1) as i8 + 1, (i + 2) as i8 + 1]);
+            if check(hand) {
+                res.push(Set::Shuntsu(i as i8 + 1));
             } else {
-                haipai[i] += 1;
-                haipai[i + 1] += 1;
-                haipai[i + 2] += 1;
+                hand.add(i, 1);
+                hand.add(i + 1, 1);
+                hand.add(i + 2, 1);
                 break;
             }
         }
     }
-
-    res
 }
 
 // Finds index of first set of repeated tiles or -1 otherwise
 // Skip excluded index - it's used below as fake pair
-pub fn find_janto(haipai: &Vec<i8>, exclude: i8) -> i8 {
-    for i in 0..haipai.len() {
-        if haipai[i] >= 2 && i as i8 != exclude {
+pub fn find_janto(hand: &Hand34, exclude: i8) -> i8 {
+    for i in 0..34 {
+        if hand.get(i) >= 2 && i as i8 != exclude {
             return i as i8;
         }
     }
     -1
 }
 
-// Find hand split variant
+// Find hand split variants, at most two
 // Skip excluded index - it's used below as fake pair
-pub fn calc(haipai: &Vec<i8>, exclude: i8, real_pair: i8) -> Vec<Vec<Vec<i8>>> {
-    let mut res: Vec<Vec<Vec<i8>>> = Vec::new();
+fn calc(hand: &Hand34, exclude: i8, real_pair: i8, res: &mut Vec<Decomposition>) {
+    let janto = |clone: &Hand34| {
+        if real_pair != -1 {
+            real_pair
+        } else {
+            find_janto(clone, exclude)
+        }
+    };
 
     // First pass: find kotsu, then shuntsu
-    let mut clone = haipai.clone();
-    let mut kotsu = find_kotsu(clone.as_mut());
-    if sum(&clone) == 2 {
+    let mut clone = *hand;
+    let mut kotsu = Decomposition::new();
+    find_kotsu(&mut clone, &mut kotsu);
+    if clone.count() == 2 {
         // toitoi-like
-        let janto = if real_pair != -1 {
-            real_pair
-        } else {
-            find_janto(&clone, exclude)
-        };
-        kotsu.extend_from_slice(&[vec![janto + 1, janto + 1]]);
+        kotsu.push(Set::Pair(janto(&clone) + 1));
         res.push(kotsu);
-    } else if kotsu.len() > 0 {
-        let shuntsu = find_shuntsu(clone.as_mut());
-        let janto = if real_pair != -1 {
-            real_pair
-        } else {
-            find_janto(&clone, exclude)
-        };
-        kotsu.extend_from_slice(&shuntsu);
-        kotsu.extend_from_slice(&[vec![janto + 1, janto + 1]]);
+    } else if kotsu.len > 0 {
+        find_shuntsu(&mut clone, &mut kotsu);
+        kotsu.push(Set::Pair(janto(&clone) + 1));
         res.push(kotsu);
     }
 
     // Second pass: find shuntsu, then kotsu
-    clone = haipai.clone();
-    let mut shuntsu = find_shuntsu(clone.as_mut());
-    if sum(&clone) == 2 {
-        let janto = if real_pair != -1 {
-            real_pair
-        } else {
-            find_janto(&clone, exclude)
-        };
+    clone = *hand;
+    let mut shuntsu = Decomposition::new();
+    find_shuntsu(&mut clone, &mut shuntsu);
+    if clone.count() == 2 {
         // pinfu-like
-        shuntsu.extend_from_slice(&[vec![janto + 1, janto + 1]]);
+        shuntsu.push(Set::Pair(janto(&clone) + 1));
         res.push(shuntsu);
     } else {
-        let kotsu = find_kotsu(clone.as_mut());
-        let janto = if real_pair != -1 {
-            real_pair
-        } else {
-            find_janto(&clone, exclude)
-        };
-        shuntsu.extend_from_slice(&kotsu);
-        shuntsu.extend_from_slice(&[vec![janto + 1, janto + 1]]);
+        let mut kotsu = Decomposition::new();
+        find_kotsu(&mut clone, &mut kotsu);
+        shuntsu.extend(&kotsu);
+        shuntsu.push(Set::Pair(janto(&clone) + 1));
         res.push(shuntsu);
     }
-
-    res
 }
 
 pub fn find_all_agari_patterns(haipai: &Vec<i8>) -> Vec<Vec<Vec<i8>>> {
-    let mut res: Vec<Vec<Vec<i8>>> = Vec::new();
+    let hand = Hand34::from_counts(haipai);
+    agari_patterns(&hand)
+        .iter()
+        .map(|decomposition| decomposition.tiles(&hand))
+        .collect()
+}
 
-    let mut clone = haipai.clone();
+pub(crate) fn agari_patterns(hand: &Hand34) -> Vec<Decomposition> {
+    let mut res: Vec<Decomposition> = Vec::new();
+
+    let mut clone = *hand;
 
     let can_be_kokushi = check13(&clone);
     let can_be_chiitoitsu = check7(&clone);
@@ -243,36 +390,31 @@ pub fn find_all_agari_patterns(haipai: &Vec<i8>) -> Vec<Vec<Vec<i8>>> {
     }
 
     // only a pair left in closed part -> try to detect and return it
-    if sum(&clone) == 2 {
+    if clone.count() == 2 {
         let found = find_janto(&clone, -1);
         if found != -1 {
-            res.push(vec![vec![found + 1, found + 1]]);
+            let mut pair = Decomposition::new();
+            pair.push(Set::Pair(found + 1));
+            res.push(pair);
         }
         return res;
     }
 
     // Check kokushi separately
     if can_be_kokushi {
-        let mut vals: Vec<i8> = Vec::new();
-        for i in 0..clone.len() {
-            if clone[i] > 0 {
-                vals.push(i as i8 + 1);
-                if clone[i] > 1 {
-                    vals.push(i as i8 + 1);
-                }
-            }
-        }
-        res.push(vec![vals]);
+        let mut kokushi = Decomposition::new();
+        kokushi.push(Set::Kokushi);
+        res.push(kokushi);
     }
 
     // Some questionable code below :)
 
     let mut fake_pair_index = -1;
-    for i in Suit::Honor as i8 * 9..34 {
-        if clone[i as usize] == 0 {
+    for i in Suit::Honor as usize * 9..34 {
+        if clone.get(i) == 0 {
             // found first honor tile that is absent in hand
-            clone[i as usize] += 2; // add two fake tiles there so calc() would think the hand is valid when another pair is excluded.
-            fake_pair_index = i; // save fake pair index to avoid processing it below
+            clone.add(i, 2); // add two fake tiles there so calc() would think the hand is valid when another pair is excluded.
+            fake_pair_index = i as i8; // save fake pair index to avoid processing it below
             break;
         }
     }
@@ -281,49 +423,41 @@ pub fn find_all_agari_patterns(haipai: &Vec<i8>) -> Vec<Vec<Vec<i8>>> {
     // If hand is still valid, this means we found another proper valid hand decomposition.
     // Fake pair added above is required to keep proper tiles count in hand.
 
-    for i in 0..clone.len() {
+    for i in 0..34 {
         if i as i8 == fake_pair_index {
             // Don't process fake pair
             continue;
         }
 
-        if clone[i] >= 2 {
-            clone[i] -= 2;
+        if clone.get(i) >= 2 {
+            clone.add(i, -2);
             if check(&clone) {
-                let calc_res = calc(&clone, fake_pair_index, i as i8);
-                res.extend_from_slice(&calc_res);
+                calc(&clone, fake_pair_index, i as i8, &mut res);
             }
-            clone[i] += 2;
+            clone.add(i, 2);
         }
     }
 
     if fake_pair_index != -1 {
-        clone[fake_pair_index as usize] -= 2;
+        clone.add(fake_pair_index as usize, -2);
     }
 
     if can_be_chiitoitsu {
-        let mut vals: Vec<Vec<i8>> = Vec::new();
-        for i in 0..clone.len() {
-            if clone[i] == 2 {
-                vals.push(vec![i as i8 + 1, i as i8 + 1]);
+        let mut pairs = Decomposition::new();
+        for i in 0..34 {
+            if clone.get(i) == 2 {
+                pairs.push(Set::Pair(i as i8 + 1));
             }
         }
-        res.push(vals);
+        res.push(pairs);
     }
 
     // Finally we try to find and eliminate duplicate decompositions.
-    let mut final_res: Vec<Vec<Vec<i8>>> = Vec::new();
-
-    for i in 0..res.len() {
-        let mut is_duplicate = false;
-        let v_digest = digest(&res[i]);
-        for ii in 0..final_res.len() {
-            if v_digest == digest(&final_res[ii]) {
-                is_duplicate = true;
-            }
-        }
-        if !is_duplicate {
-            final_res.push(res[i].clone());
+    let mut final_res: Vec<Decomposition> = Vec::new();
+
+    for decomposition in res {
+        if !final_res.iter().any(|other| decomposition.is_same(other)) {
+            final_res.push(decomposition);
         }
     }
 
@@ -334,6 +468,30 @@ pub fn find_all_agari_patterns(haipai: &Vec<i8>) -> Vec<Vec<Vec<i8>>> {
 mod tests {
     use super::*;
 
+    fn find_sets(counts: &[i8], find: fn(&mut Hand34, &mut Decomposition)) -> Vec<Vec<i8>> {
+        let mut hand = Hand34::from_counts(counts);
+        let mut res = Decomposition::new();
+        find(&mut hand, &mut res);
+        res.tiles(&hand)
+    }
+
+    fn digest(decomposition: &Vec<Vec<i8>>) -> String {
+        let mut arr = decomposition
+            .iter()
+            .map(|set| {
+                format!(
+                    "|{}|",
+                    set.iter()
+                        .map(|val| val.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",")
+                )
+            })
+            .collect::<Vec<String>>();
+        arr.sort();
+        arr.join("#")
+    }
+
     fn digest_all(decompositions: Vec<Vec<Vec<i8>>>) -> String {
         decompositions
             .iter()
@@ -345,32 +503,32 @@ mod tests {
     #[test]
     pub fn test_kokushimusou() {
         assert_eq!(
-            check13(&vec![
+            check13(&Hand34::from_counts(&[
                 1, 0, 0, 0, 0, 0, 0, 0, 1, //
                 1, 0, 0, 0, 0, 0, 0, 0, 1, //
                 1, 0, 0, 0, 0, 0, 0, 0, 1, //
                 1, 1, 2, 1, 1, 1, 1
-            ]),
+            ])),
             true
         );
 
         assert_eq!(
-            check13(&vec![
+            check13(&Hand34::from_counts(&[
                 1, 0, 0, 0, 0, 0, 0, 0, 1, //
                 1, 0, 1, 0, 0, 0, 0, 0, 1, //
                 1, 0, 0, 0, 0, 0, 0, 0, 1, //
                 1, 1, 1, 1, 1, 1, 1
-            ]),
+            ])),
             false
         );
 
         assert_eq!(
-            check13(&vec![
+            check13(&Hand34::from_counts(&[
                 0, 0, 2, 0, 0, 0, 0, 0, 0, //
                 0, 0, 0, 0, 2, 0, 2, 0, 0, //
                 2, 0, 0, 0, 0, 2, 0, 0, 0, //
                 0, 0, 2, 0, 2, 0, 0
-            ]),
+            ])),
             false
         );
     }
@@ -378,22 +536,22 @@ mod tests {
     #[test]
     pub fn test_chiitoitsu() {
         assert_eq!(
-            check7(&vec![
+            check7(&Hand34::from_counts(&[
                 0, 0, 2, 0, 0, 0, 0, 0, 0, //
                 0, 0, 0, 0, 2, 0, 2, 0, 0, //
                 2, 0, 0, 0, 0, 2, 0, 0, 0, //
                 0, 0, 2, 0, 2, 0, 0
-            ]),
+            ])),
             true
         );
 
         assert_eq!(
-            check7(&vec![
+            check7(&Hand34::from_counts(&[
                 0, 0, 2, 0, 0, 0, 0, 0, 0, //
                 0, 0, 0, 0, 2, 0, 2, 0, 0, //
                 1, 1, 0, 0, 0, 2, 0, 0, 0, //
                 0, 0, 2, 0, 2, 0, 0
-            ]),
+            ])),
             false
         );
     }
@@ -427,30 +585,30 @@ mod tests {
             ],
         ];
 
-        assert_eq!(check(&hands[0]), false);
-        assert_eq!(check(&hands[1]), false);
-        assert_eq!(check(&hands[2]), true);
-        assert_eq!(check(&hands[3]), true);
-
-        assert_eq!(check7(&hands[0]), true);
-        assert_eq!(check7(&hands[1]), false);
-        assert_eq!(check7(&hands[2]), false);
-        assert_eq!(check7(&hands[3]), true);
-
-        assert_eq!(check13(&hands[0]), false);
-        assert_eq!(check13(&hands[1]), true);
-        assert_eq!(check13(&hands[2]), false);
-        assert_eq!(check13(&hands[3]), false);
-
-        assert_eq!(check_all(&hands[0]), true);
-        assert_eq!(check_all(&hands[1]), true);
-        assert_eq!(check_all(&hands[2]), true);
-        assert_eq!(check_all(&hands[3]), true);
+        assert_eq!(check(&Hand34::from_counts(&hands[0])), false);
+        assert_eq!(check(&Hand34::from_counts(&hands[1])), false);
+        assert_eq!(check(&Hand34::from_counts(&hands[2])), true);
+        assert_eq!(check(&Hand34::from_counts(&hands[3])), true);
+
+        assert_eq!(check7(&Hand34::from_counts(&hands[0])), true);
+        assert_eq!(check7(&Hand34::from_counts(&hands[1])), false);
+        assert_eq!(check7(&Hand34::from_counts(&hands[2])), false);
+        assert_eq!(check7(&Hand34::from_counts(&hands[3])), true);
+
+        assert_eq!(check13(&Hand34::from_counts(&hands[0])), false);
+        assert_eq!(check13(&Hand34::from_counts(&hands[1])), true);
+        assert_eq!(check13(&Hand34::from_counts(&hands[2])), false);
+        assert_eq!(check13(&Hand34::from_counts(&hands[3])), false);
+
+        assert_eq!(check_all(&Hand34::from_counts(&hands[0])), true);
+        assert_eq!(check_all(&Hand34::from_counts(&hands[1])), true);
+        assert_eq!(check_all(&Hand34::from_counts(&hands[2])), true);
+        assert_eq!(check_all(&Hand34::from_counts(&hands[3])), true);
     }
 
     #[test]
     pub fn test_find_kotsu() {
-        let mut hand = vec![
+        let hand = vec![
             0, 0, 0, 0, 3, 0, 0, 0, 0, //
             0, 0, 0, 3, 0, 0, 0, 2, 0, //
             0, 0, 3, 0, 0, 0, 0, 0, 0, //
@@ -462,12 +620,12 @@ mod tests {
             vec![21, 21, 21], //
             vec![29, 29, 29],
         ];
-        assert_eq!(find_kotsu(hand.as_mut()), expected);
+        assert_eq!(find_sets(&hand, find_kotsu), expected);
     }
 
     #[test]
     pub fn test_find_shuntsu() {
-        let mut hands = [
+        let hands = [
             vec![
                 1, 1, 1, 0, 0, 0, 1, 1, 1, //
                 0, 0, 0, 1, 1, 1, 0, 2, 0, //
@@ -511,10 +669,10 @@ mod tests {
             vec![vec![21, 22, 23]],
         ];
 
-        assert_eq!(find_shuntsu(hands[0].as_mut()), expected[0]);
-        assert_eq!(find_shuntsu(hands[1].as_mut()), expected[1]);
-        assert_eq!(find_shuntsu(hands[2].as_mut()), expected[2]);
-        assert_eq!(find_shuntsu(hands[3].as_mut()), expected[3]);
+        assert_eq!(find_sets(&hands[0], find_shuntsu), expected[0]);
+        assert_eq!(find_sets(&hands[1], find_shuntsu), expected[1]);
+        assert_eq!(find_sets(&hands[2], find_shuntsu), expected[2]);
+        assert_eq!(find_sets(&hands[3], find_shuntsu), expected[3]);
     }
 
     #[test]
@@ -534,8 +692,8 @@ mod tests {
             ],
         ];
 
-        assert_eq!(find_janto(&hands[0], -1), 16);
-        assert_eq!(find_janto(&hands[1], -1), -1);
+        assert_eq!(find_janto(&Hand34::from_counts(&hands[0]), -1), 16);
+        assert_eq!(find_janto(&Hand34::from_counts(&hands[1]), -1), -1);
     }
 
     #[test]
@@ -0,0 +1,144 @@
+use crate::agari::{find_all_agari_patterns, find_waits};
+use crate::constants::tiles_to_34;
+use crate::interfaces::{RiichiAnkanResult, RiichiHand};
+use crate::riichi::split_hand;
+
+// Check if closed kan on the tile just drawn is permitted after riichi.
+// Hand is the riichi hand before the draw.
+pub fn check_riichi_ankan(hand: &RiichiHand, tile: i8) -> Result<RiichiAnkanResult, String> {
+    let (haipai, mut furo) = split_hand(hand);
+
+    if haipai.len() % 3 != 1 || haipai.len() + furo.len() * 3 > 13 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+
+    let mut haipai34 = tiles_to_34(&haipai);
+    if haipai34[(tile - 1) as usize] != 3 {
+        return Err("Ankan is not possible".parse().unwrap());
+    }
+
+    let waits = find_waits(&haipai34, &furo);
+    if waits.is_empty() {
+        return Err("Hand is not tenpai".parse().unwrap());
+    }
+
+    // Strict rule: kan tile is a kotsu in every decomposition with every wait
+    let kotsu = vec![tile, tile, tile];
+    let same_decomposition = waits.iter().all(|w| {
+        haipai34[(w - 1) as usize] += 1;
+        let patterns = find_all_agari_patterns(&haipai34);
+        haipai34[(w - 1) as usize] -= 1;
+        patterns.iter().all(|p| p.contains(&kotsu))
+    });
+
+    haipai34[(tile - 1) as usize] = 0;
+    furo.push(vec![-tile, -tile, -tile, -tile]);
+    let waits_after_kan = find_waits(&haipai34, &furo);
+
+    Ok(RiichiAnkanResult {
+        lenient: waits == waits_after_kan,
+        strict: waits == waits_after_kan && same_decomposition,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn closed_hand(tiles: Vec<Tiles>) -> RiichiHand {
+        RiichiHand {
+            closed_part: tiles.into_iter().map(|t| t as i8).collect(),
+            open_part: vec![],
+        }
+    }
+
+    #[test]
+    pub fn should_allow_ankan_on_kotsu() {
+        let hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M5,
+            Tiles::M6,
+            Tiles::M7,
+            Tiles::P1,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::S2,
+            Tiles::S3,
+            Tiles::E,
+            Tiles::E,
+        ]);
+        let res = check_riichi_ankan(&hand, Tiles::M1 as i8).unwrap();
+        assert!(res.lenient);
+        assert!(res.strict);
+    }
+
+    #[test]
+    pub fn should_forbid_ankan_changing_waits() {
+        // 1112m waits on 2m and 3m, after kan it waits on 2m only
+        let hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M2,
+            Tiles::P1,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::P4,
+            Tiles::P5,
+            Tiles::P6,
+            Tiles::P7,
+            Tiles::P8,
+            Tiles::P9,
+        ]);
+        let res = check_riichi_ankan(&hand, Tiles::M1 as i8).unwrap();
+        assert!(!res.lenient);
+        assert!(!res.strict);
+    }
+
+    #[test]
+    pub fn should_allow_ankan_changing_decomposition_in_lenient_mode() {
+        // 1112444m waits on 2m and 3m both before and after kan of 4m,
+        // but 3m can be taken as 111m + 234m + 44m
+        let hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M2,
+            Tiles::M4,
+            Tiles::M4,
+            Tiles::M4,
+            Tiles::P1,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::S1,
+            Tiles::S2,
+            Tiles::S3,
+        ]);
+        let res = check_riichi_ankan(&hand, Tiles::M4 as i8).unwrap();
+        assert!(res.lenient);
+        assert!(!res.strict);
+    }
+
+    #[test]
+    pub fn should_fail_without_three_tiles_in_hand() {
+        let hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M5,
+            Tiles::M6,
+            Tiles::M7,
+            Tiles::P1,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::S2,
+            Tiles::S3,
+            Tiles::E,
+            Tiles::E,
+        ]);
+        assert!(check_riichi_ankan(&hand, Tiles::E as i8).is_err());
+    }
+}
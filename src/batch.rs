@@ -0,0 +1,166 @@
+use crate::interfaces::{RiichiHand, RiichiOptions, RiichiResult, WinKind};
+use crate::riichi::calc_win;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// Scores every (hand, options, win) item, results go in the same order as the input.
+// With the `rayon` feature hands are scored on all cores, results are the same as in single thread.
+pub fn calc_riichi_batch(
+    hands: &[(RiichiHand, RiichiOptions, WinKind)],
+    calc_hairi: bool,
+) -> Vec<Result<RiichiResult, String>> {
+    #[cfg(feature = "rayon")]
+    let iter = hands.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let iter = hands.iter();
+
+    iter.map(|(hand, options, win)| calc_win(hand, options, *win, calc_hairi))
+        .collect()
+}
+
+// Same for any iterator of items, e.g. lines of a log being read. Scored lazily in single thread,
+// use calc_riichi_batch on chunks of input for multi-threading.
+pub fn calc_riichi_iter<'a, I>(
+    hands: I,
+    calc_hairi: bool,
+) -> impl Iterator<Item = Result<RiichiResult, String>> + 'a
+where
+    I: IntoIterator<Item = &'a (RiichiHand, RiichiOptions, WinKind)>,
+    I::IntoIter: 'a,
+{
+    hands
+        .into_iter()
+        .map(move |(hand, options, win)| calc_win(hand, options, *win, calc_hairi))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn options() -> RiichiOptions {
+        RiichiOptions {
+            dora: vec![Tiles::M2 as i8],
+            riichi: true,
+            allow_aka: true,
+            ..RiichiOptions::for_tests()
+        }
+    }
+
+    fn ron(tile: Tiles) -> WinKind {
+        WinKind::Ron {
+            tile: tile as i8,
+            from_seat: 2,
+        }
+    }
+
+    fn hands() -> Vec<(RiichiHand, RiichiOptions, WinKind)> {
+        let pinfu = vec![
+            Tiles::M1,
+            Tiles::M2,
+            Tiles::M3,
+            Tiles::P4,
+            Tiles::P5,
+            Tiles::P6,
+            Tiles::S7,
+            Tiles::S8,
+            Tiles::S9,
+            Tiles::S2,
+            Tiles::S2,
+            Tiles::M6,
+            Tiles::M7,
+        ];
+        let mut tsumo = pinfu.clone();
+        tsumo.push(Tiles::M8);
+        let mut noten = pinfu.clone();
+        noten.push(Tiles::E);
+
+        // Same options for all hands: they are not changed by scoring
+        let options = options();
+        vec![
+            (
+                RiichiHand::for_tests(pinfu.clone()),
+                options.clone(),
+                ron(Tiles::M5),
+            ),
+            (
+                RiichiHand::for_tests(tsumo.clone()),
+                options.clone(),
+                WinKind::Tsumo(Tiles::M8 as i8),
+            ),
+            (
+                RiichiHand::for_tests(pinfu[..11].to_vec()),
+                options.clone(),
+                ron(Tiles::M8),
+            ),
+            (
+                RiichiHand::for_tests(noten),
+                options.clone(),
+                WinKind::Tsumo(Tiles::E as i8),
+            ),
+            (
+                RiichiHand::for_tests(pinfu),
+                options.clone(),
+                ron(Tiles::M8),
+            ),
+            (
+                RiichiHand::for_tests(tsumo),
+                options,
+                WinKind::Tsumo(Tiles::E as i8),
+            ),
+        ]
+    }
+
+    #[test]
+    pub fn batch_keeps_order_and_errors() {
+        let hands = hands();
+        let results = calc_riichi_batch(&hands, false);
+        assert_eq!(results.len(), hands.len());
+
+        for ((hand, options, win), result) in hands.iter().zip(&results) {
+            let expected = calc_win(hand, options, *win, false);
+            match (result, expected) {
+                (Ok(result), Ok(expected)) => {
+                    assert_eq!(result.han, expected.han);
+                    assert_eq!(result.fu, expected.fu);
+                    assert_eq!(result.ten, expected.ten);
+                    assert_eq!(result.yaku, expected.yaku);
+                }
+                (Err(error), Err(expected)) => assert_eq!(*error, expected),
+                _ => panic!("Batch result differs from calc_win"),
+            }
+        }
+
+        assert_eq!(results[0].as_ref().unwrap().han, 3);
+        assert_eq!(results[1].as_ref().unwrap().han, 4);
+        assert_eq!(
+            results[2].as_ref().err().unwrap(),
+            "Incorrect number of tiles"
+        );
+        assert!(!results[3].as_ref().unwrap().is_agari);
+        assert_eq!(results[4].as_ref().unwrap().han, 3);
+        assert_eq!(
+            results[5].as_ref().err().unwrap(),
+            "Winning tile is not in hand"
+        );
+    }
+
+    #[test]
+    pub fn iter_matches_batch() {
+        let hands = hands();
+        let batch = calc_riichi_batch(&hands, true);
+        let iter = calc_riichi_iter(&hands, true).collect::<Vec<_>>();
+        for (a, b) in batch.iter().zip(&iter) {
+            match (a, b) {
+                (Ok(a), Ok(b)) => {
+                    assert_eq!(a.ten, b.ten);
+                    assert_eq!(a.yaku, b.yaku);
+                    assert_eq!(a.hairi.is_some(), b.hairi.is_some());
+                }
+                (Err(a), Err(b)) => assert_eq!(a, b),
+                _ => panic!("Iterator result differs from batch"),
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use riichi_rust::verify_dir;
+use std::path::Path;
+use std::{env, fs, process};
+
+// Score every winning hand from local game logs and compare with recorded results:
+// replay <logs directory> [report.json]
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <logs directory> [report.json]", args[0]);
+        process::exit(2);
+    }
+
+    let report = match verify_dir(Path::new(&args[1])) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    for (source, error) in &report.errors {
+        eprintln!("{}: {}", source, error);
+    }
+    for mismatch in &report.mismatches {
+        println!(
+            "{} round {} agari {}: {}",
+            mismatch.source,
+            mismatch.round,
+            mismatch.agari,
+            mismatch.differences.join(", ")
+        );
+    }
+    println!(
+        "{} logs, {} hands, {} mismatches, {} logs not parsed",
+        report.files,
+        report.hands,
+        report.mismatches.len(),
+        report.errors.len()
+    );
+
+    if let Some(output) = args.get(2) {
+        let json = serde_json::to_string_pretty(&report).unwrap();
+        if let Err(e) = fs::write(output, json) {
+            eprintln!("{}: {}", output, e);
+            process::exit(2);
+        }
+    }
+    if !report.mismatches.is_empty() {
+        process::exit(1);
+    }
+}
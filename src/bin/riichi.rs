@@ -0,0 +1,289 @@
+use riichi_rust::{
+    RiichiHand, RiichiOptions, RiichiResult, UkeireResult, WinKind, Yaku, calc_riichi_batch,
+    calc_shanten, calc_ukeire, calc_win, dora_from_indicator, format_mpsz, parse_mpsz,
+    rank_discards,
+};
+use std::io::{BufRead, BufReader};
+use std::{env, fs, io, process};
+
+// Lines scored at once by batch command
+const BATCH_SIZE: usize = 4096;
+
+static USAGE: &str = "Usage:
+  riichi score <hand> [options]   score a winning hand, the winning tile is the last one
+  riichi shanten <hand> [options] shanten and useful tiles, or the best discards for 14 tiles
+  riichi batch <file.jsonl | ->   score [RiichiHand, RiichiOptions, WinKind] items, one per line
+
+Hands are in MPSZ notation, 0 is a red five: 234m406p789s11z.
+
+Score options:
+  --meld <tiles>        open chi, pon or kan, may be repeated
+  --ankan <tile>        closed kan, may be repeated
+  --ron                 winning tile is discarded by someone, tsumo otherwise
+  --riichi, --double-riichi, --ippatsu
+  --first-take          tenhou, chihou or renhou
+  --last-tile           haitei or houtei
+  --after-kan           rinshan on tsumo, chankan on ron
+  --bakaze <E|S|W|N>    round wind, E by default
+  --jikaze <E|S|W|N>    own wind, E (dealer) by default
+  --dora <tiles>        dora indicators
+  --ura <tiles>         uradora indicators, counted in riichi only
+  --honba <n>
+  --kiriage             round 4 han 30 fu and 3 han 60 fu up to mangan
+  --no-kuitan           no open tanyao
+  --no-aka              red fives are not counted
+
+Shanten options:
+  --meld <tiles>        own meld, may be repeated
+  --visible <tiles>     discards, other melds and dora indicators: not counted as useful tiles left";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(2);
+}
+
+fn wind(name: &str) -> i8 {
+    match name {
+        "E" => 28,
+        "S" => 29,
+        "W" => 30,
+        "N" => 31,
+        _ => fail(&format!("Incorrect wind: {}", name)),
+    }
+}
+
+fn tiles(notation: &str) -> (Vec<i8>, i8) {
+    parse_mpsz(notation).unwrap_or_else(|e| fail(&e))
+}
+
+fn score(args: &[String]) {
+    let mut hand = RiichiHand {
+        closed_part: Vec::new(),
+        open_part: Vec::new(),
+    };
+    let mut options = RiichiOptions {
+        dora: vec![],
+        aka_count: 0,
+        first_take: false,
+        riichi: false,
+        ippatsu: false,
+        double_riichi: false,
+        open_riichi: false,
+        open_riichi_deal_in: false,
+        last_tile: false,
+        after_kan: false,
+        tile_discarded_by_someone: -1,
+        bakaze: 28,
+        jikaze: 28,
+        allow_aka: true,
+        allow_kuitan: true,
+        with_kiriage: false,
+        disabled_yaku: vec![],
+        local_yaku_enabled: vec![],
+        all_local_yaku_enabled: false,
+        allow_double_yakuman: false,
+    };
+    let mut ron = false;
+    let mut ura: Vec<i8> = Vec::new();
+    let mut honba = 0;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| fail(&format!("No value for {}", arg)))
+                .clone()
+        };
+        match arg.as_str() {
+            "--meld" | "--ankan" => {
+                let (mut meld, aka) = tiles(&value());
+                if arg == "--ankan" {
+                    meld = vec![meld[0]; 4];
+                }
+                options.aka_count += aka;
+                hand.open_part.push((arg == "--meld", meld));
+            }
+            "--ron" => ron = true,
+            "--riichi" => options.riichi = true,
+            "--double-riichi" => options.double_riichi = true,
+            "--ippatsu" => options.ippatsu = true,
+            "--first-take" => options.first_take = true,
+            "--last-tile" => options.last_tile = true,
+            "--after-kan" => options.after_kan = true,
+            "--bakaze" => options.bakaze = wind(&value()),
+            "--jikaze" => options.jikaze = wind(&value()),
+            "--dora" => options
+                .dora
+                .extend(tiles(&value()).0.into_iter().map(dora_from_indicator)),
+            "--ura" => ura.extend(tiles(&value()).0.into_iter().map(dora_from_indicator)),
+            "--honba" => honba = value().parse().unwrap_or_else(|_| fail("Incorrect honba")),
+            "--kiriage" => options.with_kiriage = true,
+            "--no-kuitan" => options.allow_kuitan = false,
+            "--no-aka" => options.allow_aka = false,
+            _ if arg.starts_with("--") => fail(&format!("Unknown option: {}", arg)),
+            _ => {
+                let (closed, aka) = tiles(arg);
+                hand.closed_part.extend(closed);
+                options.aka_count += aka;
+            }
+        }
+    }
+
+    if hand.closed_part.is_empty() {
+        fail(USAGE);
+    }
+    if options.riichi || options.double_riichi {
+        options.dora.extend(ura);
+    }
+    let tile = *hand.closed_part.last().unwrap();
+    let win = if ron {
+        hand.closed_part.pop();
+        // Discarder doesn't change the value of ron
+        WinKind::Ron { tile, from_seat: 3 }
+    } else {
+        WinKind::Tsumo(tile)
+    };
+
+    match calc_win(&hand, &options, win, false) {
+        Ok(result) if result.is_agari => print_result(&result, options.jikaze == 28, ron, honba),
+        Ok(_) => fail("Hand is not complete"),
+        Err(e) => fail(&e),
+    }
+}
+
+fn print_result(result: &RiichiResult, dealer: bool, ron: bool, honba: i32) {
+    for (id, han) in &result.yaku {
+        let name = Yaku::try_from(*id).map_or("?", |yaku| yaku.name());
+        if *han >= 13 {
+            println!("{:<28} yakuman", name);
+        } else {
+            println!("{:<28} {}", name, han);
+        }
+    }
+    if result.yakuman > 0 {
+        println!("Yakuman x{}", result.yakuman);
+    } else {
+        println!("{} han {} fu", result.han, result.fu);
+    }
+
+    match result.outgoing_ten {
+        Some((oya, _)) if !ron && dealer => {
+            let each = oya + honba * 100;
+            println!("Tsumo: {} all, {} total", each, each * 3);
+        }
+        Some((oya, ko)) if !ron => {
+            let (oya, ko) = (oya + honba * 100, ko + honba * 100);
+            println!("Tsumo: {} / {}, {} total", ko, oya, oya + ko * 2);
+        }
+        _ => println!("Ron: {}", result.ten + honba * 300),
+    }
+}
+
+fn shanten(args: &[String]) {
+    let mut hand = RiichiHand {
+        closed_part: Vec::new(),
+        open_part: Vec::new(),
+    };
+    let mut visible: Vec<i8> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| fail(&format!("No value for {}", arg)))
+                .clone()
+        };
+        match arg.as_str() {
+            "--meld" => hand.open_part.push((true, tiles(&value()).0)),
+            "--visible" => visible.extend(tiles(&value()).0),
+            _ if arg.starts_with("--") => fail(&format!("Unknown option: {}", arg)),
+            _ => hand.closed_part.extend(tiles(arg).0),
+        }
+    }
+
+    let print_shanten = |shanten: i8| match shanten {
+        -1 => println!("Agari"),
+        0 => println!("Tenpai"),
+        _ => println!("Shanten: {}", shanten),
+    };
+    let useful = |result: &UkeireResult| -> String {
+        let tiles: Vec<i8> = result.tiles.iter().map(|t| t.tile).collect();
+        format!("{} ({})", format_mpsz(&tiles), result.total)
+    };
+
+    match hand.closed_part.len() % 3 {
+        1 => {
+            let result = calc_ukeire(&hand, &visible).unwrap_or_else(|e| fail(&e));
+            print_shanten(result.shanten);
+            println!("Useful tiles: {}", useful(&result));
+        }
+        2 => {
+            let mut haipai34 = vec![0; 34];
+            for tile in &hand.closed_part {
+                haipai34[(tile - 1) as usize] += 1;
+            }
+            print_shanten(calc_shanten(&haipai34));
+            let discards = rank_discards(&hand, &visible).unwrap_or_else(|e| fail(&e));
+            let best = discards.first().map_or(0, |d| d.result.shanten);
+            for discard in discards.iter().filter(|d| d.result.shanten == best) {
+                println!(
+                    "Discard {}: {}",
+                    format_mpsz(&[discard.discard]),
+                    useful(&discard.result)
+                );
+            }
+        }
+        _ => fail("Incorrect number of tiles"),
+    }
+}
+
+fn batch(args: &[String]) {
+    if args.len() != 1 {
+        fail(USAGE);
+    }
+    let input: Box<dyn BufRead> = if args[0] == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        match fs::File::open(&args[0]) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => fail(&format!("{}: {}", args[0], e)),
+        }
+    };
+
+    // Hands are scored in chunks, so with the rayon feature they go to all cores
+    let mut lines = input.lines().peekable();
+    while lines.peek().is_some() {
+        let parsed = lines
+            .by_ref()
+            .map(|line| line.unwrap_or_else(|e| fail(&e.to_string())))
+            .filter(|line| !line.trim().is_empty())
+            .take(BATCH_SIZE)
+            .map(|line| {
+                serde_json::from_str::<(RiichiHand, RiichiOptions, WinKind)>(&line)
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Vec<_>>();
+        let hands = parsed
+            .iter()
+            .filter_map(|item| item.as_ref().ok().cloned())
+            .collect::<Vec<_>>();
+        let mut results = calc_riichi_batch(&hands, false).into_iter();
+        for item in parsed {
+            let result = item.and_then(|_| results.next().unwrap());
+            let output = match result {
+                Ok(result) => serde_json::to_string(&result).unwrap(),
+                Err(e) => serde_json::json!({ "error": e }).to_string(),
+            };
+            println!("{}", output);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(|a| a.as_str()) {
+        Some("score") => score(&args[1..]),
+        Some("shanten") => shanten(&args[1..]),
+        Some("batch") => batch(&args[1..]),
+        _ => fail(USAGE),
+    }
+}
@@ -0,0 +1,328 @@
+use crate::abortive::is_kyuushu_kyuuhai;
+use crate::ankan::check_riichi_ankan;
+use crate::constants::tiles_to_34;
+use crate::interfaces::{CallOption, RiichiHand, RiichiOptions, WinKind};
+use crate::riichi::{calc_win, split_hand};
+
+fn in_riichi(options: &RiichiOptions) -> bool {
+    options.riichi || options.double_riichi
+}
+
+// Shapes of chi with the tile: tiles from hand which form a shuntsu with it
+fn chi_shapes(tile: i8) -> Vec<(i8, i8)> {
+    if tile > 27 {
+        return Vec::new();
+    }
+    let value = (tile - 1) % 9 + 1;
+    let mut shapes = Vec::new();
+    if value >= 3 {
+        shapes.push((tile - 2, tile - 1));
+    }
+    if (2..=8).contains(&value) {
+        shapes.push((tile - 1, tile + 1));
+    }
+    if value <= 7 {
+        shapes.push((tile + 1, tile + 2));
+    }
+    shapes
+}
+
+// List all legal actions on a tile discarded by another player.
+// Seat of the discarder is relative to the player: 1 - shimocha, 2 - toimen, 3 - kamicha.
+// Furiten is not checked here, see check_furiten for this.
+pub fn find_discard_calls(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    tile: i8,
+    from_seat: i8,
+) -> Result<Vec<CallOption>, String> {
+    let (haipai, furo) = split_hand(hand);
+    if haipai.len() + furo.len() * 3 != 13 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    if !(1..=3).contains(&from_seat) {
+        return Err("Incorrect seat".parse().unwrap());
+    }
+
+    let mut calls: Vec<CallOption> = Vec::new();
+
+    if let Ok(result) = calc_win(hand, options, WinKind::Ron { tile, from_seat }, false) {
+        if result.is_agari {
+            calls.push(CallOption::Ron(result));
+        }
+    }
+
+    // No calls during riichi and on the last discard
+    if in_riichi(options) || options.last_tile {
+        return Ok(calls);
+    }
+
+    let haipai34 = tiles_to_34(&haipai);
+    let count = |t: i8| haipai34[(t - 1) as usize];
+
+    if from_seat == 3 {
+        for (first, second) in chi_shapes(tile) {
+            if count(first) > 0 && count(second) > 0 {
+                let mut meld = vec![first, second, tile];
+                meld.sort();
+                calls.push(CallOption::Chi(meld));
+            }
+        }
+    }
+
+    if count(tile) >= 2 {
+        calls.push(CallOption::Pon(vec![tile, tile, tile]));
+    }
+
+    if count(tile) >= 3 {
+        calls.push(CallOption::Daiminkan(vec![tile, tile, tile, tile]));
+    }
+
+    Ok(calls)
+}
+
+// List all legal actions on own draw. The drawn tile is the last one in closed part.
+// Strict rule for ankan after riichi forbids kans changing hand decomposition.
+pub fn find_draw_actions(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    strict_ankan: bool,
+) -> Result<Vec<CallOption>, String> {
+    let (haipai, furo) = split_hand(hand);
+    if haipai.len() + furo.len() * 3 != 14 || hand.closed_part.is_empty() {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+
+    let mut actions: Vec<CallOption> = Vec::new();
+
+    let drawn = *hand.closed_part.last().unwrap();
+    if let Ok(result) = calc_win(hand, options, WinKind::Tsumo(drawn), false) {
+        if result.is_agari {
+            actions.push(CallOption::Tsumo(result));
+        }
+    }
+
+    let haipai34 = tiles_to_34(&haipai);
+
+    // No kans on the last tile of the wall
+    if !options.last_tile {
+        for (i, count) in haipai34.iter().enumerate() {
+            let tile = i as i8 + 1;
+            if *count < 4 {
+                continue;
+            }
+            if in_riichi(options) {
+                if tile != drawn {
+                    continue;
+                }
+                let mut before_draw = hand.clone();
+                before_draw.closed_part.pop();
+                match check_riichi_ankan(&before_draw, tile) {
+                    Ok(res) if (strict_ankan && res.strict) || (!strict_ankan && res.lenient) => {}
+                    _ => continue,
+                }
+            }
+            actions.push(CallOption::Ankan(tile));
+        }
+
+        if !in_riichi(options) {
+            for meld in &furo {
+                if meld.len() == 3
+                    && meld[0] == meld[1]
+                    && meld[0] > 0
+                    && haipai34[(meld[0] - 1) as usize] > 0
+                {
+                    actions.push(CallOption::Shouminkan(meld[0]));
+                }
+            }
+        }
+    }
+
+    if options.first_take && furo.is_empty() && is_kyuushu_kyuuhai(&haipai34) {
+        actions.push(CallOption::Kyuushu);
+    }
+
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{Tiles, Yaku};
+
+    fn options() -> RiichiOptions {
+        RiichiOptions::for_tests()
+    }
+
+    fn closed_hand(tiles: Vec<Tiles>) -> RiichiHand {
+        RiichiHand {
+            closed_part: tiles.into_iter().map(|t| t as i8).collect(),
+            open_part: vec![],
+        }
+    }
+
+    fn noten_hand() -> RiichiHand {
+        closed_hand(vec![
+            Tiles::M2,
+            Tiles::M3,
+            Tiles::M5,
+            Tiles::M5,
+            Tiles::M5,
+            Tiles::P4,
+            Tiles::P6,
+            Tiles::S7,
+            Tiles::S8,
+            Tiles::S9,
+            Tiles::E,
+            Tiles::E,
+            Tiles::N,
+        ])
+    }
+
+    fn tenpai_hand() -> RiichiHand {
+        // tanyao and pinfu, waits on 1p and 4p
+        closed_hand(vec![
+            Tiles::M2,
+            Tiles::M3,
+            Tiles::M4,
+            Tiles::M5,
+            Tiles::M6,
+            Tiles::M7,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::S5,
+            Tiles::S6,
+            Tiles::S7,
+            Tiles::S8,
+            Tiles::S8,
+        ])
+    }
+
+    #[test]
+    pub fn should_find_chi_shapes_from_kamicha_only() {
+        let calls = find_discard_calls(&noten_hand(), &options(), Tiles::M4 as i8, 3).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(&calls[0], CallOption::Chi(m) if *m == vec![2, 3, 4]));
+        assert!(matches!(&calls[1], CallOption::Chi(m) if *m == vec![3, 4, 5]));
+
+        let calls = find_discard_calls(&noten_hand(), &options(), Tiles::M4 as i8, 2).unwrap();
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    pub fn should_find_pon_and_daiminkan() {
+        let calls = find_discard_calls(&noten_hand(), &options(), Tiles::M5 as i8, 1).unwrap();
+        assert_eq!(calls.len(), 2);
+        assert!(matches!(&calls[0], CallOption::Pon(_)));
+        assert!(matches!(&calls[1], CallOption::Daiminkan(_)));
+
+        let calls = find_discard_calls(&noten_hand(), &options(), Tiles::E as i8, 3).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(matches!(&calls[0], CallOption::Pon(m) if *m == vec![28, 28, 28]));
+    }
+
+    #[test]
+    pub fn should_find_ron() {
+        let calls = find_discard_calls(&tenpai_hand(), &options(), Tiles::P4 as i8, 3).unwrap();
+        assert!(
+            matches!(&calls[0], CallOption::Ron(r) if r.yaku == vec![(Yaku::Tanyao as i8, 1), (Yaku::Pinfu as i8, 1)])
+        );
+        // chi with 23p is possible too
+        assert_eq!(calls.len(), 2);
+
+        // only ron is possible in riichi
+        let mut riichi = options();
+        riichi.riichi = true;
+        let calls = find_discard_calls(&tenpai_hand(), &riichi, Tiles::P4 as i8, 3).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(matches!(&calls[0], CallOption::Ron(r) if r.han == 3));
+
+        // no yaku
+        let mut no_pinfu = options();
+        no_pinfu.disabled_yaku = vec![Yaku::Pinfu as i8];
+        let calls = find_discard_calls(&tenpai_hand(), &no_pinfu, Tiles::P1 as i8, 2).unwrap();
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    pub fn should_find_draw_actions() {
+        let mut hand = tenpai_hand();
+        hand.closed_part = hand.closed_part[0..8].to_vec();
+        hand.closed_part.extend([Tiles::S8 as i8, Tiles::S8 as i8]);
+        hand.open_part
+            .push((true, vec![Tiles::E as i8, Tiles::E as i8, Tiles::E as i8]));
+        hand.closed_part.push(Tiles::E as i8);
+        let actions = find_draw_actions(&hand, &options(), true).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], CallOption::Shouminkan(28)));
+
+        let mut hand = tenpai_hand();
+        hand.closed_part.push(Tiles::P4 as i8);
+        let actions = find_draw_actions(&hand, &options(), true).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], CallOption::Tsumo(r) if r.han == 3));
+    }
+
+    #[test]
+    pub fn should_check_ankan_after_riichi() {
+        // 1112m + 444m: kan of 4m changes decomposition
+        let mut hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M1,
+            Tiles::M2,
+            Tiles::M4,
+            Tiles::M4,
+            Tiles::M4,
+            Tiles::P1,
+            Tiles::P2,
+            Tiles::P3,
+            Tiles::S1,
+            Tiles::S2,
+            Tiles::S3,
+            Tiles::M4,
+        ]);
+        let mut riichi = options();
+        riichi.riichi = true;
+        assert!(find_draw_actions(&hand, &riichi, true).unwrap().is_empty());
+
+        let actions = find_draw_actions(&hand, &riichi, false).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], CallOption::Ankan(4)));
+
+        // kan is not possible on tile which is not drawn right now
+        hand.closed_part.swap(0, 13);
+        assert!(find_draw_actions(&hand, &riichi, false).unwrap().is_empty());
+    }
+
+    #[test]
+    pub fn should_find_kyuushu() {
+        let hand = closed_hand(vec![
+            Tiles::M1,
+            Tiles::M9,
+            Tiles::P1,
+            Tiles::P5,
+            Tiles::S9,
+            Tiles::S2,
+            Tiles::S3,
+            Tiles::E,
+            Tiles::S,
+            Tiles::W,
+            Tiles::W,
+            Tiles::GD,
+            Tiles::M5,
+            Tiles::RD,
+        ]);
+        let mut first_take = options();
+        first_take.first_take = true;
+        let actions = find_draw_actions(&hand, &first_take, true).unwrap();
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], CallOption::Kyuushu));
+
+        assert!(
+            find_draw_actions(&hand, &options(), true)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
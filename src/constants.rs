@@ -139,6 +139,147 @@ pub enum Yaku {
     Dora = 53,
     Uradora = 54,
     Akadora = 55,
+
+    OpenRiichi = 56,
+    OpenDaburuRiichi = 57,
+    OpenRiichiDealIn = 58,
+}
+
+impl Yaku {
+    // Name for text output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Yaku::Kokushimusou13Sides => "Kokushi musou 13-sided wait",
+            Yaku::Kokushimusou => "Kokushi musou",
+            Yaku::Chuurenpoto9Sides => "Junsei chuuren poutou",
+            Yaku::Chuurenpoto => "Chuuren poutou",
+            Yaku::SuuankouTanki => "Suu ankou tanki",
+            Yaku::Suuankou => "Suu ankou",
+            Yaku::Daisuushi => "Daisuushi",
+            Yaku::Shosuushi => "Shousuushi",
+            Yaku::Daisangen => "Daisangen",
+            Yaku::Tsuuiisou => "Tsuuiisou",
+            Yaku::Ryuuiisou => "Ryuuiisou",
+            Yaku::Chinroutou => "Chinroutou",
+            Yaku::Suukantsu => "Suukantsu",
+            Yaku::Tenhou => "Tenhou",
+            Yaku::Chihou => "Chihou",
+            Yaku::Renhou => "Renhou",
+            Yaku::Daisharin => "Daisharin",
+            Yaku::Chinitsu => "Chinitsu",
+            Yaku::Honitsu => "Honitsu",
+            Yaku::Ryanpeikou => "Ryanpeikou",
+            Yaku::Junchan => "Junchan",
+            Yaku::Chanta => "Chanta",
+            Yaku::Toitoi => "Toitoi",
+            Yaku::Honroutou => "Honroutou",
+            Yaku::Sankantsu => "Sankantsu",
+            Yaku::Shosangen => "Shousangen",
+            Yaku::SanshokuDoukou => "Sanshoku doukou",
+            Yaku::Sanankou => "San ankou",
+            Yaku::Chiitoitsu => "Chiitoitsu",
+            Yaku::DaburuRiichi => "Double riichi",
+            Yaku::Ittsu => "Ittsu",
+            Yaku::Sanshoku => "Sanshoku",
+            Yaku::Tanyao => "Tanyao",
+            Yaku::Pinfu => "Pinfu",
+            Yaku::Iipeikou => "Iipeikou",
+            Yaku::Menzentsumo => "Menzen tsumo",
+            Yaku::Riichi => "Riichi",
+            Yaku::Ippatsu => "Ippatsu",
+            Yaku::Rinshan => "Rinshan kaihou",
+            Yaku::Chankan => "Chankan",
+            Yaku::Haitei => "Haitei",
+            Yaku::Houtei => "Houtei",
+            Yaku::RoundWindEast => "Round wind: East",
+            Yaku::RoundWindSouth => "Round wind: South",
+            Yaku::RoundWindWest => "Round wind: West",
+            Yaku::RoundWindNorth => "Round wind: North",
+            Yaku::OwnWindEast => "Own wind: East",
+            Yaku::OwnWindSouth => "Own wind: South",
+            Yaku::OwnWindWest => "Own wind: West",
+            Yaku::OwnWindNorth => "Own wind: North",
+            Yaku::Haku => "Haku",
+            Yaku::Hatsu => "Hatsu",
+            Yaku::Chun => "Chun",
+            Yaku::Dora => "Dora",
+            Yaku::Uradora => "Uradora",
+            Yaku::Akadora => "Akadora",
+            Yaku::OpenRiichi => "Open riichi",
+            Yaku::OpenDaburuRiichi => "Open double riichi",
+            Yaku::OpenRiichiDealIn => "Open riichi deal-in",
+        }
+    }
+}
+
+// Yaku by id in scoring results
+impl TryFrom<i8> for Yaku {
+    type Error = String;
+
+    fn try_from(id: i8) -> Result<Self, Self::Error> {
+        Ok(match id {
+            0 => Yaku::Kokushimusou13Sides,
+            1 => Yaku::Kokushimusou,
+            2 => Yaku::Chuurenpoto9Sides,
+            3 => Yaku::Chuurenpoto,
+            4 => Yaku::SuuankouTanki,
+            5 => Yaku::Suuankou,
+            6 => Yaku::Daisuushi,
+            7 => Yaku::Shosuushi,
+            8 => Yaku::Daisangen,
+            9 => Yaku::Tsuuiisou,
+            10 => Yaku::Ryuuiisou,
+            11 => Yaku::Chinroutou,
+            12 => Yaku::Suukantsu,
+            13 => Yaku::Tenhou,
+            14 => Yaku::Chihou,
+            15 => Yaku::Renhou,
+            16 => Yaku::Daisharin,
+            17 => Yaku::Chinitsu,
+            18 => Yaku::Honitsu,
+            19 => Yaku::Ryanpeikou,
+            20 => Yaku::Junchan,
+            21 => Yaku::Chanta,
+            22 => Yaku::Toitoi,
+            23 => Yaku::Honroutou,
+            24 => Yaku::Sankantsu,
+            25 => Yaku::Shosangen,
+            26 => Yaku::SanshokuDoukou,
+            27 => Yaku::Sanankou,
+            28 => Yaku::Chiitoitsu,
+            29 => Yaku::DaburuRiichi,
+            30 => Yaku::Ittsu,
+            31 => Yaku::Sanshoku,
+            32 => Yaku::Tanyao,
+            33 => Yaku::Pinfu,
+            34 => Yaku::Iipeikou,
+            35 => Yaku::Menzentsumo,
+            36 => Yaku::Riichi,
+            37 => Yaku::Ippatsu,
+            38 => Yaku::Rinshan,
+            39 => Yaku::Chankan,
+            40 => Yaku::Haitei,
+            41 => Yaku::Houtei,
+            42 => Yaku::RoundWindEast,
+            43 => Yaku::RoundWindSouth,
+            44 => Yaku::RoundWindWest,
+            45 => Yaku::RoundWindNorth,
+            46 => Yaku::OwnWindEast,
+            47 => Yaku::OwnWindSouth,
+            48 => Yaku::OwnWindWest,
+            49 => Yaku::OwnWindNorth,
+            50 => Yaku::Haku,
+            51 => Yaku::Hatsu,
+            52 => Yaku::Chun,
+            53 => Yaku::Dora,
+            54 => Yaku::Uradora,
+            55 => Yaku::Akadora,
+            56 => Yaku::OpenRiichi,
+            57 => Yaku::OpenDaburuRiichi,
+            58 => Yaku::OpenRiichiDealIn,
+            _ => return Err("Unknown yaku".parse().unwrap()),
+        })
+    }
 }
 
 pub fn kokushi_idx() -> [i8; 13] {
@@ -171,15 +312,24 @@ pub fn ceil10(val: i32) -> i32 {
     10 * (val as f32 / 10.0).ceil() as i32
 }
 
-pub fn slice_by_suit(haipai: &Vec<i8>) -> Vec<Vec<i8>> {
-    vec![
-        haipai[Suit::Man * 9..Suit::Man * 9 + 9].to_vec(),
-        haipai[Suit::Pin * 9..Suit::Pin * 9 + 9].to_vec(),
-        haipai[Suit::Sou * 9..Suit::Sou * 9 + 9].to_vec(),
-        haipai[Suit::Honor * 9..Suit::Honor * 9 + 7].to_vec(),
+pub fn slice_by_suit(tiles_34: &[i8]) -> [&[i8]; 4] {
+    [
+        &tiles_34[Suit::Man * 9..Suit::Man * 9 + 9],
+        &tiles_34[Suit::Pin * 9..Suit::Pin * 9 + 9],
+        &tiles_34[Suit::Sou * 9..Suit::Sou * 9 + 9],
+        &tiles_34[Suit::Honor * 9..Suit::Honor * 9 + 7],
     ]
 }
 
+// Converts list of tile ids to tile34-formatted counts
+pub fn tiles_to_34(tiles: &[i8]) -> Vec<i8> {
+    let mut haipai34 = vec![0; 34];
+    for tile in tiles {
+        haipai34[(tile.abs() - 1) as usize] += 1;
+    }
+    haipai34
+}
+
 pub fn is19(tile: i8) -> bool {
     kokushi_idx().contains(&tile)
 }
@@ -207,23 +357,6 @@ pub fn is_proper_open_set(arr: &Vec<i8>) -> bool {
     }
 }
 
-pub fn digest(decomposition: &Vec<Vec<i8>>) -> String {
-    let mut arr = decomposition
-        .iter()
-        .map(|set| {
-            format!(
-                "|{}|",
-                set.iter()
-                    .map(|val| val.to_string())
-                    .collect::<Vec<String>>()
-                    .join(",")
-            )
-        })
-        .collect::<Vec<String>>();
-    arr.sort();
-    arr.join("#")
-}
-
 pub static GREENS: [i8; 6] = [
     Tiles::S2 as i8,
     Tiles::S3 as i8,
@@ -304,3 +437,19 @@ pub static SIMPLE_TILES: [i8; 21] = [
     Tiles::S7 as i8,
     Tiles::S8 as i8,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn yaku_ids_should_match() {
+        for id in 0..59 {
+            let yaku = Yaku::try_from(id).unwrap();
+            assert_eq!(yaku.clone() as i8, id);
+            assert!(!yaku.name().is_empty());
+        }
+        assert_eq!(Yaku::try_from(Yaku::Riichi as i8).unwrap().name(), "Riichi");
+        assert!(Yaku::try_from(59).is_err());
+    }
+}
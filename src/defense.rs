@@ -0,0 +1,284 @@
+use crate::constants::{slice_by_suit, tiles_to_34};
+use crate::interfaces::{DangerRules, OpponentPond, RiichiHand, TileSafety};
+use crate::ukeire::unseen_tiles;
+
+// Ryanmen shapes winning on the tile at index in the suit, with the other wait of each shape
+fn ryanmen_shapes(index: usize) -> Vec<((usize, usize), usize)> {
+    let mut shapes = Vec::new();
+    if index >= 3 {
+        shapes.push(((index - 2, index - 1), index - 3));
+    }
+    if index <= 5 {
+        shapes.push(((index + 1, index + 2), index + 3));
+    }
+    shapes
+}
+
+// Tile is outside of the discard: 1-4 are outside of 5, 1-2 are outside of 3, 8-9 are outside of 7
+fn is_outside(index: usize, discard: usize) -> bool {
+    (discard <= 4 && index < discard) || (discard >= 4 && index > discard)
+}
+
+// Classify every tile of the closed part by its safety against one opponent, safest tiles first.
+// Visible tiles are all discards (including the opponent's ones), melds of other players and dora indicators.
+pub fn analyze_danger(
+    hand: &RiichiHand,
+    pond: &OpponentPond,
+    visible: &[i8],
+    rules: &DangerRules,
+) -> Result<Vec<TileSafety>, String> {
+    if hand.closed_part.is_empty() {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    if pond.discards.iter().any(|t| !(1..=34).contains(t)) {
+        return Err("Incorrect tile".parse().unwrap());
+    }
+    if pond
+        .riichi_discard
+        .is_some_and(|i| i >= pond.discards.len())
+    {
+        return Err("Incorrect riichi discard".parse().unwrap());
+    }
+
+    let (_, unseen) = unseen_tiles(hand, visible);
+    let discarded = tiles_to_34(&pond.discards);
+    let early_count = pond
+        .riichi_discard
+        .map_or(rules.early_discards, |i| rules.early_discards.min(i));
+    let early = tiles_to_34(&pond.discards[..early_count.min(pond.discards.len())]);
+
+    let unseen_by_suit = slice_by_suit(&unseen);
+    let discarded_by_suit = slice_by_suit(&discarded);
+    let early_by_suit = slice_by_suit(&early);
+
+    let mut tiles = hand.closed_part.clone();
+    tiles.sort();
+    tiles.dedup();
+
+    let mut result: Vec<TileSafety> = tiles
+        .into_iter()
+        .map(|tile| {
+            let suit = (tile - 1) as usize / 9;
+            let index = (tile - 1) as usize % 9;
+            let mut safety = TileSafety {
+                tile,
+                genbutsu: discarded[(tile - 1) as usize] > 0,
+                suji: false,
+                no_chance: false,
+                one_chance: false,
+                visible: 4 - unseen[(tile - 1) as usize],
+                early_outside: false,
+                danger: 0.0,
+            };
+
+            let danger = if suit == 3 {
+                rules.honors[safety.visible.clamp(0, 3) as usize]
+            } else {
+                let unseen = unseen_by_suit[suit];
+                let shapes = ryanmen_shapes(index);
+                safety.suji = shapes
+                    .iter()
+                    .all(|(_, other)| discarded_by_suit[suit][*other] > 0);
+                let least_unseen =
+                    |((a, b), _): &((usize, usize), usize)| unseen[*a].min(unseen[*b]);
+                safety.no_chance = shapes.iter().all(|s| least_unseen(s) == 0);
+                safety.one_chance =
+                    !safety.no_chance && shapes.iter().all(|s| least_unseen(s) <= 1);
+                safety.early_outside = early_by_suit[suit]
+                    .iter()
+                    .enumerate()
+                    .any(|(discard, count)| *count > 0 && is_outside(index, discard));
+
+                let mut danger = match index {
+                    0 | 8 => rules.terminal,
+                    1 | 7 => rules.edge,
+                    _ => rules.middle,
+                };
+                if safety.suji {
+                    danger *= rules.suji;
+                }
+                if safety.no_chance {
+                    danger *= rules.no_chance;
+                } else if safety.one_chance {
+                    danger *= rules.one_chance;
+                }
+                if safety.early_outside {
+                    danger *= rules.early_outside;
+                }
+                danger
+            };
+            safety.danger = if safety.genbutsu {
+                rules.genbutsu
+            } else {
+                danger
+            };
+            safety
+        })
+        .collect();
+
+    result.sort_by(|a, b| a.danger.total_cmp(&b.danger).then(a.tile.cmp(&b.tile)));
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn rules() -> DangerRules {
+        DangerRules {
+            genbutsu: 0.0,
+            terminal: 5.0,
+            edge: 7.0,
+            middle: 10.0,
+            honors: [6.0, 4.0, 2.0, 0.5],
+            suji: 0.4,
+            no_chance: 0.3,
+            one_chance: 0.7,
+            early_outside: 0.8,
+            early_discards: 6,
+        }
+    }
+
+    fn safety(result: &[TileSafety], tile: Tiles) -> &TileSafety {
+        let tile = tile as i8;
+        result.iter().find(|s| s.tile == tile).unwrap()
+    }
+
+    #[test]
+    pub fn should_find_genbutsu_and_suji() {
+        let pond = OpponentPond {
+            discards: vec![
+                Tiles::N as i8,
+                Tiles::M4 as i8,
+                Tiles::P1 as i8,
+                Tiles::P7 as i8,
+            ],
+            riichi_discard: Some(3),
+        };
+        let result = analyze_danger(
+            &RiichiHand::for_tests(vec![
+                Tiles::M1,
+                Tiles::M4,
+                Tiles::M7,
+                Tiles::P4,
+                Tiles::P5,
+                Tiles::S5,
+            ]),
+            &pond,
+            &pond.discards,
+            &rules(),
+        )
+        .unwrap();
+
+        assert_eq!(result.len(), 6);
+        assert_eq!(result[0].tile, Tiles::M4 as i8);
+        assert!(result[0].genbutsu);
+        assert_eq!(result[0].danger, 0.0);
+
+        // 1m and 7m are suji of 4m
+        assert!(safety(&result, Tiles::M1).suji);
+        assert!(safety(&result, Tiles::M7).suji);
+        // 4p is nakasuji of 1p and 7p, 5p is not suji
+        assert!(safety(&result, Tiles::P4).suji);
+        assert!(!safety(&result, Tiles::P5).suji);
+        assert_eq!(safety(&result, Tiles::P4).danger, 4.0);
+
+        // Unrelated middle tile is the most dangerous one
+        assert_eq!(result[5].tile, Tiles::S5 as i8);
+        assert_eq!(result[5].danger, 10.0);
+    }
+
+    #[test]
+    pub fn should_find_no_chance_and_one_chance() {
+        // Three 8s are visible and the fourth one is in own hand: 9s can't be won on by ryanmen
+        let visible = vec![
+            Tiles::S8 as i8,
+            Tiles::S8 as i8,
+            Tiles::S8 as i8,
+            Tiles::P3 as i8,
+            Tiles::P3 as i8,
+        ];
+        let pond = OpponentPond {
+            discards: vec![],
+            riichi_discard: None,
+        };
+        let result = analyze_danger(
+            &RiichiHand::for_tests(vec![Tiles::S8, Tiles::S9, Tiles::P3, Tiles::P1, Tiles::P2]),
+            &pond,
+            &visible,
+            &rules(),
+        )
+        .unwrap();
+
+        let s9 = safety(&result, Tiles::S9);
+        assert!(s9.no_chance && !s9.one_chance);
+        assert!((s9.danger - 1.5).abs() < 1e-6);
+        assert_eq!(safety(&result, Tiles::S8).visible, 4);
+        // Only one 3p is unseen: 1p and 2p are one-chance
+        let p1 = safety(&result, Tiles::P1);
+        assert!(p1.one_chance && !p1.no_chance);
+        assert!(safety(&result, Tiles::P2).one_chance);
+        assert!(!safety(&result, Tiles::P3).one_chance);
+    }
+
+    #[test]
+    pub fn should_count_visible_honors_and_early_outside() {
+        let pond = OpponentPond {
+            discards: vec![
+                Tiles::M3 as i8,
+                Tiles::S7 as i8,
+                Tiles::E as i8,
+                Tiles::P5 as i8,
+            ],
+            riichi_discard: Some(2),
+        };
+        let mut visible = pond.discards.clone();
+        visible.extend([Tiles::WD as i8, Tiles::WD as i8]);
+        let result = analyze_danger(
+            &RiichiHand::for_tests(vec![
+                Tiles::WD,
+                Tiles::GD,
+                Tiles::M1,
+                Tiles::M4,
+                Tiles::S9,
+                Tiles::P2,
+            ]),
+            &pond,
+            &visible,
+            &rules(),
+        )
+        .unwrap();
+
+        assert_eq!(safety(&result, Tiles::WD).visible, 3);
+        assert_eq!(safety(&result, Tiles::WD).danger, 0.5);
+        assert_eq!(safety(&result, Tiles::GD).visible, 1);
+        assert_eq!(safety(&result, Tiles::GD).danger, 4.0);
+
+        // 3m and 7s were discarded before riichi, 5p is the discard after it
+        assert!(safety(&result, Tiles::M1).early_outside);
+        assert!(!safety(&result, Tiles::M4).early_outside);
+        assert!(safety(&result, Tiles::S9).early_outside);
+        assert!(!safety(&result, Tiles::P2).early_outside);
+        assert_eq!(result[0].tile, Tiles::WD as i8);
+    }
+
+    #[test]
+    pub fn should_check_pond() {
+        let pond = OpponentPond {
+            discards: vec![Tiles::M1 as i8],
+            riichi_discard: Some(1),
+        };
+        assert_eq!(
+            analyze_danger(
+                &RiichiHand::for_tests(vec![Tiles::M1]),
+                &pond,
+                &[],
+                &rules()
+            )
+            .err()
+            .unwrap(),
+            "Incorrect riichi discard"
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::agari::find_waits;
+use crate::constants::tiles_to_34;
+use crate::interfaces::{FuritenOptions, FuritenResult, RiichiHand};
+use crate::riichi::split_hand;
+
+pub fn check_furiten(hand: &RiichiHand, options: &FuritenOptions) -> Result<FuritenResult, String> {
+    let (haipai, furo) = split_hand(hand);
+
+    if haipai.len() + furo.len() * 3 != 13 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+
+    let waits = find_waits(&tiles_to_34(&haipai), &furo);
+    let any_wait_in = |tiles: &Vec<i8>| waits.iter().any(|w| tiles.contains(w));
+
+    let permanent = any_wait_in(&options.discards);
+    let temporary = any_wait_in(&options.passed_tiles);
+    let riichi = options.riichi && any_wait_in(&options.passed_since_riichi);
+
+    Ok(FuritenResult {
+        is_furiten: permanent || temporary || riichi,
+        waits,
+        permanent,
+        temporary,
+        riichi,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn tempai_hand() -> RiichiHand {
+        // 23m waiting on 1m and 4m
+        RiichiHand {
+            closed_part: vec![
+                Tiles::M2 as i8,
+                Tiles::M3 as i8,
+                Tiles::P2 as i8,
+                Tiles::P3 as i8,
+                Tiles::P4 as i8,
+                Tiles::S3 as i8,
+                Tiles::S4 as i8,
+                Tiles::S5 as i8,
+                Tiles::S7 as i8,
+                Tiles::S7 as i8,
+            ],
+            open_part: vec![(
+                true,
+                vec![Tiles::WD as i8, Tiles::WD as i8, Tiles::WD as i8],
+            )],
+        }
+    }
+
+    #[test]
+    pub fn should_detect_permanent_furiten_on_other_wait() {
+        let res = check_furiten(
+            &tempai_hand(),
+            &FuritenOptions {
+                discards: vec![Tiles::N as i8, Tiles::M4 as i8],
+                passed_tiles: vec![],
+                riichi: false,
+                passed_since_riichi: vec![],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(res.waits, vec![Tiles::M1 as i8, Tiles::M4 as i8]);
+        assert!(res.permanent);
+        assert!(!res.temporary);
+        assert!(!res.riichi);
+        assert!(res.is_furiten);
+    }
+
+    #[test]
+    pub fn should_detect_temporary_and_riichi_furiten() {
+        let mut options = FuritenOptions {
+            discards: vec![Tiles::N as i8],
+            passed_tiles: vec![Tiles::M1 as i8],
+            riichi: false,
+            passed_since_riichi: vec![Tiles::M1 as i8],
+        };
+
+        let res = check_furiten(&tempai_hand(), &options).unwrap();
+        assert!(!res.permanent);
+        assert!(res.temporary);
+        assert!(!res.riichi);
+        assert!(res.is_furiten);
+
+        options.riichi = true;
+        options.passed_tiles = vec![Tiles::P5 as i8];
+        let res = check_furiten(&tempai_hand(), &options).unwrap();
+        assert!(!res.temporary);
+        assert!(res.riichi);
+        assert!(res.is_furiten);
+    }
+
+    #[test]
+    pub fn should_not_detect_furiten_on_non_waits() {
+        let res = check_furiten(
+            &tempai_hand(),
+            &FuritenOptions {
+                discards: vec![Tiles::M2 as i8, Tiles::M5 as i8],
+                passed_tiles: vec![Tiles::S7 as i8],
+                riichi: true,
+                passed_since_riichi: vec![Tiles::S7 as i8, Tiles::M5 as i8],
+            },
+        )
+        .unwrap();
+
+        assert!(!res.is_furiten);
+    }
+
+    #[test]
+    pub fn should_use_full_wait_set() {
+        // 7-pairs wait: furiten on the only wait
+        let res = check_furiten(
+            &RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M1 as i8,
+                    Tiles::M9 as i8,
+                    Tiles::M9 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::E as i8,
+                    Tiles::E as i8,
+                    Tiles::GD as i8,
+                ],
+                open_part: vec![],
+            },
+            &FuritenOptions {
+                discards: vec![Tiles::GD as i8],
+                passed_tiles: vec![],
+                riichi: false,
+                passed_since_riichi: vec![],
+            },
+        )
+        .unwrap();
+
+        assert_eq!(res.waits, vec![Tiles::GD as i8]);
+        assert!(res.permanent);
+    }
+
+    #[test]
+    pub fn should_fail_on_wrong_tiles_count() {
+        let mut hand = tempai_hand();
+        hand.closed_part.push(Tiles::M1 as i8);
+        let res = check_furiten(
+            &hand,
+            &FuritenOptions {
+                discards: vec![],
+                passed_tiles: vec![],
+                riichi: false,
+                passed_since_riichi: vec![],
+            },
+        );
+        assert!(res.is_err());
+
+        // Partial hand without melds
+        hand.closed_part.truncate(4);
+        let res = check_furiten(
+            &hand,
+            &FuritenOptions {
+                discards: vec![],
+                passed_tiles: vec![],
+                riichi: false,
+                passed_since_riichi: vec![],
+            },
+        );
+        assert!(res.is_err());
+    }
+}
@@ -0,0 +1,476 @@
+use crate::interfaces::{
+    GameResult, GameRules, HandOutcome, RoundConfig, RoundEnd, RoundResult, RoundRules, RoundWin,
+};
+use crate::placement::{calc_placement, rank};
+
+// Pay for wins: riichi deposits go to the first winner, honba too unless every winner takes it
+pub(crate) fn pay_wins(
+    scores: &mut [i32],
+    riichi_sticks: &mut i8,
+    dealer: usize,
+    honba: i8,
+    honba_to_each: bool,
+    wins: &[RoundWin],
+) {
+    for (i, win) in wins.iter().enumerate() {
+        let honba = if i == 0 || honba_to_each {
+            honba as i32
+        } else {
+            0
+        };
+        let seat = win.seat as usize;
+        if win.from < 0 {
+            let (oya, ko) = win.result.outgoing_ten.unwrap_or((0, 0));
+            for payer in 0..scores.len() {
+                if payer == seat {
+                    continue;
+                }
+                let payment = if seat == dealer || payer == dealer {
+                    oya
+                } else {
+                    ko
+                } + honba * 100;
+                scores[payer] -= payment;
+                scores[seat] += payment;
+            }
+        } else {
+            let payment = win.result.ten + honba * 300;
+            scores[win.from as usize] -= payment;
+            scores[seat] += payment;
+        }
+    }
+    if let Some(win) = wins.first() {
+        scores[win.seat as usize] += *riichi_sticks as i32 * 1000;
+        *riichi_sticks = 0;
+    }
+}
+
+// Noten players pay 3000 in total to tenpai players
+pub(crate) fn pay_tenpai(scores: &mut [i32], tenpai: &[bool]) {
+    let count = tenpai.iter().filter(|t| **t).count() as i32;
+    if count == 0 || count == tenpai.len() as i32 {
+        return;
+    }
+    for (seat, is_tenpai) in tenpai.iter().enumerate() {
+        scores[seat] += if *is_tenpai {
+            3000 / count
+        } else {
+            -3000 / (tenpai.len() as i32 - count)
+        };
+    }
+}
+
+pub struct Game {
+    rules: GameRules,
+    round: i8, // hands played without renchan: 0-3 East, 4-7 South, 8-11 West
+    honba: i8,
+    riichi_sticks: i8,
+    scores: Vec<i32>,
+    result: Option<GameResult>,
+}
+
+impl Game {
+    // Seat 0 is the first dealer
+    pub fn new(rules: GameRules) -> Result<Game, String> {
+        let scores = vec![rules.placement.start_points; 4];
+        calc_placement(&scores, &rules.placement)?;
+        Ok(Game {
+            scores,
+            rules,
+            round: 0,
+            honba: 0,
+            riichi_sticks: 0,
+            result: None,
+        })
+    }
+
+    pub fn bakaze(&self) -> i8 {
+        28 + self.round / 4
+    }
+
+    pub fn dealer(&self) -> i8 {
+        self.round % 4
+    }
+
+    pub fn honba(&self) -> i8 {
+        self.honba
+    }
+
+    pub fn riichi_sticks(&self) -> i8 {
+        self.riichi_sticks
+    }
+
+    pub fn scores(&self) -> Vec<i32> {
+        self.scores.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn result(&self) -> Option<&GameResult> {
+        self.result.as_ref()
+    }
+
+    // Config for the round engine to play the current hand
+    pub fn round_config(&self, seed: u64, rules: RoundRules) -> RoundConfig {
+        RoundConfig {
+            seed,
+            dealer: self.dealer(),
+            bakaze: self.bakaze(),
+            honba: self.honba,
+            riichi_sticks: self.riichi_sticks,
+            scores: self.scores.clone(),
+            rules,
+        }
+    }
+
+    // Record the hand scored elsewhere: seats which declared riichi and the outcome
+    pub fn apply_hand(&mut self, riichi: &[i8], outcome: HandOutcome) -> Result<(), String> {
+        if self.is_finished() {
+            return Err("Game is over".parse().unwrap());
+        }
+        let seat_ok = |seat: i8| (0..4).contains(&seat);
+        let valid = match &outcome {
+            HandOutcome::Agari(wins) => {
+                !wins.is_empty()
+                    && wins.iter().all(|w| {
+                        seat_ok(w.seat) && w.from != w.seat && (w.from == -1 || seat_ok(w.from))
+                    })
+            }
+            HandOutcome::Ryuukyoku(tenpai) => tenpai.len() == 4,
+            HandOutcome::Abortive => true,
+        };
+        if !valid || !riichi.iter().all(|s| seat_ok(*s)) {
+            return Err("Incorrect hand outcome".parse().unwrap());
+        }
+
+        for seat in riichi {
+            self.scores[*seat as usize] -= 1000;
+            self.riichi_sticks += 1;
+        }
+
+        let dealer = self.dealer() as usize;
+        match outcome {
+            HandOutcome::Agari(wins) => {
+                pay_wins(
+                    &mut self.scores,
+                    &mut self.riichi_sticks,
+                    dealer,
+                    self.honba,
+                    self.rules.multiple_ron_honba,
+                    &wins,
+                );
+                let dealer_won = wins.iter().any(|w| w.seat as usize == dealer);
+                self.advance(dealer_won, false);
+            }
+            HandOutcome::Ryuukyoku(tenpai) => {
+                pay_tenpai(&mut self.scores, &tenpai);
+                self.advance(tenpai[dealer] && self.rules.tenpai_renchan, true);
+            }
+            HandOutcome::Abortive => self.advance(true, true),
+        }
+        Ok(())
+    }
+
+    // Record the hand played by the round engine with config from round_config
+    pub fn apply_round(&mut self, result: &RoundResult) -> Result<(), String> {
+        if self.is_finished() {
+            return Err("Game is over".parse().unwrap());
+        }
+        let dealer = self.dealer();
+        self.scores = result.scores.clone();
+        self.riichi_sticks = result.riichi_sticks;
+        match result.end {
+            RoundEnd::Agari => {
+                let dealer_won = result.wins.iter().any(|w| w.seat == dealer);
+                self.advance(dealer_won, false);
+            }
+            RoundEnd::Ryuukyoku => {
+                let dealer_tenpai = result.tenpai[dealer as usize];
+                self.advance(dealer_tenpai && self.rules.tenpai_renchan, true);
+            }
+            _ => self.advance(true, true),
+        }
+        Ok(())
+    }
+
+    fn advance(&mut self, renchan: bool, draw: bool) {
+        let last = if self.rules.hanchan { 8 } else { 4 };
+        if renchan || draw {
+            self.honba += 1;
+        } else {
+            self.honba = 0;
+        }
+
+        if self.rules.tobi && self.scores.iter().any(|s| *s < 0) {
+            self.finish();
+            return;
+        }
+
+        let reached = self
+            .scores
+            .iter()
+            .any(|s| *s >= self.rules.placement.return_points);
+        // Extension round (West, or South in tonpuusen) is played until someone reaches return points
+        if self.round >= last && reached {
+            self.finish();
+            return;
+        }
+
+        if renchan {
+            let dealer = self.dealer() as usize;
+            let dealer_leads = rank(&self.scores)[dealer] == 1;
+            if self.round == last - 1
+                && !draw
+                && self.rules.agari_yame
+                && dealer_leads
+                && (reached || !self.rules.west_extension)
+            {
+                self.finish();
+            }
+            return;
+        }
+
+        self.round += 1;
+        if self.round >= last && !(self.rules.west_extension && !reached && self.round < last + 4) {
+            self.finish();
+        }
+    }
+
+    fn finish(&mut self) {
+        // Leftover riichi deposits go to the first place
+        let first = rank(&self.scores).iter().position(|p| *p == 1).unwrap();
+        self.scores[first] += self.riichi_sticks as i32 * 1000;
+        self.riichi_sticks = 0;
+
+        // Rules are checked on start
+        let placement = calc_placement(&self.scores, &self.rules.placement).unwrap();
+        self.result = Some(GameResult {
+            scores: self.scores.clone(),
+            places: placement.places,
+            points: placement.points,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+    use crate::interfaces::{
+        AbortiveDrawRules, KuikaeRules, PlacementRules, RiichiResult, RoundAction, RoundPhase,
+    };
+    use crate::round::Round;
+
+    fn rules() -> GameRules {
+        GameRules {
+            hanchan: false,
+            placement: PlacementRules {
+                start_points: 25000,
+                return_points: 30000,
+                oka: None,
+                uma: vec![vec![15000, 5000, -5000, -15000]],
+                split_ties: false,
+            },
+            west_extension: true,
+            agari_yame: true,
+            tenpai_renchan: true,
+            tobi: true,
+            multiple_ron_honba: false,
+        }
+    }
+
+    fn win(seat: i8, from: i8, ten: i32, outgoing_ten: Option<(i32, i32)>) -> RoundWin {
+        RoundWin {
+            seat,
+            from,
+            result: RiichiResult {
+                is_agari: true,
+                yakuman: 0,
+                han: 1,
+                fu: 30,
+                ten,
+                outgoing_ten,
+                yaku: vec![],
+                hairi: None,
+            },
+        }
+    }
+
+    #[test]
+    pub fn should_rotate_dealer_and_count_honba() {
+        let mut game = Game::new(rules()).unwrap();
+
+        // dealer ron: renchan
+        game.apply_hand(&[1], HandOutcome::Agari(vec![win(0, 2, 2900, None)]))
+            .unwrap();
+        assert_eq!(game.dealer(), 0);
+        assert_eq!(game.honba(), 1);
+        assert_eq!(game.scores(), vec![28900, 24000, 22100, 25000]);
+
+        // noten dealer on exhaustive draw: rotation, honba goes on
+        game.apply_hand(&[], HandOutcome::Ryuukyoku(vec![false, true, false, false]))
+            .unwrap();
+        assert_eq!(game.dealer(), 1);
+        assert_eq!(game.honba(), 2);
+        assert_eq!(game.scores(), vec![27900, 27000, 21100, 24000]);
+
+        // riichi deposit is kept on the table after abortive draw
+        game.apply_hand(&[3], HandOutcome::Abortive).unwrap();
+        assert_eq!(game.dealer(), 1);
+        assert_eq!(game.honba(), 3);
+        assert_eq!(game.riichi_sticks(), 1);
+
+        // non-dealer tsumo: honba for every payer, deposits to the winner
+        game.apply_hand(
+            &[],
+            HandOutcome::Agari(vec![win(2, -1, 4000, Some((2000, 1000)))]),
+        )
+        .unwrap();
+        assert_eq!(game.dealer(), 2);
+        assert_eq!(game.honba(), 0);
+        assert_eq!(game.riichi_sticks(), 0);
+        assert_eq!(game.scores(), vec![26600, 24700, 27000, 21700]);
+        assert_eq!(game.bakaze(), Tiles::E as i8);
+    }
+
+    #[test]
+    pub fn should_pay_honba_on_multiple_ron() {
+        let wins = vec![win(1, 0, 2000, None), win(2, 0, 1000, None)];
+        let mut game = Game::new(rules()).unwrap();
+        game.honba = 2;
+        game.riichi_sticks = 1;
+        game.apply_hand(&[], HandOutcome::Agari(wins.clone()))
+            .unwrap();
+        assert_eq!(game.scores(), vec![21400, 28600, 26000, 25000]);
+
+        let mut game = Game::new(GameRules {
+            multiple_ron_honba: true,
+            ..rules()
+        })
+        .unwrap();
+        game.honba = 2;
+        game.riichi_sticks = 1;
+        game.apply_hand(&[], HandOutcome::Agari(wins)).unwrap();
+        assert_eq!(game.scores(), vec![20800, 28600, 26600, 25000]);
+    }
+
+    #[test]
+    pub fn should_finish_with_oka_and_uma() {
+        let mut game = Game::new(rules()).unwrap();
+        for (seat, from, ten) in [(1, 0, 12000), (2, 3, 1000), (0, 3, 1000)] {
+            game.apply_hand(&[], HandOutcome::Agari(vec![win(seat, from, ten, None)]))
+                .unwrap();
+            assert!(!game.is_finished());
+        }
+        assert_eq!(game.dealer(), 3);
+        game.apply_hand(&[], HandOutcome::Agari(vec![win(1, 0, 1000, None)]))
+            .unwrap();
+        assert!(game.is_finished());
+
+        let result = game.result().unwrap();
+        assert_eq!(result.scores, vec![13000, 38000, 26000, 23000]);
+        assert_eq!(result.places, vec![4, 1, 2, 3]);
+        assert_eq!(result.points, vec![-32000, 43000, 1000, -12000]);
+    }
+
+    #[test]
+    pub fn should_play_west_round_until_return_points() {
+        let mut game = Game::new(rules()).unwrap();
+        for _ in 0..4 {
+            game.apply_hand(&[], HandOutcome::Ryuukyoku(vec![false; 4]))
+                .unwrap();
+        }
+        // nobody has 30000: extension round
+        assert!(!game.is_finished());
+        assert_eq!(game.bakaze(), Tiles::S as i8);
+        assert_eq!(game.honba(), 4);
+
+        game.apply_hand(&[], HandOutcome::Agari(vec![win(2, 3, 5200, None)]))
+            .unwrap();
+        assert!(game.is_finished());
+
+        let result = game.result().unwrap();
+        assert_eq!(result.scores, vec![25000, 25000, 31400, 18600]);
+        assert_eq!(result.places, vec![2, 3, 1, 4]);
+        assert_eq!(result.points, vec![0, -10000, 36400, -26400]);
+        assert!(game.apply_hand(&[], HandOutcome::Abortive).is_err());
+    }
+
+    #[test]
+    pub fn should_stop_on_agari_yame_and_tobi() {
+        let mut game = Game::new(rules()).unwrap();
+        for _ in 0..3 {
+            game.apply_hand(&[], HandOutcome::Agari(vec![win(3, 1, 1000, None)]))
+                .unwrap();
+        }
+        assert_eq!(game.dealer(), 3);
+        // last dealer wins and leads: game is over
+        game.apply_hand(&[], HandOutcome::Agari(vec![win(3, 1, 3000, None)]))
+            .unwrap();
+        assert!(game.is_finished());
+        assert_eq!(game.result().unwrap().places[3], 1);
+
+        let mut game = Game::new(rules()).unwrap();
+        game.apply_hand(&[], HandOutcome::Agari(vec![win(1, 0, 32000, None)]))
+            .unwrap();
+        assert!(game.is_finished());
+        assert_eq!(game.result().unwrap().places, vec![4, 1, 2, 3]);
+    }
+
+    #[test]
+    pub fn should_play_game_with_round_engine() {
+        let round_rules = RoundRules {
+            allow_aka: true,
+            allow_kuitan: true,
+            with_kiriage: false,
+            multiple_ron: false,
+            multiple_ron_honba: false,
+            strict_ankan: false,
+            kuikae: KuikaeRules {
+                forbid_genbutsu: true,
+                forbid_suji: true,
+            },
+            abortive: AbortiveDrawRules {
+                kyuushu_kyuuhai: true,
+                suufon_renda: true,
+                suucha_riichi: true,
+                suukaikan: true,
+                sanchahou: true,
+            },
+            disabled_yaku: vec![],
+            local_yaku_enabled: vec![],
+            all_local_yaku_enabled: false,
+            allow_double_yakuman: false,
+        };
+
+        let mut game = Game::new(rules()).unwrap();
+        let mut seed = 0;
+        while !game.is_finished() && seed < 30 {
+            let mut round = Round::new(game.round_config(seed, round_rules.clone())).unwrap();
+            while round.phase() != RoundPhase::Ended {
+                let seat = (0..4)
+                    .find(|s| !round.legal_actions(*s).is_empty())
+                    .unwrap();
+                let actions = round.legal_actions(seat);
+                let action = if actions.contains(&RoundAction::Tsumo) {
+                    RoundAction::Tsumo
+                } else if actions.contains(&RoundAction::Ron) {
+                    RoundAction::Ron
+                } else {
+                    actions.last().unwrap().clone()
+                };
+                round.apply(seat, action).unwrap();
+            }
+            game.apply_round(round.result().unwrap()).unwrap();
+            seed += 1;
+        }
+
+        let scores = game.scores();
+        assert_eq!(
+            scores.iter().sum::<i32>() + game.riichi_sticks() as i32 * 1000,
+            100000
+        );
+    }
+}
@@ -0,0 +1,114 @@
+// Counts of 34 tile kinds packed by 4 bits, one u64 for each suit and honors.
+// Small copy type for the hot paths instead of allocating Vec<i8> for every hand variant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Hand34 {
+    suits: [u64; 4],
+}
+
+const BITS: usize = 4;
+const MASK: u64 = 15;
+
+impl Hand34 {
+    pub fn new() -> Self {
+        Hand34 { suits: [0; 4] }
+    }
+
+    // From tile34-formatted counts
+    pub fn from_counts(counts: &[i8]) -> Self {
+        let mut hand = Hand34::new();
+        for (index, count) in counts.iter().enumerate() {
+            hand.add(index, *count);
+        }
+        hand
+    }
+
+    // From tile ids, closed kans with minus sign are counted too
+    pub fn from_tiles(tiles: &[i8]) -> Self {
+        let mut hand = Hand34::new();
+        for tile in tiles {
+            hand.add((tile.abs() - 1) as usize, 1);
+        }
+        hand
+    }
+
+    pub fn get(&self, index: usize) -> i8 {
+        (self.suits[index / 9] >> (index % 9 * BITS) & MASK) as i8
+    }
+
+    // Count may be negative; counts should stay within 0..=15
+    pub fn add(&mut self, index: usize, count: i8) {
+        assert!(index < 34, "Incorrect tile index {}", index);
+        let suit = &mut self.suits[index / 9];
+        *suit = suit.wrapping_add((count as i64 as u64) << (index % 9 * BITS));
+    }
+
+    pub fn set(&mut self, index: usize, count: i8) {
+        assert!(index < 34, "Incorrect tile index {}", index);
+        let shift = index % 9 * BITS;
+        let suit = &mut self.suits[index / 9];
+        *suit = *suit & !(MASK << shift) | (count as u64) << shift;
+    }
+
+    // Counts of one suit, honors are padded with zeros
+    pub fn suit(&self, suit: usize) -> [i8; 9] {
+        let mut counts = [0; 9];
+        for (i, count) in counts.iter_mut().enumerate() {
+            *count = (self.suits[suit] >> (i * BITS) & MASK) as i8;
+        }
+        counts
+    }
+
+    pub fn suit_count(&self, suit: usize) -> i8 {
+        self.suit(suit).iter().sum()
+    }
+
+    pub fn count(&self) -> i8 {
+        (0..4).map(|suit| self.suit_count(suit)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.suits == [0; 4]
+    }
+
+    pub fn to_vec(&self) -> Vec<i8> {
+        (0..34).map(|index| self.get(index)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn hand34_keeps_counts() {
+        let counts = vec![
+            1, 0, 0, 0, 4, 0, 0, 0, 1, //
+            0, 2, 0, 0, 0, 0, 0, 0, 3, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            1, 0, 0, 0, 0, 0, 2,
+        ];
+        let mut hand = Hand34::from_counts(&counts);
+        assert_eq!(hand.to_vec(), counts);
+        assert_eq!(hand.count(), 14);
+        assert_eq!(hand.suit_count(1), 5);
+        assert_eq!(hand.suit(3), [1, 0, 0, 0, 0, 0, 2, 0, 0]);
+
+        hand.add(4, -3);
+        hand.add(17, 1);
+        hand.set(33, 0);
+        assert_eq!(hand.get(4), 1);
+        assert_eq!(hand.get(17), 4);
+        assert_eq!(hand.get(33), 0);
+        assert_eq!(hand.get(16), 0);
+        assert_eq!(hand.count(), 10);
+
+        assert_eq!(Hand34::from_tiles(&[1, -5, -5, 34]), {
+            let mut expected = Hand34::new();
+            expected.add(0, 1);
+            expected.add(4, 2);
+            expected.add(33, 1);
+            expected
+        });
+        assert!(Hand34::new().is_empty());
+    }
+}
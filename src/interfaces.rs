@@ -1,13 +1,33 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HairiResult {
     pub now: i8,
     pub wait: Vec<i8>,
     pub waits_after_discard: Vec<(i8, Vec<i8>)>,
+    #[serde(default)]
+    pub riichi_after_discard: Vec<RiichiDeclaration>, // only for closed tenpai 14-tile hands
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RiichiDeclaration {
+    pub discard: i8,
+    pub waits: Vec<i8>,
+    pub live_tiles: i8,                    // copies of waits not in own hand
+    pub has_yaku_without_riichi: bool,     // every wait has yaku on ron even without riichi
+    pub values: Vec<RiichiDeclarationWin>, // value of riichi for every wait
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RiichiDeclarationWin {
+    pub tile: i8,
+    pub ron_han: i32,
+    pub ron_ten: i32,
+    pub tsumo_han: i32,
+    pub tsumo_ten: i32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RiichiOptions {
     pub dora: Vec<i8>,
     pub aka_count: i8,
@@ -15,6 +35,10 @@ pub struct RiichiOptions {
     pub riichi: bool,
     pub ippatsu: bool,
     pub double_riichi: bool,
+    #[serde(default)]
+    pub open_riichi: bool, // riichi (or double riichi) was declared with open hand
+    #[serde(default)]
+    pub open_riichi_deal_in: bool, // ron on open riichi from a player who is not in riichi
     pub last_tile: bool,               // haitei/houtei
     pub after_kan: bool,               // chankan/rinshan
     pub tile_discarded_by_someone: i8, // -1 if tsumo
@@ -29,13 +53,62 @@ pub struct RiichiOptions {
     pub allow_double_yakuman: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+// Options of a closed hand without riichi and dora, east round, south seat, open tanyao allowed.
+// Tests override only the fields they check.
+#[cfg(test)]
+impl RiichiOptions {
+    pub(crate) fn for_tests() -> Self {
+        RiichiOptions {
+            dora: vec![],
+            aka_count: 0,
+            first_take: false,
+            riichi: false,
+            ippatsu: false,
+            double_riichi: false,
+            open_riichi: false,
+            open_riichi_deal_in: false,
+            last_tile: false,
+            after_kan: false,
+            tile_discarded_by_someone: -1,
+            bakaze: crate::constants::Tiles::E as i8,
+            jikaze: crate::constants::Tiles::S as i8,
+            allow_aka: false,
+            allow_kuitan: true,
+            with_kiriage: false,
+            disabled_yaku: vec![],
+            local_yaku_enabled: vec![],
+            all_local_yaku_enabled: false,
+            allow_double_yakuman: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RiichiHand {
     pub closed_part: Vec<i8>,
     pub open_part: Vec<(bool, Vec<i8>)>, // (isOpenMeld, tiles)
 }
 
-#[derive(Serialize, Deserialize)]
+// Hand without melds, tests push melds to open_part when they need them
+#[cfg(test)]
+impl RiichiHand {
+    pub(crate) fn for_tests(closed_part: Vec<crate::constants::Tiles>) -> Self {
+        RiichiHand {
+            closed_part: closed_part.into_iter().map(|t| t as i8).collect(),
+            open_part: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum WinKind {
+    Tsumo(i8), // drawn tile, it is in closed part of the hand
+    // discarded (or added to kan) tile, it is not in closed part of the hand.
+    // Seat of the discarder is relative to the winner: 1 - shimocha, 2 - toimen, 3 - kamicha.
+    Ron { tile: i8, from_seat: i8 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RiichiResult {
     pub is_agari: bool,
     pub yakuman: i8,
@@ -46,3 +119,494 @@ pub struct RiichiResult {
     pub yaku: Vec<(i8, i8)>,              // (yaku_id, han_count)
     pub hairi: Option<HairiResult>,
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct FuritenOptions {
+    pub discards: Vec<i8>,     // own discards, including tiles called by others
+    pub passed_tiles: Vec<i8>, // tiles not taken for ron since own last discard
+    pub riichi: bool,          // if the player is in riichi
+    pub passed_since_riichi: Vec<i8>, // tiles not taken for ron since riichi declaration
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FuritenResult {
+    pub waits: Vec<i8>,
+    pub permanent: bool, // one of the waits is in own discards
+    pub temporary: bool, // one of the waits was passed since own last discard
+    pub riichi: bool,    // one of the waits was passed after riichi
+    pub is_furiten: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TenpaiWait {
+    pub tile: i8,
+    pub remaining: i8,               // unseen copies of the tile
+    pub ron: Option<RiichiResult>,   // nothing if there is no yaku on ron
+    pub tsumo: Option<RiichiResult>, // nothing if there is no yaku on tsumo
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TenpaiResult {
+    pub waits: Vec<TenpaiWait>,
+    pub remaining: i8, // unseen copies of all waits
+    pub ron_ev: f32,   // expected ron points over unseen copies of all waits
+    pub tsumo_ev: f32, // expected tsumo points over unseen copies of all waits
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RiichiAnkanResult {
+    pub lenient: bool, // waits are not changed by kan
+    pub strict: bool,  // waits and hand decomposition are not changed by kan
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KuikaeRules {
+    pub forbid_genbutsu: bool, // same tile as the called one can't be discarded
+    pub forbid_suji: bool,     // tile on the other end of chi can't be discarded
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AbortiveDrawRules {
+    pub kyuushu_kyuuhai: bool, // nine different terminals and honors on the first draw
+    pub suufon_renda: bool,    // same wind discarded by all players on the first go-around
+    pub suucha_riichi: bool,   // all four players declared riichi
+    pub suukaikan: bool,       // four kans declared by more than one player
+    pub sanchahou: bool,       // three players call ron on the same discard
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum CallOption {
+    Chi(Vec<i8>),       // meld tiles, including the called one
+    Pon(Vec<i8>),       // meld tiles, including the called one
+    Daiminkan(Vec<i8>), // meld tiles, including the called one
+    Ron(RiichiResult),
+    Ankan(i8),
+    Shouminkan(i8),
+    Tsumo(RiichiResult),
+    Kyuushu,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoundRules {
+    pub allow_aka: bool, // one red five of every suit in the wall
+    pub allow_kuitan: bool,
+    pub with_kiriage: bool,
+    pub multiple_ron: bool, // all players calling ron win, otherwise only the first one in turn order
+    pub multiple_ron_honba: bool, // every winner of multiple ron takes honba, otherwise only the first one
+    pub strict_ankan: bool,       // ankan after riichi can't change hand decomposition
+    pub kuikae: KuikaeRules,
+    pub abortive: AbortiveDrawRules,
+    pub disabled_yaku: Vec<i8>,
+    pub local_yaku_enabled: Vec<i8>,
+    pub all_local_yaku_enabled: bool,
+    pub allow_double_yakuman: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoundConfig {
+    pub seed: u64,
+    pub dealer: i8, // seat 0-3
+    pub bakaze: i8,
+    pub honba: i8,
+    pub riichi_sticks: i8, // deposits left from previous hands
+    pub scores: Vec<i32>,  // by seat
+    pub rules: RoundRules,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RoundAction {
+    Discard(i8),
+    Riichi(i8), // declare riichi with the discard
+    Tsumo,
+    Ankan(i8),
+    Shouminkan(i8),
+    Kyuushu,
+    Ron,
+    Chi(Vec<i8>), // meld tiles, including the called one
+    Pon,
+    Daiminkan,
+    Pass,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RoundPhase {
+    Turn(i8),  // seat should discard or declare something on own tile
+    Calls(i8), // players may call the tile discarded (or added to kan) by the seat
+    Ended,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum RoundEvent {
+    Draw { seat: i8, tile: i8 },
+    Discard { seat: i8, tile: i8, riichi: bool },
+    RiichiAccepted { seat: i8 },
+    Chi { seat: i8, from: i8, tiles: Vec<i8> },
+    Pon { seat: i8, from: i8, tiles: Vec<i8> },
+    Daiminkan { seat: i8, from: i8, tiles: Vec<i8> },
+    Ankan { seat: i8, tile: i8 },
+    Shouminkan { seat: i8, tile: i8 },
+    DoraIndicator { tile: i8 },
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RoundEnd {
+    Agari,
+    Ryuukyoku,
+    KyuushuKyuuhai,
+    SuufonRenda,
+    SuuchaRiichi,
+    Suukaikan,
+    Sanchahou,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoundWin {
+    pub seat: i8,
+    pub from: i8, // discarder seat, or -1 on tsumo
+    pub result: RiichiResult,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub end: RoundEnd,
+    pub wins: Vec<RoundWin>,
+    pub tenpai: Vec<bool>, // by seat, filled on exhaustive draw only
+    pub deltas: Vec<i32>,  // score changes by seat, including riichi deposits
+    pub scores: Vec<i32>,
+    pub riichi_sticks: i8, // deposits left on the table
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlacementRules {
+    pub start_points: i32,
+    pub return_points: i32,
+    pub oka: Option<i32>, // bonus for the first place, (return - start) * 4 if nothing
+    // Uma by place: one row, or five rows by count of players with return points or more (0 to 4)
+    pub uma: Vec<Vec<i32>>,
+    pub split_ties: bool, // tied players share uma and oka equally, otherwise seat order wins
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PlacementResult {
+    pub places: Vec<i8>, // by seat, starting from 1; tied players share the place if ties are split
+    pub points: Vec<i32>, // scores with return points, oka and uma applied
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameRules {
+    pub hanchan: bool,             // east and south rounds, otherwise east only
+    pub placement: PlacementRules, // return points are also the score to reach in extension round
+    pub west_extension: bool,
+    pub agari_yame: bool, // dealer may stop the game when leading in the last hand
+    pub tenpai_renchan: bool, // dealer keeps the seat when tenpai on exhaustive draw
+    pub tobi: bool,       // game ends when someone goes below zero
+    pub multiple_ron_honba: bool, // every winner of multiple ron takes honba, otherwise only the first one
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum HandOutcome {
+    Agari(Vec<RoundWin>), // wins in turn order from the discarder
+    Ryuukyoku(Vec<bool>), // tenpai by seat
+    Abortive,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameResult {
+    pub scores: Vec<i32>, // including leftover riichi deposits
+    pub places: Vec<i8>,  // by seat, starting from 1
+    pub points: Vec<i32>, // scores with return points, oka and uma applied
+}
+
+// Tiles in game logs are in 136 format: tile is index / 4 + 1, red fives are the first copies of 5m, 5p and 5s
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LogEvent {
+    Draw {
+        seat: i8,
+        tile: u8,
+    },
+    Discard {
+        seat: i8,
+        tile: u8,
+        tsumogiri: bool,
+        riichi: bool,
+    },
+    Chi {
+        seat: i8,
+        from: i8,
+        tiles: Vec<u8>,
+        called: u8,
+    },
+    Pon {
+        seat: i8,
+        from: i8,
+        tiles: Vec<u8>,
+        called: u8,
+    },
+    Daiminkan {
+        seat: i8,
+        from: i8,
+        tiles: Vec<u8>,
+        called: u8,
+    },
+    Ankan {
+        seat: i8,
+        tiles: Vec<u8>,
+    },
+    Shouminkan {
+        seat: i8,
+        tile: u8,
+    },
+    Dora {
+        indicator: u8,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogAgari {
+    pub seat: i8,
+    pub from: i8, // same as seat on tsumo
+    pub pao: i8,  // same as seat if there is no pao
+    pub han: i32,
+    pub fu: i32,  // 0 if not recorded
+    pub ten: i32, // hand value without honba and riichi deposits
+    pub yakuman: i8,
+    pub yaku: Vec<(i8, i8)>, // (yaku_id, han_count), 13 for yakuman
+    pub dora_indicators: Vec<u8>,
+    pub uradora_indicators: Vec<u8>,
+    pub deltas: Vec<i32>, // by seat, including honba and riichi deposits
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogRyuukyoku {
+    pub end: RoundEnd,
+    pub deltas: Vec<i32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogRound {
+    pub bakaze: i8,
+    pub dealer: i8,
+    pub honba: i8,
+    pub riichi_sticks: i8,
+    pub scores: Vec<i32>,
+    pub haipai: Vec<Vec<u8>>,
+    pub dora_indicators: Vec<u8>, // including kan dora
+    pub events: Vec<LogEvent>,
+    pub agari: Vec<LogAgari>,
+    pub ryuukyoku: Option<LogRyuukyoku>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LogGame {
+    pub players: Vec<String>,
+    pub aka: bool,
+    pub rounds: Vec<LogRound>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayMismatch {
+    pub source: String, // log file name
+    pub round: usize,   // round index in the log
+    pub agari: usize,   // agari index in the round
+    pub hand: Option<RiichiHand>,
+    pub options: Option<RiichiOptions>,
+    #[serde(default)]
+    pub win: Option<WinKind>,
+    pub expected: LogAgari,
+    pub computed: Option<RiichiResult>,
+    pub computed_deltas: Vec<i32>, // to compare with deltas of expected agari
+    pub differences: Vec<String>,  // "han", "fu", "yakuman", "yaku", "payments" or an error
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ReplayReport {
+    pub files: usize,
+    pub hands: usize,
+    pub errors: Vec<(String, String)>, // (file name, error) for logs which can't be parsed
+    pub mismatches: Vec<ReplayMismatch>,
+}
+
+// mjai protocol events. Tiles are named "1m".."9m", "1p".., "1s".., "E", "S", "W", "N", "P", "F", "C",
+// red fives are "5mr", "5pr", "5sr" and unknown tiles are "?"
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MjaiEvent {
+    StartGame {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<i8>, // own seat when sent to a bot
+        #[serde(default)]
+        names: Vec<String>,
+    },
+    StartKyoku {
+        bakaze: String,
+        kyoku: i8, // 1-4
+        honba: i8,
+        kyotaku: i8,
+        oya: i8,
+        dora_marker: String,
+        scores: Vec<i32>,
+        tehais: Vec<Vec<String>>,
+    },
+    Tsumo {
+        actor: i8,
+        pai: String,
+    },
+    Dahai {
+        actor: i8,
+        pai: String,
+        tsumogiri: bool,
+    },
+    Chi {
+        actor: i8,
+        target: i8,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Pon {
+        actor: i8,
+        target: i8,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Daiminkan {
+        actor: i8,
+        target: i8,
+        pai: String,
+        consumed: Vec<String>,
+    },
+    Ankan {
+        actor: i8,
+        consumed: Vec<String>,
+    },
+    Kakan {
+        actor: i8,
+        pai: String,
+        consumed: Vec<String>, // tiles of the pon
+    },
+    Dora {
+        dora_marker: String,
+    },
+    Reach {
+        actor: i8,
+    },
+    ReachAccepted {
+        actor: i8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scores: Option<Vec<i32>>,
+    },
+    Hora {
+        actor: i8,
+        target: i8,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pai: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        uradora_markers: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scores: Option<Vec<i32>>,
+    },
+    Ryukyoku {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scores: Option<Vec<i32>>,
+    },
+    EndKyoku,
+    EndGame,
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum HandForm {
+    Regular, // four melds and a pair
+    Chiitoitsu,
+    Kokushi,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ShantenForms {
+    pub regular: i8,
+    pub chiitoitsu: Option<i8>, // none for hands with melds
+    pub kokushi: Option<i8>,    // none for hands with melds
+    pub best: HandForm,         // form with the lowest shanten, regular one on ties
+    pub shanten: i8,            // shanten of the best form
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct UkeireTile {
+    pub tile: i8,
+    pub remaining: i8, // copies not in own hand and not visible
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UkeireResult {
+    pub shanten: i8,
+    pub tiles: Vec<UkeireTile>, // tiles lowering shanten
+    pub total: i32,             // sum of remaining copies
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UkeireDiscard {
+    pub discard: i8,
+    pub result: UkeireResult, // for the hand after discard
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum WaitQuality {
+    Good, // waiting on more than one tile kind, like ryanmen or nobetan
+    Poor, // kanchan, penchan, tanki or shanpon
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TenpaiShape {
+    pub draw: i8,    // improving tile
+    pub discard: i8, // best discard after it
+    pub waits: Vec<i8>,
+    pub remaining: i8, // unseen copies of waits
+    pub quality: WaitQuality,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HandEvaluation {
+    pub shanten: i8,
+    pub ukeire: i32,                     // unseen copies of tiles lowering shanten
+    pub ukeire2: i32, // sum of copies of every improving tile times acceptance after the best discard
+    pub tenpai_shapes: Vec<TenpaiShape>, // for 1-shanten hands: tenpai after every improving tile
+    pub good_shape_tiles: i32, // copies of tiles giving good shape tenpai, or of waits if the hand is good shape tenpai
+    pub tenpai_probability: f32, // chance to reach tenpai within given number of draws
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DiscardEvaluation {
+    pub discard: i8,
+    pub evaluation: HandEvaluation, // for the hand after discard
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OpponentPond {
+    pub discards: Vec<i8>, // in discard order, including tiles called by others
+    pub riichi_discard: Option<usize>, // index of riichi declaration tile in discards, nothing if not in riichi
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DangerRules {
+    pub genbutsu: f32,         // danger of tiles discarded by the opponent
+    pub terminal: f32,         // base danger of 1 and 9
+    pub edge: f32,             // base danger of 2 and 8
+    pub middle: f32,           // base danger of 3 to 7
+    pub honors: [f32; 4],      // danger of honor tile by its visible copies, 0 to 3
+    pub suji: f32,             // multiplier of base danger for suji
+    pub no_chance: f32,        // multiplier of base danger for no-chance
+    pub one_chance: f32,       // multiplier of base danger for one-chance
+    pub early_outside: f32,    // multiplier of base danger for early-outside tiles
+    pub early_discards: usize, // count of first discards treated as early, only ones before riichi are taken
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TileSafety {
+    pub tile: i8,
+    pub genbutsu: bool,      // discarded by the opponent
+    pub suji: bool,          // every ryanmen wait on the tile is furiten for the opponent
+    pub no_chance: bool,     // every ryanmen wait on the tile needs a tile with all copies visible
+    pub one_chance: bool, // every ryanmen wait on the tile needs a tile with one unseen copy at most
+    pub visible: i8,      // visible copies, including own hand
+    pub early_outside: bool, // closer to terminal than a tile of the same suit discarded early
+    pub danger: f32,
+}
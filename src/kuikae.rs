@@ -0,0 +1,220 @@
+use crate::constants::{HONORS, is_proper_open_set};
+use crate::interfaces::{KuikaeRules, RiichiHand};
+use crate::riichi::split_hand;
+
+// Tile on the other end of chi (e.g. 7m for 4m called with 56m), or -1 if there is none
+fn suji_tile(meld: &[i8], called_tile: i8) -> i8 {
+    if meld[0] == meld[1] {
+        return -1;
+    }
+
+    let (swap, bound) = if called_tile == meld[0] {
+        (called_tile + 3, meld[0] + 8 - (meld[0] - 1) % 9)
+    } else if called_tile == meld[2] {
+        (called_tile - 3, meld[2] - (meld[2] - 1) % 9)
+    } else {
+        return -1;
+    };
+
+    if (called_tile < swap && swap > bound) || (called_tile > swap && swap < bound) {
+        -1
+    } else {
+        swap
+    }
+}
+
+// Check if the tile can be discarded after chi or pon.
+// Hand is the hand before the call, meld includes the called tile.
+pub fn check_kuikae(
+    hand: &RiichiHand,
+    meld: &[i8],
+    called_tile: i8,
+    discard: i8,
+    rules: &KuikaeRules,
+) -> Result<bool, String> {
+    let mut meld = meld.to_vec();
+    meld.sort();
+    if meld.len() != 3
+        || !is_proper_open_set(&meld)
+        || (meld[0] != meld[1] && HONORS.contains(&meld[0]))
+    {
+        return Err("Incorrect meld".parse().unwrap());
+    }
+
+    let (mut haipai, _furo) = split_hand(hand);
+    let mut taken = false;
+    for tile in &meld {
+        if *tile == called_tile && !taken {
+            taken = true;
+            continue;
+        }
+        match haipai.iter().position(|t| t == tile) {
+            Some(pos) => {
+                haipai.remove(pos);
+            }
+            None => return Err("Meld tiles are not in hand".parse().unwrap()),
+        }
+    }
+
+    if !taken {
+        return Err("Called tile is not in meld".parse().unwrap());
+    }
+
+    if !haipai.contains(&discard) {
+        return Err("Discard is not in hand".parse().unwrap());
+    }
+
+    if rules.forbid_genbutsu && discard == called_tile {
+        return Ok(false);
+    }
+
+    if rules.forbid_suji && discard == suji_tile(&meld, called_tile) {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles;
+
+    fn hand() -> RiichiHand {
+        RiichiHand {
+            closed_part: vec![
+                Tiles::M1 as i8,
+                Tiles::M4 as i8,
+                Tiles::M5 as i8,
+                Tiles::M6 as i8,
+                Tiles::M7 as i8,
+                Tiles::M9 as i8,
+                Tiles::P5 as i8,
+                Tiles::P5 as i8,
+                Tiles::P7 as i8,
+                Tiles::S2 as i8,
+                Tiles::S3 as i8,
+                Tiles::E as i8,
+                Tiles::E as i8,
+            ],
+            open_part: vec![],
+        }
+    }
+
+    fn rules() -> KuikaeRules {
+        KuikaeRules {
+            forbid_genbutsu: true,
+            forbid_suji: true,
+        }
+    }
+
+    #[test]
+    pub fn should_forbid_genbutsu_after_chi_and_pon() {
+        let chi = [Tiles::M3 as i8, Tiles::M4 as i8, Tiles::M5 as i8];
+        assert_eq!(
+            check_kuikae(&hand(), &chi, Tiles::M3 as i8, Tiles::M1 as i8, &rules()),
+            Ok(true)
+        );
+        // 3m is not in hand
+        assert!(check_kuikae(&hand(), &chi, Tiles::M3 as i8, Tiles::M3 as i8, &rules()).is_err());
+
+        let pon = [Tiles::P5 as i8, Tiles::P5 as i8, Tiles::P5 as i8];
+        let mut hand = hand();
+        hand.closed_part.push(Tiles::P5 as i8);
+        hand.closed_part.remove(0);
+        assert_eq!(
+            check_kuikae(&hand, &pon, Tiles::P5 as i8, Tiles::P5 as i8, &rules()),
+            Ok(false)
+        );
+        assert_eq!(
+            check_kuikae(&hand, &pon, Tiles::P5 as i8, Tiles::P7 as i8, &rules()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    pub fn should_forbid_suji_after_chi() {
+        // 4m called with 56m: 7m is forbidden
+        let chi = [Tiles::M4 as i8, Tiles::M5 as i8, Tiles::M6 as i8];
+        let mut hand_with_8m = hand();
+        hand_with_8m.closed_part[1] = Tiles::M8 as i8;
+        assert_eq!(
+            check_kuikae(
+                &hand_with_8m,
+                &chi,
+                Tiles::M4 as i8,
+                Tiles::M7 as i8,
+                &rules()
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            check_kuikae(
+                &hand_with_8m,
+                &chi,
+                Tiles::M4 as i8,
+                Tiles::M7 as i8,
+                &KuikaeRules {
+                    forbid_genbutsu: true,
+                    forbid_suji: false,
+                }
+            ),
+            Ok(true)
+        );
+
+        // 7m called with 56m: 4m is forbidden
+        let chi = [Tiles::M5 as i8, Tiles::M6 as i8, Tiles::M7 as i8];
+        assert_eq!(
+            check_kuikae(&hand(), &chi, Tiles::M7 as i8, Tiles::M4 as i8, &rules()),
+            Ok(false)
+        );
+
+        // kanchan call: no suji swap
+        let chi = [Tiles::M4 as i8, Tiles::M5 as i8, Tiles::M6 as i8];
+        assert_eq!(
+            check_kuikae(&hand(), &chi, Tiles::M5 as i8, Tiles::M7 as i8, &rules()),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    pub fn should_not_go_over_suit_bounds() {
+        // 7s called with 89s has no swap tile over 9s, 9s called with 78s forbids 6s
+        assert_eq!(
+            suji_tile(
+                &[Tiles::S7 as i8, Tiles::S8 as i8, Tiles::S9 as i8],
+                Tiles::S7 as i8
+            ),
+            -1
+        );
+        assert_eq!(
+            suji_tile(
+                &[Tiles::S7 as i8, Tiles::S8 as i8, Tiles::S9 as i8],
+                Tiles::S9 as i8
+            ),
+            Tiles::S6 as i8
+        );
+        assert_eq!(
+            suji_tile(
+                &[Tiles::P1 as i8, Tiles::P2 as i8, Tiles::P3 as i8],
+                Tiles::P3 as i8
+            ),
+            -1
+        );
+        assert_eq!(
+            suji_tile(
+                &[Tiles::P1 as i8, Tiles::P2 as i8, Tiles::P3 as i8],
+                Tiles::P1 as i8
+            ),
+            Tiles::P4 as i8
+        );
+    }
+
+    #[test]
+    pub fn should_fail_on_wrong_meld() {
+        let chi = [Tiles::E as i8, Tiles::S as i8, Tiles::W as i8];
+        assert!(check_kuikae(&hand(), &chi, Tiles::S as i8, Tiles::M1 as i8, &rules()).is_err());
+        let chi = [Tiles::M1 as i8, Tiles::M2 as i8, Tiles::M3 as i8];
+        assert!(check_kuikae(&hand(), &chi, Tiles::M3 as i8, Tiles::M9 as i8, &rules()).is_err());
+    }
+}
@@ -1,14 +1,72 @@
 #![forbid(unsafe_code)]
 
+mod abortive;
 mod agari;
+mod ankan;
+mod batch;
+mod calls;
 mod constants;
+mod defense;
+mod furiten;
+mod game;
+mod hand34;
 mod interfaces;
+mod kuikae;
+mod mjai;
+mod mpsz;
+mod placement;
+mod replay;
 mod riichi;
+mod round;
 mod shanten;
+#[cfg(feature = "shanten-table")]
+mod shanten_table;
+mod table;
+mod tenhou;
+mod tenpai;
+mod ukeire;
 mod yaku;
 
+pub use crate::abortive::{
+    check_kyuushu_kyuuhai, check_sanchahou, check_suucha_riichi, check_suufon_renda,
+    check_suukaikan,
+};
 pub use crate::agari::find_all_agari_patterns;
+pub use crate::ankan::check_riichi_ankan;
+pub use crate::batch::{calc_riichi_batch, calc_riichi_iter};
+pub use crate::calls::{find_discard_calls, find_draw_actions};
 pub use crate::constants::{Tiles, Yaku};
-pub use crate::interfaces::{RiichiHand, RiichiOptions, RiichiResult};
+pub use crate::defense::analyze_danger;
+pub use crate::furiten::check_furiten;
+pub use crate::game::Game;
+pub use crate::hand34::Hand34;
+pub use crate::interfaces::{
+    AbortiveDrawRules, CallOption, DangerRules, DiscardEvaluation, FuritenOptions, FuritenResult,
+    GameResult, GameRules, HandEvaluation, HandForm, HandOutcome, KuikaeRules, LogAgari, LogEvent,
+    LogGame, LogRound, LogRyuukyoku, MjaiEvent, OpponentPond, PlacementResult, PlacementRules,
+    RiichiAnkanResult, RiichiHand, RiichiOptions, RiichiResult, RoundAction, RoundConfig, RoundEnd,
+    RoundEvent, RoundPhase, RoundResult, RoundRules, RoundWin, ShantenForms, TenpaiResult,
+    TenpaiShape, TenpaiWait, TileSafety, UkeireDiscard, UkeireResult, UkeireTile, WaitQuality,
+    WinKind,
+};
+pub use crate::kuikae::check_kuikae;
+pub use crate::mjai::{MjaiState, parse_mjai};
+pub use crate::mpsz::{format_mpsz, parse_mpsz};
+pub use crate::placement::calc_placement;
+pub use crate::replay::{verify_dir, verify_game, verify_round};
+#[allow(deprecated)]
 pub use crate::riichi::calc_riichi;
-pub use crate::shanten::calc_shanten;
+pub use crate::riichi::calc_win;
+pub use crate::round::Round;
+pub use crate::shanten::{
+    calc_shanten, calc_shanten_for_form, calc_shanten_for_hand, calc_shanten_forms, hairi,
+    hairi_for_form, hairi_for_hand,
+};
+#[cfg(feature = "shanten-table")]
+pub use crate::shanten_table::init_shanten_table;
+pub use crate::table::dora_from_indicator;
+pub use crate::tenhou::{agari_hands, parse_mjlog, parse_tenhou_json};
+pub use crate::tenpai::analyze_tenpai;
+pub use crate::ukeire::{
+    calc_ukeire, calc_ukeire_for_form, evaluate_discards, evaluate_hand, rank_discards,
+};
@@ -0,0 +1,956 @@
+use crate::agari::find_waits;
+use crate::calls::{find_discard_calls, find_draw_actions};
+use crate::constants::tiles_to_34;
+use crate::interfaces::{
+    CallOption, HairiResult, MjaiEvent, RiichiHand, RiichiOptions, RiichiResult, RoundRules,
+    WinKind,
+};
+use crate::kuikae::check_kuikae;
+use crate::riichi::{calc_win, split_hand};
+use crate::shanten::hairi_for_hand;
+use crate::table::{CallInfo, LIVE_TILES, Meld, Player, RED_FIVES, TableInfo, error, tile_id};
+
+const HONOR_NAMES: [&str; 7] = ["E", "S", "W", "N", "P", "F", "C"];
+
+// Tile in 136 format from mjai name: red fives are the first copies, other fives are the second ones
+fn mjai_tile(name: &str) -> Result<u8, String> {
+    if let Some(pos) = HONOR_NAMES.iter().position(|n| *n == name) {
+        return Ok(((27 + pos) * 4) as u8);
+    }
+    let bytes = name.as_bytes();
+    let red = bytes.len() == 3 && bytes[2] == b'r';
+    if !(bytes.len() == 2 || red) {
+        return error("Incorrect tile");
+    }
+    let value = (bytes[0] as char).to_digit(10).unwrap_or(0) as usize;
+    let suit = match bytes[1] {
+        b'm' => 0,
+        b'p' => 1,
+        b's' => 2,
+        _ => return error("Incorrect tile"),
+    };
+    if !(1..=9).contains(&value) || (red && value != 5) {
+        return error("Incorrect tile");
+    }
+    let copy = if value == 5 && !red { 1 } else { 0 };
+    Ok(((suit * 9 + value - 1) * 4 + copy) as u8)
+}
+
+fn mjai_name(tile: u8) -> String {
+    let id = tile_id(tile);
+    if id > 27 {
+        return HONOR_NAMES[(id - 28) as usize].to_string();
+    }
+    let suit = ["m", "p", "s"][((id - 1) / 9) as usize];
+    let red = if RED_FIVES.contains(&tile) { "r" } else { "" };
+    format!("{}{}{}", (id - 1) % 9 + 1, suit, red)
+}
+
+// Parse a line from mjai server: a single event or an array of events
+pub fn parse_mjai(line: &str) -> Result<Vec<MjaiEvent>, String> {
+    if line.trim_start().starts_with('[') {
+        serde_json::from_str(line).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(line)
+            .map(|event| vec![event])
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Game state rebuilt from mjai events, from the point of view of a bot or of an observer
+pub struct MjaiState {
+    rules: RoundRules,
+    seat: Option<i8>,
+    names: Vec<String>,
+    bakaze: i8,
+    dealer: usize,
+    honba: i8,
+    riichi_sticks: i8,
+    scores: Vec<i32>,
+    dora_indicators: Vec<u8>,
+    tiles_left: i8,
+    players: Vec<Player>,
+    unknown: Vec<usize>,  // hidden tiles of other players by seat
+    reach: Option<usize>, // riichi is declared, but the tile is not discarded yet
+    no_calls: bool,
+    kan: bool,     // next draw is from the dead wall
+    rinshan: bool, // last draw was from the dead wall
+    last: Option<MjaiEvent>,
+    last_call: Option<CallInfo>,
+    passed: Option<(usize, i8)>, // discard or added kan tile which may still be taken for ron
+}
+
+impl MjaiState {
+    pub fn new(rules: RoundRules) -> MjaiState {
+        MjaiState {
+            rules,
+            seat: None,
+            names: Vec::new(),
+            bakaze: 28,
+            dealer: 0,
+            honba: 0,
+            riichi_sticks: 0,
+            scores: vec![0; 4],
+            dora_indicators: Vec::new(),
+            tiles_left: LIVE_TILES,
+            players: vec![Player::default(); 4],
+            unknown: vec![0; 4],
+            reach: None,
+            no_calls: true,
+            kan: false,
+            rinshan: false,
+            last: None,
+            last_call: None,
+            passed: None,
+        }
+    }
+
+    // Own seat from start_game event, if the state is tracked for a bot
+    pub fn seat(&self) -> Option<i8> {
+        self.seat
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn bakaze(&self) -> i8 {
+        self.bakaze
+    }
+
+    pub fn dealer(&self) -> i8 {
+        self.dealer as i8
+    }
+
+    pub fn honba(&self) -> i8 {
+        self.honba
+    }
+
+    pub fn riichi_sticks(&self) -> i8 {
+        self.riichi_sticks
+    }
+
+    pub fn scores(&self) -> Vec<i32> {
+        self.scores.clone()
+    }
+
+    pub fn tiles_left(&self) -> i8 {
+        self.tiles_left
+    }
+
+    pub fn dora_indicators(&self) -> Vec<i8> {
+        self.dora_indicators.iter().map(|t| tile_id(*t)).collect()
+    }
+
+    // Known tiles of the player: hidden tiles of other players are not included
+    pub fn hand(&self, seat: i8) -> RiichiHand {
+        self.players[seat as usize].hand()
+    }
+
+    pub fn discards(&self, seat: i8) -> Vec<i8> {
+        self.players[seat as usize].discards.clone()
+    }
+
+    pub fn is_riichi(&self, seat: i8) -> bool {
+        self.players[seat as usize].riichi
+    }
+
+    pub fn update(&mut self, event: &MjaiEvent) -> Result<(), String> {
+        // Tile which was not taken for ron is passed by everyone else
+        if !matches!(event, MjaiEvent::Hora { .. }) {
+            if let Some((from, tile)) = self.passed.take() {
+                for (seat, player) in self.players.iter_mut().enumerate() {
+                    if seat != from {
+                        player.passed_tiles.push(tile);
+                        if player.riichi {
+                            player.passed_since_riichi.push(tile);
+                        }
+                    }
+                }
+            }
+        }
+
+        match event {
+            MjaiEvent::StartGame { id, names } => {
+                self.seat = *id;
+                self.names = names.clone();
+            }
+            MjaiEvent::StartKyoku {
+                bakaze,
+                honba,
+                kyotaku,
+                oya,
+                dora_marker,
+                scores,
+                tehais,
+                ..
+            } => {
+                self.bakaze = match HONOR_NAMES[..4].iter().position(|n| n == bakaze) {
+                    Some(pos) => 28 + pos as i8,
+                    None => return error("Incorrect round wind"),
+                };
+                if !(0..4).contains(oya) || tehais.len() != 4 {
+                    return error("Incorrect number of players");
+                }
+                self.dealer = *oya as usize;
+                self.honba = *honba;
+                self.riichi_sticks = *kyotaku;
+                self.scores = scores.clone();
+                self.dora_indicators = vec![mjai_tile(dora_marker)?];
+                self.tiles_left = LIVE_TILES;
+                self.no_calls = true;
+                self.kan = false;
+                self.rinshan = false;
+                self.last_call = None;
+                self.reach = None;
+                self.players = Vec::new();
+                self.unknown = Vec::new();
+                for tiles in tehais {
+                    let mut player = Player::default();
+                    let mut unknown = 0;
+                    for tile in tiles {
+                        if tile == "?" {
+                            unknown += 1;
+                        } else {
+                            player.hand.push(mjai_tile(tile)?);
+                        }
+                    }
+                    self.players.push(player);
+                    self.unknown.push(unknown);
+                }
+            }
+            MjaiEvent::Tsumo { actor, pai } => {
+                let player = self.player(*actor)?;
+                if pai == "?" {
+                    self.unknown[*actor as usize] += 1;
+                } else {
+                    player.hand.push(mjai_tile(pai)?);
+                }
+                self.tiles_left -= 1;
+                self.rinshan = self.kan;
+                self.kan = false;
+                self.last_call = None;
+            }
+            MjaiEvent::Dahai { actor, pai, .. } => {
+                let tile = tile_id(self.take(*actor, pai)?);
+                let no_calls = self.no_calls;
+                let reach = self
+                    .reach
+                    .take_if(|seat| *seat == *actor as usize)
+                    .is_some();
+                let player = self.player(*actor)?;
+                player.ippatsu = reach;
+                if reach {
+                    player.double_riichi = no_calls && player.discards.is_empty();
+                    player.riichi = true;
+                }
+                player.discards.push(tile);
+                player.passed_tiles.clear();
+                self.last_call = None;
+                self.rinshan = false;
+                self.passed = Some((*actor as usize, tile));
+            }
+            MjaiEvent::Chi {
+                actor,
+                pai,
+                consumed,
+                ..
+            }
+            | MjaiEvent::Pon {
+                actor,
+                pai,
+                consumed,
+                ..
+            }
+            | MjaiEvent::Daiminkan {
+                actor,
+                pai,
+                consumed,
+                ..
+            } => {
+                let called = mjai_tile(pai)?;
+                let before = self.hand(*actor);
+                let mut tiles = vec![called];
+                for tile in consumed {
+                    tiles.push(self.take(*actor, tile)?);
+                }
+                tiles.sort();
+                if tiles.len() == 4 {
+                    self.kan = true;
+                } else {
+                    let mut meld: Vec<i8> = tiles.iter().map(|t| tile_id(*t)).collect();
+                    meld.sort();
+                    self.last_call = Some(CallInfo {
+                        hand: before,
+                        meld,
+                        called: tile_id(called),
+                    });
+                }
+                self.player(*actor)?.melds.push(Meld { open: true, tiles });
+                self.interrupt();
+            }
+            MjaiEvent::Ankan { actor, consumed } => {
+                let tiles = consumed
+                    .iter()
+                    .map(|t| self.take(*actor, t))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                self.player(*actor)?.melds.push(Meld { open: false, tiles });
+                self.kan = true;
+                self.interrupt();
+            }
+            MjaiEvent::Kakan { actor, pai, .. } => {
+                let added = self.take(*actor, pai)?;
+                let meld = self.player(*actor)?.melds.iter_mut().find(|m| {
+                    m.open && m.tiles.len() == 3 && tile_id(m.tiles[0]) == tile_id(added)
+                });
+                match meld {
+                    Some(meld) => meld.tiles.push(added),
+                    None => return error("Pon is not found"),
+                }
+                self.kan = true;
+                self.interrupt();
+                self.passed = Some((*actor as usize, tile_id(added)));
+            }
+            MjaiEvent::Dora { dora_marker } => {
+                self.dora_indicators.push(mjai_tile(dora_marker)?);
+            }
+            MjaiEvent::Reach { actor } => {
+                self.player(*actor)?;
+                self.reach = Some(*actor as usize);
+            }
+            MjaiEvent::ReachAccepted { actor, scores } => {
+                self.player(*actor)?;
+                match scores {
+                    Some(scores) => self.scores = scores.clone(),
+                    None => self.scores[*actor as usize] -= 1000,
+                }
+                self.riichi_sticks += 1;
+            }
+            MjaiEvent::Hora { scores, .. } | MjaiEvent::Ryukyoku { scores } => {
+                if let Some(scores) = scores {
+                    self.scores = scores.clone();
+                }
+                if matches!(event, MjaiEvent::Hora { .. }) {
+                    self.riichi_sticks = 0;
+                }
+            }
+            MjaiEvent::EndKyoku | MjaiEvent::EndGame | MjaiEvent::None => {}
+        }
+
+        // Remember the event other players may respond to
+        match event {
+            MjaiEvent::Tsumo { .. }
+            | MjaiEvent::Dahai { .. }
+            | MjaiEvent::Chi { .. }
+            | MjaiEvent::Pon { .. }
+            | MjaiEvent::Kakan { .. }
+            | MjaiEvent::Reach { .. } => self.last = Some(event.clone()),
+            // Double ron: the next hora is on the same tile
+            MjaiEvent::Dora { .. }
+            | MjaiEvent::ReachAccepted { .. }
+            | MjaiEvent::Hora { .. }
+            | MjaiEvent::None => {}
+            _ => self.last = None,
+        }
+        Ok(())
+    }
+
+    // Hand value for the player winning on the last drawn, discarded or added to kan tile.
+    // Uradora markers are taken from the hora event when the player is in riichi.
+    pub fn calc_hora(&self, seat: i8, uradora_markers: &[String]) -> Result<RiichiResult, String> {
+        if !(0..4).contains(&seat) {
+            return error("Incorrect seat");
+        }
+        let (tile, ron_tile, after_kan, from) = match &self.last {
+            Some(MjaiEvent::Tsumo { actor, pai }) if *actor == seat => {
+                (mjai_tile(pai)?, None, self.rinshan, seat)
+            }
+            Some(MjaiEvent::Dahai { actor, pai, .. }) if *actor != seat => {
+                let tile = mjai_tile(pai)?;
+                (tile, Some(tile), false, *actor)
+            }
+            Some(MjaiEvent::Kakan { actor, pai, .. }) if *actor != seat => {
+                let tile = mjai_tile(pai)?;
+                (tile, Some(tile), true, *actor)
+            }
+            _ => return error("No winning tile"),
+        };
+        let win = match ron_tile {
+            Some(_) => WinKind::Ron {
+                tile: tile_id(tile),
+                from_seat: (from + 4 - seat) % 4,
+            },
+            None => WinKind::Tsumo(tile_id(tile)),
+        };
+        let uradora = uradora_markers
+            .iter()
+            .map(|t| mjai_tile(t))
+            .collect::<Result<Vec<u8>, String>>()?;
+
+        let options = self.options(seat as usize, ron_tile, after_kan, &uradora);
+        let result = calc_win(&self.hand(seat), &options, win, false)?;
+        if !result.is_agari {
+            return error("Hand is not complete");
+        }
+        Ok(result)
+    }
+
+    // Shanten and useful tiles for the hand with its melds
+    pub fn calc_hairi(&self, seat: i8) -> Option<HairiResult> {
+        if !(0..4).contains(&seat) || self.unknown[seat as usize] > 0 {
+            return None;
+        }
+        hairi_for_hand(&self.hand(seat))
+    }
+
+    // Responses the player may send to the last event, in mjai format.
+    // Declining a call is the "none" event, it's listed only together with other options.
+    pub fn legal_actions(&self, seat: i8) -> Vec<MjaiEvent> {
+        if !(0..4).contains(&seat) || self.unknown[seat as usize] > 0 {
+            return Vec::new();
+        }
+        let seat_index = seat as usize;
+        match &self.last {
+            Some(MjaiEvent::Tsumo { actor, .. }) if *actor == seat => self.turn_actions(seat_index),
+            Some(MjaiEvent::Chi { actor, .. } | MjaiEvent::Pon { actor, .. }) if *actor == seat => {
+                self.discard_actions(seat_index, false)
+            }
+            Some(MjaiEvent::Reach { actor }) if *actor == seat => {
+                self.discard_actions(seat_index, true)
+            }
+            Some(MjaiEvent::Dahai { actor, pai, .. }) if *actor != seat => {
+                self.call_actions(seat_index, *actor as usize, pai, false)
+            }
+            Some(MjaiEvent::Kakan { actor, pai, .. }) if *actor != seat => {
+                self.call_actions(seat_index, *actor as usize, pai, true)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn player(&mut self, seat: i8) -> Result<&mut Player, String> {
+        match self.players.get_mut(seat as usize) {
+            Some(player) if seat >= 0 => Ok(player),
+            _ => error("Incorrect seat"),
+        }
+    }
+
+    // Remove tile from hand, or one of hidden tiles if it's not known
+    fn take(&mut self, seat: i8, name: &str) -> Result<u8, String> {
+        let tile = mjai_tile(name)?;
+        let player = self.player(seat)?;
+        if let Some(pos) = player.hand.iter().position(|t| *t == tile) {
+            return Ok(player.hand.remove(pos));
+        }
+        let unknown = &mut self.unknown[seat as usize];
+        if *unknown > 0 {
+            *unknown -= 1;
+            return Ok(tile);
+        }
+        error("Tile is not in hand")
+    }
+
+    fn interrupt(&mut self) {
+        self.no_calls = false;
+        for player in self.players.iter_mut() {
+            player.ippatsu = false;
+        }
+    }
+
+    fn kans_total(&self) -> usize {
+        self.players.iter().map(|p| p.kans() as usize).sum()
+    }
+
+    fn options(
+        &self,
+        seat: usize,
+        ron_tile: Option<u8>,
+        after_kan: bool,
+        uradora: &[u8],
+    ) -> RiichiOptions {
+        let table = TableInfo {
+            rules: &self.rules,
+            bakaze: self.bakaze,
+            dealer: self.dealer,
+            dora_indicators: &self.dora_indicators,
+            no_calls: self.no_calls,
+            tiles_left: self.tiles_left,
+        };
+        self.players[seat].options(seat, &table, ron_tile, after_kan, uradora)
+    }
+
+    // Distinct tile names in hand, red fives are listed separately
+    fn names_in_hand(&self, seat: usize) -> Vec<String> {
+        let mut tiles = self.players[seat].hand.clone();
+        tiles.sort();
+        tiles.dedup();
+        tiles.into_iter().map(mjai_name).collect()
+    }
+
+    // All distinct ways to take tiles from hand for a call
+    fn consumed_options(&self, seat: usize, tiles: &[i8]) -> Vec<Vec<String>> {
+        let hand: Vec<String> = self.players[seat]
+            .hand
+            .iter()
+            .map(|t| mjai_name(*t))
+            .collect();
+        let mut options: Vec<Vec<String>> = vec![Vec::new()];
+        for tile in tiles {
+            let mut next: Vec<Vec<String>> = Vec::new();
+            for option in &options {
+                for name in self.names_in_hand(seat) {
+                    let available = hand.iter().filter(|n| **n == name).count()
+                        > option.iter().filter(|n| **n == name).count();
+                    if !available || tile_id(mjai_tile(&name).unwrap()) != *tile {
+                        continue;
+                    }
+                    let mut consumed = option.clone();
+                    consumed.push(name);
+                    consumed.sort();
+                    if !next.contains(&consumed) {
+                        next.push(consumed);
+                    }
+                }
+            }
+            options = next;
+        }
+        options
+    }
+
+    fn turn_actions(&self, seat: usize) -> Vec<MjaiEvent> {
+        let player = &self.players[seat];
+        let drawn = match player.hand.last() {
+            Some(tile) => *tile,
+            None => return Vec::new(),
+        };
+        let hand = self.hand(seat as i8);
+        let options = self.options(seat, None, self.rinshan, &[]);
+        let kan_allowed = self.kans_total() < 4 && self.tiles_left > 0;
+        let actor = seat as i8;
+        let names_of = |tiles: &mut dyn Iterator<Item = &u8>, tile: i8| -> Vec<String> {
+            tiles
+                .filter(|t| tile_id(**t) == tile)
+                .map(|t| mjai_name(*t))
+                .collect()
+        };
+
+        let mut actions: Vec<MjaiEvent> = Vec::new();
+        for action in
+            find_draw_actions(&hand, &options, self.rules.strict_ankan).unwrap_or_default()
+        {
+            match action {
+                CallOption::Tsumo(_) => actions.push(MjaiEvent::Hora {
+                    actor,
+                    target: actor,
+                    pai: Some(mjai_name(drawn)),
+                    uradora_markers: None,
+                    scores: None,
+                }),
+                CallOption::Ankan(tile) if kan_allowed => actions.push(MjaiEvent::Ankan {
+                    actor,
+                    consumed: names_of(&mut player.hand.iter(), tile),
+                }),
+                CallOption::Shouminkan(tile) if kan_allowed => {
+                    let pon = player
+                        .melds
+                        .iter()
+                        .find(|m| m.open && m.tiles.len() == 3 && tile_id(m.tiles[0]) == tile);
+                    if let (Some(pai), Some(pon)) =
+                        (names_of(&mut player.hand.iter(), tile).pop(), pon)
+                    {
+                        actions.push(MjaiEvent::Kakan {
+                            actor,
+                            pai,
+                            consumed: pon.tiles.iter().map(|t| mjai_name(*t)).collect(),
+                        });
+                    }
+                }
+                CallOption::Kyuushu if self.rules.abortive.kyuushu_kyuuhai => {
+                    actions.push(MjaiEvent::Ryukyoku { scores: None })
+                }
+                _ => {}
+            }
+        }
+
+        if player.riichi {
+            actions.push(MjaiEvent::Dahai {
+                actor,
+                pai: mjai_name(drawn),
+                tsumogiri: true,
+            });
+            return actions;
+        }
+
+        let menzen = player.melds.iter().all(|m| !m.open);
+        if menzen
+            && self.scores.get(seat).is_some_and(|s| *s >= 1000)
+            && self.tiles_left >= 4
+            && !self.discard_actions(seat, true).is_empty()
+        {
+            actions.push(MjaiEvent::Reach { actor });
+        }
+
+        actions.extend(self.discard_actions(seat, false));
+        actions
+    }
+
+    // Discards after draw or call; after riichi declaration only the ones keeping tenpai
+    fn discard_actions(&self, seat: usize, tenpai_only: bool) -> Vec<MjaiEvent> {
+        let player = &self.players[seat];
+        let drawn = match (&self.last_call, player.hand.last()) {
+            (None, Some(tile)) => Some(mjai_name(*tile)),
+            _ => None,
+        };
+        let hand = self.hand(seat as i8);
+        let (haipai, furo) = split_hand(&hand);
+        let mut haipai34 = tiles_to_34(&haipai);
+
+        let mut actions: Vec<MjaiEvent> = Vec::new();
+        for name in self.names_in_hand(seat) {
+            let tile = tile_id(mjai_tile(&name).unwrap());
+            if let Some(call) = &self.last_call {
+                if !check_kuikae(
+                    &call.hand,
+                    &call.meld,
+                    call.called,
+                    tile,
+                    &self.rules.kuikae,
+                )
+                .unwrap_or(false)
+                {
+                    continue;
+                }
+            }
+            if tenpai_only {
+                haipai34[(tile - 1) as usize] -= 1;
+                let tenpai = !find_waits(&haipai34, &furo).is_empty();
+                haipai34[(tile - 1) as usize] += 1;
+                if !tenpai {
+                    continue;
+                }
+            }
+            actions.push(MjaiEvent::Dahai {
+                actor: seat as i8,
+                tsumogiri: drawn.as_ref() == Some(&name),
+                pai: name,
+            });
+        }
+        actions
+    }
+
+    fn call_actions(&self, seat: usize, from: usize, pai: &str, chankan: bool) -> Vec<MjaiEvent> {
+        let tile = match mjai_tile(pai) {
+            Ok(tile) => tile,
+            Err(_) => return Vec::new(),
+        };
+        let called = tile_id(tile);
+        let options = self.options(seat, Some(tile), chankan, &[]);
+        let from_seat = ((from + 4 - seat) % 4) as i8;
+        let calls = find_discard_calls(&self.hand(seat as i8), &options, called, from_seat)
+            .unwrap_or_default();
+        let (actor, target, pai) = (seat as i8, from as i8, pai.to_string());
+
+        let mut actions: Vec<MjaiEvent> = Vec::new();
+        for call in calls {
+            match call {
+                CallOption::Ron(_) if !self.players[seat].is_furiten() => {
+                    actions.push(MjaiEvent::Hora {
+                        actor,
+                        target,
+                        pai: Some(pai.clone()),
+                        uradora_markers: None,
+                        scores: None,
+                    })
+                }
+                CallOption::Chi(meld)
+                    if !chankan
+                        && self.players[seat].can_discard_after_call(
+                            &meld,
+                            called,
+                            &self.rules.kuikae,
+                        ) =>
+                {
+                    let mut own = meld.clone();
+                    own.remove(own.iter().position(|t| *t == called).unwrap());
+                    for consumed in self.consumed_options(seat, &own) {
+                        actions.push(MjaiEvent::Chi {
+                            actor,
+                            target,
+                            pai: pai.clone(),
+                            consumed,
+                        });
+                    }
+                }
+                CallOption::Pon(meld)
+                    if !chankan
+                        && self.players[seat].can_discard_after_call(
+                            &meld,
+                            called,
+                            &self.rules.kuikae,
+                        ) =>
+                {
+                    for consumed in self.consumed_options(seat, &[called, called]) {
+                        actions.push(MjaiEvent::Pon {
+                            actor,
+                            target,
+                            pai: pai.clone(),
+                            consumed,
+                        });
+                    }
+                }
+                CallOption::Daiminkan(_)
+                    if !chankan && self.kans_total() < 4 && self.tiles_left > 0 =>
+                {
+                    actions.push(MjaiEvent::Daiminkan {
+                        actor,
+                        target,
+                        pai: pai.clone(),
+                        consumed: self
+                            .consumed_options(seat, &[called, called, called])
+                            .remove(0),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if !actions.is_empty() {
+            actions.push(MjaiEvent::None);
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interfaces::{AbortiveDrawRules, KuikaeRules};
+
+    fn rules() -> RoundRules {
+        RoundRules {
+            allow_aka: true,
+            allow_kuitan: true,
+            with_kiriage: false,
+            multiple_ron: true,
+            multiple_ron_honba: false,
+            strict_ankan: true,
+            kuikae: KuikaeRules {
+                forbid_genbutsu: true,
+                forbid_suji: true,
+            },
+            abortive: AbortiveDrawRules {
+                kyuushu_kyuuhai: true,
+                suufon_renda: true,
+                suucha_riichi: true,
+                suukaikan: true,
+                sanchahou: true,
+            },
+            disabled_yaku: vec![],
+            local_yaku_enabled: vec![],
+            all_local_yaku_enabled: false,
+            allow_double_yakuman: false,
+        }
+    }
+
+    fn play(state: &mut MjaiState, lines: &[&str]) {
+        for line in lines {
+            for event in parse_mjai(line).unwrap() {
+                state.update(&event).unwrap();
+            }
+        }
+    }
+
+    static START: [&str; 2] = [
+        r#"{"type":"start_game","id":1,"names":["A","B","C","D"]}"#,
+        r#"{"type":"start_kyoku","bakaze":"E","kyoku":1,"honba":0,"kyotaku":0,"oya":0,"dora_marker":"1m",
+            "scores":[25000,25000,25000,25000],
+            "tehais":[["?","?","?","?","?","?","?","?","?","?","?","?","?"],
+                ["2m","3m","4m","5mr","6m","7m","3p","4p","5p","6s","6s","8s","9s"],
+                ["?","?","?","?","?","?","?","?","?","?","?","?","?"],
+                ["?","?","?","?","?","?","?","?","?","?","?","?","?"]]}"#,
+    ];
+
+    #[test]
+    pub fn should_convert_tiles() {
+        for tile in 0..136u8 {
+            let name = mjai_name(tile);
+            assert_eq!(tile_id(mjai_tile(&name).unwrap()), tile_id(tile));
+        }
+        assert_eq!(mjai_name(mjai_tile("5pr").unwrap()), "5pr");
+        assert_eq!(mjai_name(mjai_tile("5p").unwrap()), "5p");
+        assert_eq!(mjai_name(mjai_tile("C").unwrap()), "C");
+        assert!(mjai_tile("5zr").is_err());
+        assert!(mjai_tile("3mr").is_err());
+    }
+
+    fn names(tiles: &[&str]) -> Vec<String> {
+        tiles.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    pub fn should_list_calls_and_discards_after_chi() {
+        let mut state = MjaiState::new(rules());
+        play(&mut state, &START);
+        play(
+            &mut state,
+            &[
+                r#"[{"type":"tsumo","actor":0,"pai":"?"},{"type":"dahai","actor":0,"pai":"7s","tsumogiri":true}]"#,
+            ],
+        );
+        assert_eq!(state.seat(), Some(1));
+        assert!(state.legal_actions(0).is_empty());
+        assert!(state.legal_actions(2).is_empty());
+        assert_eq!(
+            state.legal_actions(1),
+            vec![
+                MjaiEvent::Chi {
+                    actor: 1,
+                    target: 0,
+                    pai: "7s".to_string(),
+                    consumed: names(&["6s", "8s"]),
+                },
+                MjaiEvent::Chi {
+                    actor: 1,
+                    target: 0,
+                    pai: "7s".to_string(),
+                    consumed: names(&["8s", "9s"]),
+                },
+                MjaiEvent::None,
+            ]
+        );
+
+        play(
+            &mut state,
+            &[r#"{"type":"chi","actor":1,"target":0,"pai":"7s","consumed":["8s","9s"]}"#],
+        );
+        let actions = state.legal_actions(1);
+        assert_eq!(actions.len(), 10);
+        assert!(actions.contains(&MjaiEvent::Dahai {
+            actor: 1,
+            pai: "5mr".to_string(),
+            tsumogiri: false,
+        }));
+        assert_eq!(state.hand(1).open_part, vec![(true, vec![25, 26, 27])]);
+    }
+
+    #[test]
+    pub fn should_calc_tsumo() {
+        let mut state = MjaiState::new(rules());
+        play(&mut state, &START);
+        play(
+            &mut state,
+            &[
+                r#"{"type":"tsumo","actor":0,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":0,"pai":"1p","tsumogiri":true}"#,
+                r#"{"type":"tsumo","actor":1,"pai":"7s"}"#,
+            ],
+        );
+        let actions = state.legal_actions(1);
+        assert_eq!(
+            actions[0],
+            MjaiEvent::Hora {
+                actor: 1,
+                target: 1,
+                pai: Some("7s".to_string()),
+                uradora_markers: None,
+                scores: None,
+            }
+        );
+        assert_eq!(actions[1], MjaiEvent::Reach { actor: 1 });
+
+        // Chihou on the first draw without calls
+        let result = state.calc_hora(1, &[]).unwrap();
+        assert_eq!((result.yakuman, result.ten), (1, 32000));
+        assert!(state.calc_hora(2, &[]).is_err());
+    }
+
+    #[test]
+    pub fn should_track_riichi_and_ron() {
+        let mut state = MjaiState::new(rules());
+        play(&mut state, &START);
+        play(
+            &mut state,
+            &[
+                r#"{"type":"tsumo","actor":0,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":0,"pai":"1p","tsumogiri":true}"#,
+                r#"{"type":"tsumo","actor":1,"pai":"1p"}"#,
+                r#"{"type":"reach","actor":1}"#,
+            ],
+        );
+        // Only discard keeping tenpai
+        assert_eq!(
+            state.legal_actions(1),
+            vec![MjaiEvent::Dahai {
+                actor: 1,
+                pai: "1p".to_string(),
+                tsumogiri: true,
+            }]
+        );
+
+        play(
+            &mut state,
+            &[
+                r#"{"type":"dahai","actor":1,"pai":"1p","tsumogiri":true}"#,
+                r#"{"type":"reach_accepted","actor":1}"#,
+                r#"{"type":"tsumo","actor":2,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":2,"pai":"7s","tsumogiri":true}"#,
+            ],
+        );
+        assert!(state.is_riichi(1));
+        assert_eq!(state.scores()[1], 24000);
+        assert_eq!(state.riichi_sticks(), 1);
+        // No calls in riichi
+        assert_eq!(
+            state.legal_actions(1),
+            vec![
+                MjaiEvent::Hora {
+                    actor: 1,
+                    target: 2,
+                    pai: Some("7s".to_string()),
+                    uradora_markers: None,
+                    scores: None,
+                },
+                MjaiEvent::None,
+            ]
+        );
+
+        // Double riichi, ippatsu, dora and aka
+        let result = state.calc_hora(1, &names(&["9s"])).unwrap();
+        assert_eq!((result.han, result.fu, result.ten), (5, 40, 8000));
+        assert_eq!(state.calc_hairi(1).unwrap().now, 0);
+
+        // Passed tile makes the hand furiten
+        play(
+            &mut state,
+            &[
+                r#"{"type":"tsumo","actor":3,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":3,"pai":"7s","tsumogiri":true}"#,
+            ],
+        );
+        assert!(state.legal_actions(1).is_empty());
+    }
+
+    #[test]
+    pub fn should_calc_second_ron_on_same_tile() {
+        let mut state = MjaiState::new(rules());
+        play(&mut state, &START);
+        play(
+            &mut state,
+            &[
+                r#"{"type":"tsumo","actor":0,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":0,"pai":"1p","tsumogiri":true}"#,
+                r#"{"type":"tsumo","actor":1,"pai":"1p"}"#,
+                r#"{"type":"reach","actor":1}"#,
+                r#"{"type":"dahai","actor":1,"pai":"1p","tsumogiri":true}"#,
+                r#"{"type":"reach_accepted","actor":1}"#,
+                r#"{"type":"tsumo","actor":2,"pai":"?"}"#,
+                r#"{"type":"dahai","actor":2,"pai":"7s","tsumogiri":true}"#,
+                r#"{"type":"hora","actor":3,"target":2,"pai":"7s","uradora_markers":["9s"]}"#,
+            ],
+        );
+        // First winner is closer to the discarder, the second one still rons on 7s
+        let result = state.calc_hora(1, &names(&["9s"])).unwrap();
+        assert_eq!((result.han, result.fu, result.ten), (5, 40, 8000));
+        assert!(state.calc_hora(2, &[]).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+// MPSZ notation: digits followed by suit letter, like "123m406p789s11z".
+// Honors are 1-7z (E, S, W, N, haku, hatsu, chun), 0 is a red five.
+
+// Tiles in the order they are written and count of red fives
+pub fn parse_mpsz(notation: &str) -> Result<(Vec<i8>, i8), String> {
+    let mut tiles: Vec<i8> = Vec::new();
+    let mut aka_count = 0;
+    let mut digits: Vec<i8> = Vec::new();
+    for c in notation.chars() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if let Some(digit) = c.to_digit(10) {
+            digits.push(digit as i8);
+            continue;
+        }
+        let offset = match c {
+            'm' => 0,
+            'p' => 9,
+            's' => 18,
+            'z' => 27,
+            _ => return Err(format!("Unknown suit: {}", c)),
+        };
+        if digits.is_empty() {
+            return Err(format!("No tiles before suit: {}", c));
+        }
+        for digit in digits.drain(..) {
+            let value = match (c, digit) {
+                ('z', 1..=7) => digit,
+                ('z', _) => return Err(format!("Incorrect honor: {}z", digit)),
+                (_, 0) => {
+                    aka_count += 1;
+                    5
+                }
+                _ => digit,
+            };
+            tiles.push(offset + value);
+        }
+    }
+    if !digits.is_empty() {
+        return Err("Tiles without suit".parse().unwrap());
+    }
+    Ok((tiles, aka_count))
+}
+
+// Sorted tiles in short notation, like "123m55p"
+pub fn format_mpsz(tiles: &[i8]) -> String {
+    let mut sorted = tiles.to_vec();
+    sorted.sort();
+    let mut result = String::new();
+    for (offset, suit) in [(0, 'm'), (9, 'p'), (18, 's'), (27, 'z')] {
+        let values: String = sorted
+            .iter()
+            .filter(|t| **t > offset && **t <= offset + 9)
+            .map(|t| char::from_digit((t - offset) as u32, 10).unwrap())
+            .collect();
+        if !values.is_empty() {
+            result.push_str(&values);
+            result.push(suit);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles::*;
+
+    #[test]
+    pub fn should_parse_mpsz() {
+        assert_eq!(
+            parse_mpsz("12m 0p 9s17z").unwrap(),
+            (
+                vec![M1 as i8, M2 as i8, P5 as i8, S9 as i8, E as i8, RD as i8],
+                1
+            )
+        );
+        assert!(parse_mpsz("123").is_err());
+        assert!(parse_mpsz("8z").is_err());
+        assert!(parse_mpsz("m").is_err());
+        assert!(parse_mpsz("12x").is_err());
+    }
+
+    #[test]
+    pub fn should_format_mpsz() {
+        assert_eq!(
+            format_mpsz(&[RD as i8, M3 as i8, M1 as i8, S5 as i8, E as i8]),
+            "13m5s17z"
+        );
+        let (tiles, _) = parse_mpsz("406m11z").unwrap();
+        assert_eq!(format_mpsz(&tiles), "456m11z");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::interfaces::{PlacementResult, PlacementRules};
+
+// Places by seat, ties are resolved by seat order
+pub(crate) fn rank(scores: &[i32]) -> Vec<i8> {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by_key(|s| -scores[*s]);
+    let mut places = vec![0; scores.len()];
+    for (place, seat) in order.iter().enumerate() {
+        places[*seat] = place as i8 + 1;
+    }
+    places
+}
+
+pub fn calc_placement(scores: &[i32], rules: &PlacementRules) -> Result<PlacementResult, String> {
+    if scores.len() != 4 {
+        return Err("Incorrect number of players".parse().unwrap());
+    }
+    let above = scores.iter().filter(|s| **s >= rules.return_points).count();
+    let uma = match rules.uma.len() {
+        1 => &rules.uma[0],
+        5 => &rules.uma[above],
+        _ => return Err("Incorrect uma table".parse().unwrap()),
+    };
+    if uma.len() != 4 {
+        return Err("Incorrect uma table".parse().unwrap());
+    }
+
+    let oka = rules
+        .oka
+        .unwrap_or((rules.return_points - rules.start_points) * 4);
+    // Uma and oka for every place
+    let bonus: Vec<i32> = (0..4)
+        .map(|place| uma[place] + if place == 0 { oka } else { 0 })
+        .collect();
+
+    let mut places = rank(scores);
+    let mut shares: Vec<i32> = places.iter().map(|p| bonus[*p as usize - 1]).collect();
+
+    if rules.split_ties {
+        for seat in 0..4 {
+            let tied: Vec<usize> = (0..4).filter(|s| scores[*s] == scores[seat]).collect();
+            if tied.len() < 2 || tied[0] != seat {
+                continue;
+            }
+            let best = tied.iter().map(|s| places[*s]).min().unwrap();
+            let total: i32 = tied.iter().map(|s| bonus[places[*s] as usize - 1]).sum();
+            let count = tied.len() as i32;
+            // Remainder of the split goes to the seats in order
+            let mut remainder = total.rem_euclid(count);
+            for s in &tied {
+                places[*s] = best;
+                shares[*s] = total.div_euclid(count) + if remainder > 0 { 1 } else { 0 };
+                remainder -= 1;
+            }
+        }
+    }
+
+    Ok(PlacementResult {
+        points: (0..4)
+            .map(|seat| scores[seat] - rules.return_points + shares[seat])
+            .collect(),
+        places,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> PlacementRules {
+        PlacementRules {
+            start_points: 25000,
+            return_points: 30000,
+            oka: None,
+            uma: vec![vec![15000, 5000, -5000, -15000]],
+            split_ties: false,
+        }
+    }
+
+    #[test]
+    pub fn should_apply_oka_and_uma() {
+        let res = calc_placement(&[13000, 38000, 26000, 23000], &rules()).unwrap();
+        assert_eq!(res.places, vec![4, 1, 2, 3]);
+        assert_eq!(res.points, vec![-32000, 43000, 1000, -12000]);
+
+        let mut no_oka = rules();
+        no_oka.oka = Some(0);
+        no_oka.return_points = 25000;
+        let res = calc_placement(&[13000, 38000, 26000, 23000], &no_oka).unwrap();
+        assert_eq!(res.points, vec![-27000, 28000, 6000, -7000]);
+    }
+
+    #[test]
+    pub fn should_use_uma_by_players_above_return_points() {
+        let mut rules = rules();
+        rules.oka = Some(0);
+        rules.uma = vec![
+            vec![0, 0, 0, 0],
+            vec![12000, -1000, -3000, -8000],
+            vec![8000, 4000, -4000, -8000],
+            vec![8000, 3000, 1000, -12000],
+            vec![0, 0, 0, 0],
+        ];
+        let res = calc_placement(&[40000, 30000, 20000, 10000], &rules).unwrap();
+        assert_eq!(res.points, vec![18000, 4000, -14000, -28000]);
+
+        let res = calc_placement(&[45000, 25000, 20000, 10000], &rules).unwrap();
+        assert_eq!(res.points, vec![27000, -6000, -13000, -28000]);
+
+        rules.uma.pop();
+        assert!(calc_placement(&[45000, 25000, 20000, 10000], &rules).is_err());
+    }
+
+    #[test]
+    pub fn should_resolve_ties() {
+        let scores = [30000, 30000, 25000, 15000];
+        let res = calc_placement(&scores, &rules()).unwrap();
+        assert_eq!(res.places, vec![1, 2, 3, 4]);
+        assert_eq!(res.points, vec![35000, 5000, -10000, -30000]);
+
+        let mut split = rules();
+        split.split_ties = true;
+        let res = calc_placement(&scores, &split).unwrap();
+        assert_eq!(res.places, vec![1, 1, 3, 4]);
+        assert_eq!(res.points, vec![20000, 20000, -10000, -30000]);
+
+        // Three-way tie: 35000 of uma and oka split unevenly, remainder goes in seat order
+        let res = calc_placement(&[30000, 30000, 30000, 10000], &split).unwrap();
+        assert_eq!(res.places, vec![1, 1, 1, 4]);
+        assert_eq!(res.points, vec![11667, 11667, 11666, -35000]);
+
+        // Four-way tie: uma sums to zero, only oka of 1 is split with a remainder
+        split.uma = vec![vec![15000, 5000, -5000, -15000]];
+        split.oka = Some(1);
+        let res = calc_placement(&[25000, 25000, 25000, 25000], &split).unwrap();
+        assert_eq!(res.places, vec![1, 1, 1, 1]);
+        assert_eq!(res.points, vec![-4999, -5000, -5000, -5000]);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::constants::Yaku;
+use crate::game::pay_wins;
+use crate::interfaces::{
+    LogEvent, LogGame, LogRound, ReplayMismatch, ReplayReport, RiichiResult, RoundWin,
+};
+use crate::riichi::calc_win;
+use crate::tenhou::{agari_hands, parse_mjlog, parse_tenhou_json};
+use std::fs;
+use std::path::Path;
+
+// Tenhou lists uradora separately, while calc_win counts them as dora
+fn normalize_yaku(yaku: &[(i8, i8)]) -> Vec<(i8, i8)> {
+    let mut result: Vec<(i8, i8)> = Vec::new();
+    for (id, han) in yaku {
+        let id = if *id == Yaku::Uradora as i8 {
+            Yaku::Dora as i8
+        } else {
+            *id
+        };
+        match result.iter_mut().find(|(y, _)| *y == id) {
+            Some((_, total)) => *total += han,
+            None => result.push((id, *han)),
+        }
+    }
+    result.retain(|(_, han)| *han > 0);
+    result.sort();
+    result
+}
+
+// Riichi deposits taken by the winner: ones left from previous rounds and accepted in this round
+fn riichi_sticks(round: &LogRound) -> i8 {
+    let mut sticks = round.riichi_sticks;
+    for (i, event) in round.events.iter().enumerate() {
+        if let LogEvent::Discard {
+            seat, riichi: true, ..
+        } = event
+        {
+            // Riichi is not accepted if the declaration tile is taken for ron
+            let ron = i + 1 == round.events.len()
+                && round
+                    .agari
+                    .iter()
+                    .any(|a| a.from == *seat && a.seat != a.from);
+            if !ron {
+                sticks += 1;
+            }
+        }
+    }
+    sticks
+}
+
+fn mismatch(source: &str, round: usize, agari: usize, expected: &LogRound) -> ReplayMismatch {
+    ReplayMismatch {
+        source: source.to_string(),
+        round,
+        agari,
+        hand: None,
+        options: None,
+        win: None,
+        expected: expected.agari[agari].clone(),
+        computed: None,
+        computed_deltas: Vec::new(),
+        differences: Vec::new(),
+    }
+}
+
+// Score every agari of the round and compare with recorded values.
+// As on tenhou, each winner takes honba on multiple ron, and riichi deposits go to the first one.
+pub fn verify_round(
+    round: &LogRound,
+    aka: bool,
+    source: &str,
+    index: usize,
+) -> Vec<ReplayMismatch> {
+    let hands = match agari_hands(round, aka) {
+        Ok(hands) => hands,
+        Err(e) => {
+            return (0..round.agari.len())
+                .map(|i| {
+                    let mut result = mismatch(source, index, i, round);
+                    result.differences.push(e.clone());
+                    result
+                })
+                .collect();
+        }
+    };
+
+    let mut results = Vec::new();
+    let mut wins = Vec::new();
+    for (i, (hand, options, win)) in hands.into_iter().enumerate() {
+        let expected = &round.agari[i];
+        let mut result = mismatch(source, index, i, round);
+        let computed: Result<RiichiResult, String> = calc_win(&hand, &options, win, false);
+        result.hand = Some(hand);
+        result.options = Some(options);
+        result.win = Some(win);
+
+        match computed {
+            Ok(computed) => {
+                if expected.yakuman > 0 || computed.yakuman > 0 {
+                    if computed.yakuman != expected.yakuman {
+                        result.differences.push("yakuman".to_string());
+                    }
+                } else {
+                    if computed.han != expected.han {
+                        result.differences.push("han".to_string());
+                    }
+                    // Fu are not recorded for limit hands in JSON logs
+                    if expected.fu > 0 && computed.fu != expected.fu {
+                        result.differences.push("fu".to_string());
+                    }
+                }
+                if normalize_yaku(&computed.yaku) != normalize_yaku(&expected.yaku) {
+                    result.differences.push("yaku".to_string());
+                }
+                wins.push(RoundWin {
+                    seat: expected.seat,
+                    from: if expected.seat == expected.from {
+                        -1
+                    } else {
+                        expected.from
+                    },
+                    result: computed.clone(),
+                });
+                result.computed = Some(computed);
+            }
+            Err(e) => result.differences.push(e),
+        }
+        results.push(result);
+    }
+
+    // Payments are compared only if every agari is scored: deltas of each one are payments for the wins
+    // up to it without payments for the wins before it
+    if wins.len() == results.len() {
+        let payments = |count: usize| {
+            let mut deltas = vec![0; round.scores.len()];
+            let mut sticks = riichi_sticks(round);
+            pay_wins(
+                &mut deltas,
+                &mut sticks,
+                round.dealer as usize,
+                round.honba,
+                true,
+                &wins[..count],
+            );
+            deltas
+        };
+        for (i, result) in results.iter_mut().enumerate() {
+            let expected = &round.agari[i];
+            // Payments with pao are not supported by the engine, so they are not compared
+            if expected.pao != expected.seat {
+                continue;
+            }
+            let before = payments(i);
+            let deltas: Vec<i32> = payments(i + 1)
+                .iter()
+                .zip(&before)
+                .map(|(after, before)| after - before)
+                .collect();
+            if deltas != expected.deltas {
+                result.differences.push("payments".to_string());
+            }
+            result.computed_deltas = deltas;
+        }
+    }
+
+    results
+        .into_iter()
+        .filter(|r| !r.differences.is_empty())
+        .collect()
+}
+
+pub fn verify_game(game: &LogGame, source: &str) -> ReplayReport {
+    let mut report = ReplayReport {
+        files: 1,
+        ..ReplayReport::default()
+    };
+    for (i, round) in game.rounds.iter().enumerate() {
+        report.hands += round.agari.len();
+        report
+            .mismatches
+            .extend(verify_round(round, game.aka, source, i));
+    }
+    report
+}
+
+// Verify all logs in the directory and its subdirectories:
+// tenhou.net/6 JSON logs (*.json) and uncompressed mjlog XML (*.mjlog, *.xml)
+pub fn verify_dir(path: &Path) -> Result<ReplayReport, String> {
+    let mut report = ReplayReport::default();
+    let mut entries = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect::<Vec<_>>(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            let nested = verify_dir(&entry)?;
+            report.files += nested.files;
+            report.hands += nested.hands;
+            report.errors.extend(nested.errors);
+            report.mismatches.extend(nested.mismatches);
+            continue;
+        }
+        let source = entry.display().to_string();
+        let extension = entry.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !["json", "mjlog", "xml"].contains(&extension) {
+            continue;
+        }
+        let game = fs::read_to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                if extension == "json" {
+                    parse_tenhou_json(&text)
+                } else {
+                    parse_mjlog(&text)
+                }
+            });
+        match game {
+            Ok(game) => {
+                let checked = verify_game(&game, &source);
+                report.files += 1;
+                report.hands += checked.hands;
+                report.mismatches.extend(checked.mismatches);
+            }
+            Err(e) => report.errors.push((source, e)),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static JSON_LOG: &str = r#"{
+        "name": ["A", "B", "C", "D"],
+        "rule": {"disp": "般南喰赤", "aka": 1},
+        "log": [[
+            [0, 1, 1],
+            [25000, 25000, 24000, 25000],
+            [42],
+            [47],
+            [11, 12, 13, 14, 15, 16, 17, 18, 19, 41, 41, 42, 45], [22], [45],
+            [21, 21, 23, 24, 25, 31, 32, 33, 45, 45, 37, 38, 39], ["p454545"], [23],
+            [13, 13, 14, 15, 16, 26, 27, 28, 34, 35, 36, 29, 29], [44], ["r44"],
+            [31, 31, 31, 32, 34, 42, 43, 44, 46, 46, 47, 47, 47], [29], [60],
+            ["和了", [0, 0, 4900, -2900], [2, 3, 2, "40符2飜2600点", "立直(1飜)", "一発(1飜)"]]
+        ]]
+    }"#;
+
+    #[test]
+    pub fn should_verify_recorded_hand() {
+        let game = parse_tenhou_json(JSON_LOG).unwrap();
+        let report = verify_game(&game, "test.json");
+        assert_eq!((report.files, report.hands), (1, 1));
+        assert!(report.mismatches.is_empty());
+    }
+
+    #[test]
+    pub fn should_report_mismatch() {
+        let mut game = parse_tenhou_json(JSON_LOG).unwrap();
+        let agari = &mut game.rounds[0].agari[0];
+        agari.fu = 30;
+        agari.deltas = vec![0, 0, 4000, -2000];
+        agari.yaku.push((Yaku::Uradora as i8, 1));
+        let report = verify_game(&game, "test.json");
+        assert_eq!(report.mismatches.len(), 1);
+        let mismatch = &report.mismatches[0];
+        assert_eq!((mismatch.round, mismatch.agari), (0, 0));
+        assert_eq!(mismatch.differences, vec!["fu", "yaku", "payments"]);
+        assert_eq!(mismatch.computed_deltas, vec![0, 0, 4900, -2900]);
+        assert_eq!(mismatch.computed.as_ref().unwrap().fu, 40);
+    }
+
+    #[test]
+    pub fn should_merge_uradora() {
+        assert_eq!(
+            normalize_yaku(&[
+                (Yaku::Uradora as i8, 1),
+                (Yaku::Riichi as i8, 1),
+                (Yaku::Dora as i8, 2),
+                (Yaku::Akadora as i8, 0),
+            ]),
+            vec![(Yaku::Riichi as i8, 1), (Yaku::Dora as i8, 3)]
+        );
+    }
+}
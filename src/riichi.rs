@@ -1,49 +1,83 @@
-use crate::agari::{check_all, find_all_agari_patterns};
+use crate::agari::{SetTiles, agari_patterns, check_all};
 use crate::constants::{Yaku, ceil10, ceil100, is_proper_open_set, is19};
-use crate::interfaces::{RiichiHand, RiichiOptions, RiichiResult};
-use crate::shanten::hairi;
+use crate::hand34::Hand34;
+use crate::interfaces::{RiichiHand, RiichiOptions, RiichiResult, WinKind};
+use crate::shanten::hairi_with_melds;
+use crate::tenpai::calc_riichi_declarations;
 use crate::yaku::{YAKU_SETTINGS, YakuCheckInput};
 
+// Scores the hand won by tsumo or ron. Options are only read and their tile_discarded_by_someone
+// is ignored, so the same options may be shared between calls and threads.
+pub fn calc_win(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    win: WinKind,
+    calc_hairi: bool,
+) -> Result<RiichiResult, String> {
+    match win {
+        WinKind::Tsumo(tile) => score_win(hand, options, tile, true, calc_hairi),
+        WinKind::Ron { tile, from_seat } => {
+            if !(1..=3).contains(&from_seat) {
+                return Err("Incorrect seat".parse().unwrap());
+            }
+            score_win(hand, options, tile, false, calc_hairi)
+        }
+    }
+}
+
+#[deprecated(note = "use calc_win with explicit WinKind, options are not changed anymore")]
 pub fn calc_riichi(
     hand: RiichiHand,
     options: &mut RiichiOptions,
     calc_hairi: bool,
+) -> Result<RiichiResult, String> {
+    score_hand(&hand, options, calc_hairi)
+}
+
+// Same as calc_riichi: ron on tile_discarded_by_someone, or tsumo on the last tile of closed part
+pub(crate) fn score_hand(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    calc_hairi: bool,
+) -> Result<RiichiResult, String> {
+    if options.tile_discarded_by_someone != -1 {
+        score_win(
+            hand,
+            options,
+            options.tile_discarded_by_someone,
+            false,
+            calc_hairi,
+        )
+    } else {
+        match hand.closed_part.last() {
+            Some(tile) => score_win(hand, options, *tile, true, calc_hairi),
+            None => Err("Incorrect number of tiles".parse().unwrap()),
+        }
+    }
+}
+
+// Scores the hand won on taken tile, on ron the tile is added to closed part
+pub(crate) fn score_win(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    taken_tile: i8,
+    is_tsumo: bool,
+    calc_hairi: bool,
 ) -> Result<RiichiResult, String> {
     // Closed part
     let mut haipai = hand.closed_part.clone();
     // Open tiles
     // Closed kan will be with minus sign in tile values
-    let mut furo: Vec<Vec<i8>> = Vec::new();
+    let (closed_tiles, furo) = split_hand(hand);
     // tile34-formatted haipai
-    let mut haipai34: Vec<i8> = vec![
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0,
-    ];
-
-    let is_tsumo: bool;
+    let mut haipai34 = Hand34::new();
 
-    if options.tile_discarded_by_someone != -1 {
-        haipai.push(options.tile_discarded_by_someone);
-        is_tsumo = false;
-    } else {
-        options.tile_discarded_by_someone = haipai.last().unwrap().clone();
-        is_tsumo = true;
+    if !is_tsumo {
+        haipai.push(taken_tile);
     }
 
-    for (open, meld_tiles) in hand.open_part {
-        if is_proper_open_set(&meld_tiles) {
-            let mut tiles = meld_tiles
-                .iter()
-                .map(|tile| if open { *tile } else { -1 * tile })
-                .collect::<Vec<i8>>();
-            tiles.sort();
-            furo.push(tiles);
-        } else {
-            haipai.extend_from_slice(meld_tiles.as_slice());
-        }
-    }
+    // Improper sets from open part go after the taken tile
+    haipai.extend_from_slice(&closed_tiles[hand.closed_part.len()..]);
 
     if haipai.len() % 3 == 0 {
         return Err("Incorrect number of tiles".parse().unwrap());
@@ -53,21 +87,73 @@ pub fn calc_riichi(
         return Err("Incorrect number of tiles".parse().unwrap());
     }
 
+    check_winning_tile(&haipai, &furo, options, taken_tile, is_tsumo)?;
+
     for hai in &haipai {
-        haipai34[*hai as usize - 1] += 1;
+        haipai34.add(*hai as usize - 1, 1);
     }
 
     calc_all(
-        &haipai,
-        &haipai34,
-        &furo,
-        &options.dora,
-        options,
-        is_tsumo,
-        calc_hairi,
+        &haipai, &haipai34, &furo, options, taken_tile, is_tsumo, calc_hairi,
     )
 }
 
+// Split hand into closed tiles and melds.
+// Closed kan will be with minus sign in tile values.
+// Improper sets in open part are treated as closed tiles.
+pub(crate) fn split_hand(hand: &RiichiHand) -> (Vec<i8>, Vec<Vec<i8>>) {
+    let mut haipai = hand.closed_part.clone();
+    let mut furo: Vec<Vec<i8>> = Vec::new();
+    for (open, meld_tiles) in &hand.open_part {
+        if is_proper_open_set(meld_tiles) {
+            let mut tiles = meld_tiles
+                .iter()
+                .map(|tile| if *open { *tile } else { -tile })
+                .collect::<Vec<i8>>();
+            tiles.sort();
+            furo.push(tiles);
+        } else {
+            haipai.extend_from_slice(meld_tiles.as_slice());
+        }
+    }
+    (haipai, furo)
+}
+
+// Winning tile must be in hand, and the hand can't hold more than four copies of it.
+// Rinshan needs own kan, and nobody else can hold the tile robbed by chankan.
+fn check_winning_tile(
+    haipai: &[i8],
+    furo: &[Vec<i8>],
+    options: &RiichiOptions,
+    taken_tile: i8,
+    is_tsumo: bool,
+) -> Result<(), String> {
+    if !(1..=34).contains(&taken_tile) {
+        return Err("Incorrect winning tile".parse().unwrap());
+    }
+    let in_hand = haipai.iter().filter(|t| **t == taken_tile).count();
+    let in_melds = furo
+        .iter()
+        .flatten()
+        .filter(|t| t.abs() == taken_tile)
+        .count();
+    if in_hand == 0 {
+        return Err("Winning tile is not in hand".parse().unwrap());
+    }
+    if in_hand + in_melds > 4 {
+        return Err("Too many copies of winning tile".parse().unwrap());
+    }
+    if options.after_kan {
+        if is_tsumo && !furo.iter().any(|meld| meld.len() == 4) {
+            return Err("No kan for rinshan".parse().unwrap());
+        }
+        if !is_tsumo && in_hand + in_melds > 1 {
+            return Err("Chankan tile is in hand".parse().unwrap());
+        }
+    }
+    Ok(())
+}
+
 fn is_menzen(furo: &Vec<Vec<i8>>) -> bool {
     for meld in furo {
         if meld.len() > 2 {
@@ -192,7 +278,7 @@ pub fn calc_fu(
     jikaze: i8,
     found_yaku: Vec<i8>,
     taken_tile: i8, // -1 if nothing taken
-    current_pattern: &Vec<Vec<i8>>,
+    current_pattern: &[SetTiles],
     furo: &Vec<Vec<i8>>,
 ) -> Option<i32> {
     let mut fu;
@@ -312,10 +398,11 @@ pub fn calc_fu(
 
 fn calc_yaku(
     haipai: &Vec<i8>,
-    haipai34: &Vec<i8>,
+    haipai34: &Hand34,
     furo: &Vec<Vec<i8>>,
-    current_pattern: &Vec<Vec<i8>>,
+    current_pattern: &[SetTiles],
     settings: &RiichiOptions,
+    taken_tile: i8,
     is_tsumo: bool,
 ) -> (Vec<(i8, i8)>, i8, i32) {
     let mut yaku_list: Vec<(i8, i8)> = Vec::new();
@@ -324,30 +411,32 @@ fn calc_yaku(
 
     let check_input = YakuCheckInput {
         haipai,
-        haipai34,
+        haipai34: *haipai34,
         furo,
         current_pattern,
-        taken_tile: settings.tile_discarded_by_someone,
+        taken_tile,
         is_tsumo,
         jikaze: settings.jikaze,
         bakaze: settings.bakaze,
         first_take: settings.first_take,
         riichi: settings.riichi,
         double_riichi: settings.double_riichi,
+        open_riichi: settings.open_riichi,
+        open_riichi_deal_in: settings.open_riichi_deal_in,
         ippatsu: settings.ippatsu,
         after_kan: settings.after_kan,
         last_tile: settings.last_tile,
         allow_kuitan: settings.allow_kuitan,
     };
 
-    for y in 0..YAKU_SETTINGS.len() {
-        let yaku = &YAKU_SETTINGS[y];
-        if settings.disabled_yaku.contains(&(y as i8)) {
+    for yaku in YAKU_SETTINGS.iter() {
+        let y = yaku.id;
+        if settings.disabled_yaku.contains(&y) {
             continue;
         }
         if yaku.is_local
             && !settings.all_local_yaku_enabled
-            && !settings.local_yaku_enabled.contains(&(y as i8))
+            && !settings.local_yaku_enabled.contains(&y)
         {
             continue;
         }
@@ -367,13 +456,13 @@ fn calc_yaku(
                     1
                 };
                 yakuman += n;
-                yaku_list.push((y as i8, if n > 1 { 26 } else { 13 }));
+                yaku_list.push((y, if n > 1 { 26 } else { 13 }));
             } else {
                 let mut n = yaku.han;
                 if yaku.is_furo_minus && !is_menzen(furo) {
                     n -= 1;
                 }
-                yaku_list.push((y as i8, n));
+                yaku_list.push((y, n));
                 han += n as i32;
             }
         }
@@ -384,10 +473,10 @@ fn calc_yaku(
 
 fn calc_all(
     haipai: &Vec<i8>,
-    haipai34: &Vec<i8>,
+    haipai34: &Hand34,
     furo: &Vec<Vec<i8>>,
-    dora: &Vec<i8>,
     opts: &RiichiOptions,
+    taken_tile: i8,
     is_tsumo: bool,
     calc_hairi: bool,
 ) -> Result<RiichiResult, String> {
@@ -406,29 +495,47 @@ fn calc_all(
 
     if !result.is_agari || haipai.len() + furo.len() * 3 != 14 {
         if calc_hairi {
-            result.hairi = hairi(haipai34.clone().as_mut());
+            result.hairi = hairi_with_melds(haipai34.to_vec().as_mut(), furo);
+            if let Some(hairi) = result.hairi.as_mut() {
+                if hairi.now == 0 && is_menzen(furo) {
+                    hairi.riichi_after_discard =
+                        calc_riichi_declarations(haipai, furo, opts, &hairi.waits_after_discard);
+                }
+            }
         }
         return Ok(result);
     }
 
     result.is_agari = true;
-    let agari_patterns = find_all_agari_patterns(haipai34);
-
-    for v in agari_patterns {
-        let mut current_pattern = v.clone();
+    for decomposition in agari_patterns(haipai34) {
+        let v = decomposition.pattern(haipai34);
+        let mut current_pattern = v;
         for f in furo {
-            current_pattern.push(f.clone());
+            current_pattern.push(SetTiles::new(f));
         }
-        let (mut yaku_list, yakuman, mut han) =
-            calc_yaku(haipai, haipai34, furo, &current_pattern, &opts, is_tsumo);
+        let (mut yaku_list, yakuman, mut han) = calc_yaku(
+            haipai,
+            haipai34,
+            furo,
+            &current_pattern,
+            opts,
+            taken_tile,
+            is_tsumo,
+        );
         if yakuman == 0 && han == 0 {
             continue;
         }
 
         let mut fu = 0;
         if han > 0 || yakuman > 0 {
-            let (dora_count, akadora_count) =
-                calc_all_dora(haipai, furo, dora, han, opts.aka_count, opts.allow_aka);
+            let (dora_count, akadora_count) = calc_all_dora(
+                haipai,
+                furo,
+                &opts.dora,
+                han,
+                opts.aka_count,
+                opts.allow_aka,
+            );
             if dora_count > 0 {
                 han += dora_count as i32;
                 yaku_list.push((Yaku::Dora as i8, dora_count));
@@ -442,7 +549,7 @@ fn calc_all(
                 opts.bakaze,
                 opts.jikaze,
                 yaku_list.iter().map(|(y, _c)| *y).collect::<Vec<i8>>(),
-                opts.tile_discarded_by_someone,
+                taken_tile,
                 &v,
                 furo,
             )
@@ -476,6 +583,7 @@ fn calc_all(
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use crate::constants::Tiles;
@@ -508,6 +616,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::E as i8,
                 bakaze: Tiles::E as i8,
@@ -563,6 +673,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -625,6 +737,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -685,6 +799,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -744,6 +860,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -803,6 +921,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -869,6 +989,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -932,6 +1054,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -996,6 +1120,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1059,6 +1185,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1117,6 +1245,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::P3 as i8,
                 bakaze: Tiles::E as i8,
@@ -1179,6 +1309,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1241,6 +1373,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::S as i8,
                 bakaze: Tiles::E as i8,
@@ -1301,6 +1435,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::P5 as i8,
                 bakaze: Tiles::E as i8,
@@ -1358,6 +1494,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::M3 as i8,
                 bakaze: Tiles::E as i8,
@@ -1416,6 +1554,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1501,6 +1641,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::M1 as i8,
                 bakaze: Tiles::E as i8,
@@ -1575,6 +1717,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1636,6 +1780,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::P9 as i8,
                 bakaze: Tiles::E as i8,
@@ -1698,6 +1844,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1754,6 +1902,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -1816,6 +1966,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::P3 as i8,
                 bakaze: Tiles::E as i8,
@@ -1873,6 +2025,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::M1 as i8,
                 bakaze: Tiles::E as i8,
@@ -1930,6 +2084,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::S4 as i8,
                 bakaze: Tiles::E as i8,
@@ -1996,6 +2152,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::M1 as i8,
                 bakaze: Tiles::E as i8,
@@ -2057,6 +2215,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::M1 as i8,
                 bakaze: Tiles::E as i8,
@@ -2112,6 +2272,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::E as i8,
@@ -2181,6 +2343,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: true,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: -1,
                 bakaze: Tiles::S as i8,
@@ -2243,6 +2407,8 @@ mod tests {
                 riichi: false,
                 ippatsu: false,
                 double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
                 after_kan: false,
                 tile_discarded_by_someone: Tiles::P1 as i8,
                 bakaze: Tiles::E as i8,
@@ -2268,4 +2434,453 @@ mod tests {
         assert_eq!(r.yaku, vec![(Yaku::Chuurenpoto as i8, 13),]);
         assert_eq!(r.yakuman, 1);
     }
+
+    #[test]
+    pub fn should_parse_open_riichi_pinfu() {
+        let res = calc_riichi(
+            RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::P2 as i8,
+                    Tiles::P3 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::S3 as i8,
+                    Tiles::S4 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::M7 as i8,
+                    Tiles::M8 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P5 as i8,
+                ],
+                open_part: vec![],
+            },
+            &mut RiichiOptions {
+                riichi: true,
+                open_riichi: true,
+                open_riichi_deal_in: true,
+                tile_discarded_by_someone: Tiles::M9 as i8,
+                jikaze: Tiles::W as i8,
+                allow_kuitan: false,
+                ..RiichiOptions::for_tests()
+            },
+            false,
+        );
+
+        assert!(res.is_ok());
+        let r = res.unwrap();
+        assert_eq!(r.fu, 30);
+        assert_eq!(r.han, 3);
+        assert!(r.is_agari);
+        assert_eq!(r.ten, 3900);
+        assert_eq!(
+            r.yaku,
+            vec![(Yaku::Pinfu as i8, 1), (Yaku::OpenRiichi as i8, 2)]
+        );
+        assert_eq!(r.yakuman, 0);
+    }
+
+    #[test]
+    pub fn should_parse_open_double_riichi_pinfu() {
+        let res = calc_riichi(
+            RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::P2 as i8,
+                    Tiles::P3 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::S3 as i8,
+                    Tiles::S4 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::M7 as i8,
+                    Tiles::M8 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P5 as i8,
+                ],
+                open_part: vec![],
+            },
+            &mut RiichiOptions {
+                riichi: true,
+                double_riichi: true,
+                open_riichi: true,
+                tile_discarded_by_someone: Tiles::M9 as i8,
+                jikaze: Tiles::W as i8,
+                allow_kuitan: false,
+                ..RiichiOptions::for_tests()
+            },
+            false,
+        );
+
+        assert!(res.is_ok());
+        let r = res.unwrap();
+        assert_eq!(r.fu, 30);
+        assert_eq!(r.han, 4);
+        assert!(r.is_agari);
+        assert_eq!(r.ten, 7700);
+        assert_eq!(
+            r.yaku,
+            vec![(Yaku::Pinfu as i8, 1), (Yaku::OpenDaburuRiichi as i8, 3)]
+        );
+        assert_eq!(r.yakuman, 0);
+    }
+
+    #[test]
+    pub fn should_parse_open_riichi_deal_in() {
+        let res = calc_riichi(
+            RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::P2 as i8,
+                    Tiles::P3 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::S3 as i8,
+                    Tiles::S4 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::M7 as i8,
+                    Tiles::M8 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P5 as i8,
+                ],
+                open_part: vec![],
+            },
+            &mut RiichiOptions {
+                riichi: true,
+                open_riichi: true,
+                open_riichi_deal_in: true,
+                tile_discarded_by_someone: Tiles::M9 as i8,
+                jikaze: Tiles::W as i8,
+                allow_kuitan: false,
+                local_yaku_enabled: vec![Yaku::OpenRiichiDealIn as i8],
+                ..RiichiOptions::for_tests()
+            },
+            false,
+        );
+
+        assert!(res.is_ok());
+        let r = res.unwrap();
+        assert_eq!(r.fu, 30);
+        assert_eq!(r.han, 0);
+        assert!(r.is_agari);
+        assert_eq!(r.ten, 32000);
+        assert_eq!(r.yaku, vec![(Yaku::OpenRiichiDealIn as i8, 13)]);
+        assert_eq!(r.yakuman, 1);
+    }
+
+    #[test]
+    pub fn should_calc_riichi_declarations() {
+        let res = calc_riichi(
+            RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::P2 as i8,
+                    Tiles::P3 as i8,
+                    Tiles::P4 as i8,
+                    Tiles::S3 as i8,
+                    Tiles::S4 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::M7 as i8,
+                    Tiles::M8 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::N as i8,
+                ],
+                open_part: vec![],
+            },
+            &mut RiichiOptions {
+                dora: vec![],
+                aka_count: 0,
+                first_take: false,
+                riichi: false,
+                ippatsu: false,
+                double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
+                after_kan: false,
+                tile_discarded_by_someone: -1,
+                bakaze: Tiles::E as i8,
+                jikaze: Tiles::W as i8,
+                allow_aka: false,
+                allow_kuitan: false,
+                with_kiriage: false,
+                disabled_yaku: vec![],
+                local_yaku_enabled: vec![],
+                all_local_yaku_enabled: false,
+                allow_double_yakuman: false,
+                last_tile: false,
+            },
+            true,
+        );
+
+        assert!(res.is_ok());
+        let r = res.unwrap();
+        assert!(!r.is_agari);
+        let hairi = r.hairi.unwrap();
+        assert_eq!(hairi.now, 0);
+        assert_eq!(hairi.riichi_after_discard.len(), 1);
+
+        let declaration = &hairi.riichi_after_discard[0];
+        assert_eq!(declaration.discard, Tiles::N as i8 - 1);
+        assert_eq!(
+            declaration.waits,
+            vec![Tiles::M6 as i8 - 1, Tiles::M9 as i8 - 1]
+        );
+        assert_eq!(declaration.live_tiles, 8);
+        assert!(declaration.has_yaku_without_riichi);
+        for win in &declaration.values {
+            assert_eq!(win.ron_han, 2);
+            assert_eq!(win.ron_ten, 2000);
+            assert_eq!(win.tsumo_han, 3);
+            assert_eq!(win.tsumo_ten, 2700);
+        }
+    }
+
+    #[test]
+    pub fn hairi_respects_melds() {
+        // Ten terminals and honors with a pon of 5m: kokushi shanten is not counted
+        let res = calc_riichi(
+            RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M9 as i8,
+                    Tiles::P1 as i8,
+                    Tiles::P9 as i8,
+                    Tiles::S1 as i8,
+                    Tiles::S9 as i8,
+                    Tiles::E as i8,
+                    Tiles::S as i8,
+                    Tiles::W as i8,
+                    Tiles::N as i8,
+                    Tiles::WD as i8,
+                ],
+                open_part: vec![(
+                    true,
+                    vec![Tiles::M5 as i8, Tiles::M5 as i8, Tiles::M5 as i8],
+                )],
+            },
+            &mut RiichiOptions {
+                dora: vec![],
+                aka_count: 0,
+                first_take: false,
+                riichi: false,
+                ippatsu: false,
+                double_riichi: false,
+                open_riichi: false,
+                open_riichi_deal_in: false,
+                after_kan: false,
+                tile_discarded_by_someone: -1,
+                bakaze: Tiles::E as i8,
+                jikaze: Tiles::W as i8,
+                allow_aka: false,
+                allow_kuitan: false,
+                with_kiriage: false,
+                disabled_yaku: vec![],
+                local_yaku_enabled: vec![],
+                all_local_yaku_enabled: false,
+                allow_double_yakuman: false,
+                last_tile: false,
+            },
+            true,
+        );
+
+        let hairi = res.unwrap().hairi.unwrap();
+        assert_eq!(hairi.now, 6);
+        assert!(hairi.riichi_after_discard.is_empty());
+    }
+
+    fn win_options() -> RiichiOptions {
+        RiichiOptions {
+            riichi: true,
+            jikaze: Tiles::W as i8,
+            ..RiichiOptions::for_tests()
+        }
+    }
+
+    #[test]
+    pub fn calc_win_uses_given_winning_tile() {
+        // 123m 234p 345s 789m 55p: tsumo on 9m is pinfu, on 7m it is not
+        let hand = RiichiHand {
+            closed_part: vec![
+                Tiles::M9 as i8,
+                Tiles::M1 as i8,
+                Tiles::M2 as i8,
+                Tiles::M3 as i8,
+                Tiles::P2 as i8,
+                Tiles::P3 as i8,
+                Tiles::P4 as i8,
+                Tiles::S3 as i8,
+                Tiles::S4 as i8,
+                Tiles::S5 as i8,
+                Tiles::M7 as i8,
+                Tiles::M8 as i8,
+                Tiles::P5 as i8,
+                Tiles::P5 as i8,
+            ],
+            open_part: vec![],
+        };
+        let options = win_options();
+
+        let ryanmen = calc_win(&hand, &options, WinKind::Tsumo(Tiles::M9 as i8), false).unwrap();
+        assert_eq!((ryanmen.han, ryanmen.fu), (3, 20));
+        assert!(ryanmen.yaku.contains(&(Yaku::Pinfu as i8, 1)));
+
+        let penchan = calc_win(&hand, &options, WinKind::Tsumo(Tiles::M7 as i8), false).unwrap();
+        assert_eq!((penchan.han, penchan.fu), (2, 30));
+        assert!(!penchan.yaku.contains(&(Yaku::Pinfu as i8, 1)));
+    }
+
+    #[test]
+    pub fn calc_win_shares_options() {
+        let mut hand = RiichiHand {
+            closed_part: vec![
+                Tiles::M1 as i8,
+                Tiles::M2 as i8,
+                Tiles::M3 as i8,
+                Tiles::P2 as i8,
+                Tiles::P3 as i8,
+                Tiles::P4 as i8,
+                Tiles::S3 as i8,
+                Tiles::S4 as i8,
+                Tiles::S5 as i8,
+                Tiles::M7 as i8,
+                Tiles::M8 as i8,
+                Tiles::P5 as i8,
+                Tiles::P5 as i8,
+            ],
+            open_part: vec![],
+        };
+        let options = win_options();
+        let ron = WinKind::Ron {
+            tile: Tiles::M6 as i8,
+            from_seat: 1,
+        };
+
+        let first = calc_win(&hand, &options, ron, false).unwrap();
+        assert_eq!(first.outgoing_ten, None);
+        assert_eq!(first.ten, 2000);
+
+        hand.closed_part.push(Tiles::M9 as i8);
+        let tsumo = calc_win(&hand, &options, WinKind::Tsumo(Tiles::M9 as i8), false).unwrap();
+        assert!(tsumo.outgoing_ten.is_some());
+        assert_eq!(options.tile_discarded_by_someone, -1);
+
+        hand.closed_part.pop();
+        let second = calc_win(&hand, &options, ron, false).unwrap();
+        assert_eq!(second.ten, first.ten);
+
+        let wrong_seat = WinKind::Ron {
+            tile: Tiles::M6 as i8,
+            from_seat: 0,
+        };
+        assert_eq!(
+            calc_win(&hand, &options, wrong_seat, false).err().unwrap(),
+            "Incorrect seat"
+        );
+    }
+
+    #[test]
+    pub fn calc_win_checks_winning_tile() {
+        // 234m 567m 11s with a kan of 9p
+        let mut hand = RiichiHand {
+            closed_part: vec![
+                Tiles::M2 as i8,
+                Tiles::M3 as i8,
+                Tiles::M4 as i8,
+                Tiles::M5 as i8,
+                Tiles::M6 as i8,
+                Tiles::M7 as i8,
+                Tiles::S1 as i8,
+                Tiles::S1 as i8,
+                Tiles::S2 as i8,
+                Tiles::S3 as i8,
+                Tiles::S4 as i8,
+            ],
+            open_part: vec![(false, vec![Tiles::P9 as i8; 4])],
+        };
+        let mut options = win_options();
+        let error = |hand: &RiichiHand, options: &RiichiOptions, win: WinKind| {
+            calc_win(hand, options, win, false).err().unwrap()
+        };
+
+        assert_eq!(
+            error(&hand, &options, WinKind::Tsumo(Tiles::M1 as i8)),
+            "Winning tile is not in hand"
+        );
+        assert_eq!(
+            error(&hand, &options, WinKind::Tsumo(0)),
+            "Incorrect winning tile"
+        );
+
+        // Rinshan: the drawn tile doesn't have to be the last one
+        options.after_kan = true;
+        let rinshan = calc_win(&hand, &options, WinKind::Tsumo(Tiles::M2 as i8), false).unwrap();
+        assert!(rinshan.yaku.contains(&(Yaku::Rinshan as i8, 1)));
+
+        let no_kan = RiichiHand {
+            closed_part: [hand.closed_part.clone(), vec![Tiles::P9 as i8; 3]].concat(),
+            open_part: vec![],
+        };
+        assert_eq!(
+            error(&no_kan, &options, WinKind::Tsumo(Tiles::M2 as i8)),
+            "No kan for rinshan"
+        );
+
+        // Chankan: all other copies of the tile are in the kan of the discarder
+        let chankan = WinKind::Ron {
+            tile: Tiles::M7 as i8,
+            from_seat: 2,
+        };
+        hand.closed_part.retain(|t| *t != Tiles::M7 as i8);
+        let result = calc_win(&hand, &options, chankan, false).unwrap();
+        assert!(result.yaku.contains(&(Yaku::Chankan as i8, 1)));
+
+        // Shanpon on 7m and 1s can't win on 7m added to kan
+        hand.closed_part
+            .retain(|t| *t != Tiles::M5 as i8 && *t != Tiles::M6 as i8);
+        hand.closed_part.extend([Tiles::M7 as i8, Tiles::M7 as i8]);
+        assert_eq!(error(&hand, &options, chankan), "Chankan tile is in hand");
+    }
+
+    #[test]
+    pub fn calc_riichi_keeps_options() {
+        let mut hand = RiichiHand {
+            closed_part: vec![],
+            open_part: vec![],
+        };
+        let mut options = win_options();
+        assert_eq!(
+            calc_riichi(hand.clone(), &mut options, false)
+                .err()
+                .unwrap(),
+            "Incorrect number of tiles"
+        );
+
+        hand.closed_part = vec![
+            Tiles::M1 as i8,
+            Tiles::M2 as i8,
+            Tiles::M3 as i8,
+            Tiles::P2 as i8,
+            Tiles::P3 as i8,
+            Tiles::P4 as i8,
+            Tiles::S3 as i8,
+            Tiles::S4 as i8,
+            Tiles::S5 as i8,
+            Tiles::M7 as i8,
+            Tiles::M8 as i8,
+            Tiles::P5 as i8,
+            Tiles::P5 as i8,
+            Tiles::M9 as i8,
+        ];
+        let first = calc_riichi(hand.clone(), &mut options, false).unwrap();
+        assert_eq!(options.tile_discarded_by_someone, -1);
+        let second = calc_riichi(hand, &mut options, false).unwrap();
+        assert_eq!(first.outgoing_ten, second.outgoing_ten);
+        assert!(second.outgoing_ten.is_some());
+    }
 }
@@ -0,0 +1,1083 @@
+use crate::abortive::{check_sanchahou, check_suucha_riichi, check_suufon_renda, check_suukaikan};
+use crate::agari::find_waits;
+use crate::calls::{find_discard_calls, find_draw_actions};
+use crate::constants::tiles_to_34;
+use crate::game::{pay_tenpai, pay_wins};
+use crate::interfaces::{
+    CallOption, RiichiHand, RiichiOptions, RiichiResult, RoundAction, RoundConfig, RoundEnd,
+    RoundEvent, RoundPhase, RoundResult, RoundRules, RoundWin, WinKind,
+};
+use crate::kuikae::check_kuikae;
+use crate::riichi::{calc_win, split_hand};
+use crate::table::{CallInfo, Meld, Player, RED_FIVES, TableInfo, tile_id};
+
+const LIVE_WALL: usize = 122;
+
+// Splitmix64: enough to shuffle the wall reproducibly from a seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+struct CallWindow {
+    tile: u8,
+    from: usize,
+    chankan: bool,
+    options: Vec<Vec<RoundAction>>,
+    responses: Vec<Option<RoundAction>>,
+}
+
+pub struct Round {
+    rules: RoundRules,
+    dealer: usize,
+    bakaze: i8,
+    honba: i8,
+    riichi_sticks: i8,
+    start_scores: Vec<i32>,
+    scores: Vec<i32>,
+    live: Vec<u8>,
+    next: usize,
+    live_end: usize,
+    // Dead wall: rinshan tiles at 0-3, dora indicators at 4, 6, 8, 10, 12, uradora indicators after them
+    dead: Vec<u8>,
+    revealed: usize,
+    players: Vec<Player>,
+    phase: RoundPhase,
+    window: Option<CallWindow>,
+    last_call: Option<CallInfo>,
+    rinshan: bool,
+    riichi_pending: Option<usize>,
+    no_calls: bool,
+    events: Vec<RoundEvent>,
+    result: Option<RoundResult>,
+}
+
+impl Round {
+    // Start a round with the wall shuffled from the config seed
+    pub fn new(config: RoundConfig) -> Result<Round, String> {
+        let mut rng = Rng(config.seed);
+        let mut wall: Vec<u8> = (0..136).collect();
+        for i in (1..wall.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            wall.swap(i, j);
+        }
+        Round::from_wall(config, wall)
+    }
+
+    // Start a round with the given wall of 136-format tiles: 122 tiles of live wall in draw order,
+    // then 14 tiles of dead wall. Tiles are dealt 4-4-4-1 starting from the dealer.
+    pub fn from_wall(config: RoundConfig, wall: Vec<u8>) -> Result<Round, String> {
+        let mut seen = [false; 136];
+        for tile in &wall {
+            if *tile >= 136 || seen[*tile as usize] {
+                return Err("Incorrect wall".parse().unwrap());
+            }
+            seen[*tile as usize] = true;
+        }
+        if wall.len() != 136 {
+            return Err("Incorrect wall".parse().unwrap());
+        }
+        if config.scores.len() != 4 || !(0..4).contains(&config.dealer) {
+            return Err("Incorrect number of players".parse().unwrap());
+        }
+
+        let mut round = Round {
+            rules: config.rules,
+            dealer: config.dealer as usize,
+            bakaze: config.bakaze,
+            honba: config.honba,
+            riichi_sticks: config.riichi_sticks,
+            start_scores: config.scores.clone(),
+            scores: config.scores,
+            live: wall[..LIVE_WALL].to_vec(),
+            next: 0,
+            live_end: LIVE_WALL,
+            dead: wall[LIVE_WALL..].to_vec(),
+            revealed: 0,
+            players: vec![Player::default(); 4],
+            phase: RoundPhase::Turn(config.dealer),
+            window: None,
+            last_call: None,
+            rinshan: false,
+            riichi_pending: None,
+            no_calls: true,
+            events: Vec::new(),
+            result: None,
+        };
+
+        round.reveal_dora();
+        for count in [4, 4, 4, 1] {
+            for i in 0..4 {
+                let seat = (round.dealer + i) % 4;
+                for _ in 0..count {
+                    let tile = round.live[round.next];
+                    round.next += 1;
+                    round.players[seat].hand.push(tile);
+                }
+            }
+        }
+        for player in round.players.iter_mut() {
+            player.hand.sort();
+        }
+        round.draw(round.dealer, false);
+        Ok(round)
+    }
+
+    pub fn phase(&self) -> RoundPhase {
+        self.phase
+    }
+
+    pub fn result(&self) -> Option<&RoundResult> {
+        self.result.as_ref()
+    }
+
+    pub fn events(&self) -> &[RoundEvent] {
+        &self.events
+    }
+
+    pub fn scores(&self) -> Vec<i32> {
+        self.scores.clone()
+    }
+
+    pub fn riichi_sticks(&self) -> i8 {
+        self.riichi_sticks
+    }
+
+    pub fn tiles_left(&self) -> i8 {
+        (self.live_end - self.next) as i8
+    }
+
+    pub fn dora_indicators(&self) -> Vec<i8> {
+        (0..self.revealed)
+            .map(|i| tile_id(self.dead[4 + i * 2]))
+            .collect()
+    }
+
+    pub fn hand(&self, seat: i8) -> RiichiHand {
+        self.players[seat as usize].hand()
+    }
+
+    pub fn discards(&self, seat: i8) -> Vec<i8> {
+        self.players[seat as usize].discards.clone()
+    }
+
+    pub fn is_riichi(&self, seat: i8) -> bool {
+        self.players[seat as usize].riichi
+    }
+
+    pub fn legal_actions(&self, seat: i8) -> Vec<RoundAction> {
+        if !(0..4).contains(&seat) {
+            return Vec::new();
+        }
+        match self.phase {
+            RoundPhase::Turn(current) if current == seat => self.turn_actions(seat as usize),
+            RoundPhase::Calls(_) => {
+                let window = self.window.as_ref().unwrap();
+                let options = &window.options[seat as usize];
+                if options.is_empty() || window.responses[seat as usize].is_some() {
+                    return Vec::new();
+                }
+                let mut actions = options.clone();
+                actions.push(RoundAction::Pass);
+                actions
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn apply(&mut self, seat: i8, action: RoundAction) -> Result<(), String> {
+        if !self.legal_actions(seat).contains(&action) {
+            return Err("Illegal action".parse().unwrap());
+        }
+        let seat = seat as usize;
+
+        if let RoundPhase::Calls(_) = self.phase {
+            let window = self.window.as_mut().unwrap();
+            window.responses[seat] = Some(action);
+            let done =
+                (0..4).all(|s| window.options[s].is_empty() || window.responses[s].is_some());
+            if done {
+                self.resolve_calls()?;
+            }
+            return Ok(());
+        }
+
+        match action {
+            RoundAction::Discard(tile) => self.discard(seat, tile, false)?,
+            RoundAction::Riichi(tile) => self.discard(seat, tile, true)?,
+            RoundAction::Tsumo => {
+                let result = self.calc_win(seat, None, self.rinshan)?;
+                self.finish(
+                    RoundEnd::Agari,
+                    vec![RoundWin {
+                        seat: seat as i8,
+                        from: -1,
+                        result,
+                    }],
+                    Vec::new(),
+                );
+            }
+            RoundAction::Ankan(tile) => {
+                let tiles = (0..4).map(|_| self.take_tile(seat, tile)).collect();
+                self.players[seat].melds.push(Meld { open: false, tiles });
+                self.events.push(RoundEvent::Ankan {
+                    seat: seat as i8,
+                    tile,
+                });
+                self.declare_kan(seat);
+            }
+            RoundAction::Shouminkan(tile) => {
+                let added = self.take_tile(seat, tile);
+                let meld = self.players[seat]
+                    .melds
+                    .iter_mut()
+                    .find(|m| m.open && m.tiles.len() == 3 && tile_id(m.tiles[0]) == tile)
+                    .unwrap();
+                meld.tiles.push(added);
+                self.events.push(RoundEvent::Shouminkan {
+                    seat: seat as i8,
+                    tile,
+                });
+                self.interrupt();
+                self.open_calls(seat, added, true)?;
+            }
+            RoundAction::Kyuushu => self.finish(RoundEnd::KyuushuKyuuhai, Vec::new(), Vec::new()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn turn_actions(&self, seat: usize) -> Vec<RoundAction> {
+        let player = &self.players[seat];
+        let mut distinct: Vec<i8> = player.hand.iter().map(|t| tile_id(*t)).collect();
+        distinct.sort();
+        distinct.dedup();
+
+        // Only discards are allowed right after chi or pon
+        if let Some(call) = &self.last_call {
+            return distinct
+                .into_iter()
+                .filter(|t| {
+                    check_kuikae(&call.hand, &call.meld, call.called, *t, &self.rules.kuikae)
+                        .unwrap_or(false)
+                })
+                .map(RoundAction::Discard)
+                .collect();
+        }
+
+        let hand = self.hand(seat as i8);
+        let options = self.options(seat, None, self.rinshan, false);
+        let kan_allowed = self.kans_total() < 4 && self.tiles_left() > 0;
+        let mut actions: Vec<RoundAction> = Vec::new();
+        for action in
+            find_draw_actions(&hand, &options, self.rules.strict_ankan).unwrap_or_default()
+        {
+            match action {
+                CallOption::Tsumo(_) => actions.push(RoundAction::Tsumo),
+                CallOption::Ankan(tile) if kan_allowed => actions.push(RoundAction::Ankan(tile)),
+                CallOption::Shouminkan(tile) if kan_allowed => {
+                    actions.push(RoundAction::Shouminkan(tile))
+                }
+                CallOption::Kyuushu if self.rules.abortive.kyuushu_kyuuhai => {
+                    actions.push(RoundAction::Kyuushu)
+                }
+                _ => {}
+            }
+        }
+
+        if player.riichi {
+            actions.push(RoundAction::Discard(tile_id(*player.hand.last().unwrap())));
+            return actions;
+        }
+
+        let menzen = player.melds.iter().all(|m| !m.open);
+        if menzen && self.scores[seat] >= 1000 && self.tiles_left() >= 4 {
+            let (haipai, furo) = split_hand(&hand);
+            let mut haipai34 = tiles_to_34(&haipai);
+            for tile in &distinct {
+                haipai34[(*tile - 1) as usize] -= 1;
+                if !find_waits(&haipai34, &furo).is_empty() {
+                    actions.push(RoundAction::Riichi(*tile));
+                }
+                haipai34[(*tile - 1) as usize] += 1;
+            }
+        }
+
+        actions.extend(distinct.into_iter().map(RoundAction::Discard));
+        actions
+    }
+
+    fn options(
+        &self,
+        seat: usize,
+        ron_tile: Option<u8>,
+        after_kan: bool,
+        with_ura: bool,
+    ) -> RiichiOptions {
+        let indicators: Vec<u8> = (0..self.revealed).map(|i| self.dead[4 + i * 2]).collect();
+        let uradora: Vec<u8> = match with_ura {
+            true => (0..self.revealed).map(|i| self.dead[5 + i * 2]).collect(),
+            false => Vec::new(),
+        };
+        let table = TableInfo {
+            rules: &self.rules,
+            bakaze: self.bakaze,
+            dealer: self.dealer,
+            dora_indicators: &indicators,
+            no_calls: self.no_calls,
+            tiles_left: self.tiles_left(),
+        };
+        self.players[seat].options(seat, &table, ron_tile, after_kan, &uradora)
+    }
+
+    fn kans_total(&self) -> i8 {
+        self.players.iter().map(|p| p.kans()).sum()
+    }
+
+    // Remove tile from hand, keeping red five if possible
+    fn take_tile(&mut self, seat: usize, tile: i8) -> u8 {
+        let hand = &mut self.players[seat].hand;
+        let pos = hand
+            .iter()
+            .position(|t| tile_id(*t) == tile && !RED_FIVES.contains(t))
+            .or_else(|| hand.iter().position(|t| tile_id(*t) == tile))
+            .unwrap();
+        hand.remove(pos)
+    }
+
+    fn reveal_dora(&mut self) {
+        self.events.push(RoundEvent::DoraIndicator {
+            tile: tile_id(self.dead[4 + self.revealed * 2]),
+        });
+        self.revealed += 1;
+    }
+
+    fn draw(&mut self, seat: usize, rinshan: bool) {
+        let tile = if rinshan {
+            self.live_end -= 1;
+            self.dead[(self.kans_total() - 1) as usize]
+        } else {
+            self.next += 1;
+            self.live[self.next - 1]
+        };
+        self.players[seat].hand.push(tile);
+        self.rinshan = rinshan;
+        self.phase = RoundPhase::Turn(seat as i8);
+        self.events.push(RoundEvent::Draw {
+            seat: seat as i8,
+            tile: tile_id(tile),
+        });
+    }
+
+    // Any call breaks ippatsu and first go-around
+    fn interrupt(&mut self) {
+        self.no_calls = false;
+        for player in self.players.iter_mut() {
+            player.ippatsu = false;
+        }
+    }
+
+    fn declare_kan(&mut self, seat: usize) {
+        self.interrupt();
+        self.reveal_dora();
+        self.draw(seat, true);
+    }
+
+    fn discard(&mut self, seat: usize, tile: i8, riichi: bool) -> Result<(), String> {
+        let first_take = self.no_calls && self.players[seat].discards.is_empty();
+        let taken = if self.players[seat].riichi {
+            self.players[seat].hand.pop().unwrap()
+        } else {
+            self.take_tile(seat, tile)
+        };
+
+        let player = &mut self.players[seat];
+        player.hand.sort();
+        player.ippatsu = false;
+        if riichi {
+            player.riichi = true;
+            player.double_riichi = first_take;
+            player.ippatsu = true;
+            player.passed_since_riichi.clear();
+            self.riichi_pending = Some(seat);
+        }
+        player.passed_tiles.clear();
+        player.discards.push(tile);
+
+        self.last_call = None;
+        self.rinshan = false;
+        self.events.push(RoundEvent::Discard {
+            seat: seat as i8,
+            tile,
+            riichi,
+        });
+        self.open_calls(seat, taken, false)
+    }
+
+    fn open_calls(&mut self, from: usize, tile: u8, chankan: bool) -> Result<(), String> {
+        let called = tile_id(tile);
+        let mut options: Vec<Vec<RoundAction>> = vec![Vec::new(); 4];
+        for (seat, seat_options) in options.iter_mut().enumerate() {
+            if seat == from {
+                continue;
+            }
+            let riichi_options = self.options(seat, Some(tile), chankan, false);
+            let from_seat = ((from + 4 - seat) % 4) as i8;
+            let calls =
+                find_discard_calls(&self.hand(seat as i8), &riichi_options, called, from_seat)
+                    .unwrap_or_default();
+            for call in calls {
+                match call {
+                    // Ron is offered only if the win can be scored
+                    CallOption::Ron(_)
+                        if !self.players[seat].is_furiten()
+                            && self.calc_win(seat, Some((tile, from)), chankan).is_ok() =>
+                    {
+                        seat_options.push(RoundAction::Ron);
+                    }
+                    CallOption::Chi(meld)
+                        if !chankan
+                            && self.players[seat].can_discard_after_call(
+                                &meld,
+                                called,
+                                &self.rules.kuikae,
+                            ) =>
+                    {
+                        seat_options.push(RoundAction::Chi(meld));
+                    }
+                    CallOption::Pon(meld)
+                        if !chankan
+                            && self.players[seat].can_discard_after_call(
+                                &meld,
+                                called,
+                                &self.rules.kuikae,
+                            ) =>
+                    {
+                        seat_options.push(RoundAction::Pon);
+                    }
+                    CallOption::Daiminkan(_)
+                        if !chankan && self.kans_total() < 4 && self.tiles_left() > 0 =>
+                    {
+                        seat_options.push(RoundAction::Daiminkan);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let nobody = options.iter().all(|o| o.is_empty());
+        self.window = Some(CallWindow {
+            tile,
+            from,
+            chankan,
+            options,
+            responses: vec![None; 4],
+        });
+        self.phase = RoundPhase::Calls(from as i8);
+        if nobody {
+            return self.resolve_calls();
+        }
+        Ok(())
+    }
+
+    fn resolve_calls(&mut self) -> Result<(), String> {
+        let window = self.window.take().unwrap();
+        let called = tile_id(window.tile);
+        // Priority goes by turn order from the discarder
+        let order: Vec<usize> = (1..4).map(|i| (window.from + i) % 4).collect();
+        let responded = |action: &dyn Fn(&RoundAction) -> bool| -> Vec<usize> {
+            order
+                .iter()
+                .filter(|s| window.responses[**s].as_ref().is_some_and(action))
+                .copied()
+                .collect()
+        };
+
+        let rons = responded(&|a| *a == RoundAction::Ron);
+        if !rons.is_empty() {
+            if check_sanchahou(rons.len() as i8, &self.rules.abortive) {
+                self.riichi_pending = None;
+                self.finish(RoundEnd::Sanchahou, Vec::new(), Vec::new());
+                return Ok(());
+            }
+            let winners = if self.rules.multiple_ron {
+                rons
+            } else {
+                vec![rons[0]]
+            };
+            let mut wins = Vec::new();
+            for seat in winners {
+                let result =
+                    self.calc_win(seat, Some((window.tile, window.from)), window.chankan)?;
+                wins.push(RoundWin {
+                    seat: seat as i8,
+                    from: window.from as i8,
+                    result,
+                });
+            }
+            // Riichi is not accepted if its discard was called for ron
+            self.riichi_pending = None;
+            self.finish(RoundEnd::Agari, wins, Vec::new());
+            return Ok(());
+        }
+
+        for seat in &order {
+            let player = &mut self.players[*seat];
+            player.passed_tiles.push(called);
+            if player.riichi {
+                player.passed_since_riichi.push(called);
+            }
+        }
+
+        if window.chankan {
+            self.declare_kan(window.from);
+            return Ok(());
+        }
+
+        if let Some(seat) = self.riichi_pending.take() {
+            self.scores[seat] -= 1000;
+            self.riichi_sticks += 1;
+            self.events
+                .push(RoundEvent::RiichiAccepted { seat: seat as i8 });
+        }
+
+        let kans = responded(&|a| *a == RoundAction::Daiminkan);
+        let pons = responded(&|a| *a == RoundAction::Pon);
+        let chis = responded(&|a| matches!(a, RoundAction::Chi(_)));
+        if let Some(seat) = kans.first() {
+            self.call(*seat, &window, vec![called; 4]);
+            return Ok(());
+        }
+        if let Some(seat) = pons.first() {
+            self.call(*seat, &window, vec![called; 3]);
+            return Ok(());
+        }
+        if let Some(seat) = chis.first() {
+            if let Some(RoundAction::Chi(meld)) = &window.responses[*seat] {
+                self.call(*seat, &window, meld.clone());
+                return Ok(());
+            }
+        }
+
+        let riichi: Vec<bool> = self.players.iter().map(|p| p.riichi).collect();
+        let first_discards: Vec<i8> = self
+            .players
+            .iter()
+            .filter(|p| p.discards.len() == 1)
+            .map(|p| p.discards[0])
+            .collect();
+        let kans: Vec<i8> = self.players.iter().map(|p| p.kans()).collect();
+        if check_suucha_riichi(&riichi, &self.rules.abortive) {
+            self.finish(RoundEnd::SuuchaRiichi, Vec::new(), Vec::new());
+        } else if self.no_calls && check_suufon_renda(&first_discards, &self.rules.abortive) {
+            self.finish(RoundEnd::SuufonRenda, Vec::new(), Vec::new());
+        } else if check_suukaikan(&kans, &self.rules.abortive) {
+            self.finish(RoundEnd::Suukaikan, Vec::new(), Vec::new());
+        } else if self.tiles_left() == 0 {
+            self.exhaustive_draw();
+        } else {
+            self.draw((window.from + 1) % 4, false);
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, seat: usize, window: &CallWindow, meld: Vec<i8>) {
+        let called = tile_id(window.tile);
+        let before = self.hand(seat as i8);
+        let mut tiles = vec![window.tile];
+        let mut taken = false;
+        for tile in &meld {
+            if *tile == called && !taken {
+                taken = true;
+            } else {
+                tiles.push(self.take_tile(seat, *tile));
+            }
+        }
+        tiles.sort();
+        self.players[seat].melds.push(Meld { open: true, tiles });
+        self.interrupt();
+
+        let from = window.from as i8;
+        let event_seat = seat as i8;
+        match meld.len() {
+            4 => {
+                self.events.push(RoundEvent::Daiminkan {
+                    seat: event_seat,
+                    from,
+                    tiles: meld,
+                });
+                self.declare_kan(seat);
+            }
+            _ => {
+                self.events.push(if meld[0] == meld[1] {
+                    RoundEvent::Pon {
+                        seat: event_seat,
+                        from,
+                        tiles: meld.clone(),
+                    }
+                } else {
+                    RoundEvent::Chi {
+                        seat: event_seat,
+                        from,
+                        tiles: meld.clone(),
+                    }
+                });
+                self.last_call = Some(CallInfo {
+                    hand: before,
+                    meld,
+                    called,
+                });
+                self.rinshan = false;
+                self.phase = RoundPhase::Turn(event_seat);
+            }
+        }
+    }
+
+    // Score a win by tsumo, or by ron on the given tile discarded from the given seat
+    fn calc_win(
+        &self,
+        seat: usize,
+        ron: Option<(u8, usize)>,
+        after_kan: bool,
+    ) -> Result<RiichiResult, String> {
+        let win = match ron {
+            None => WinKind::Tsumo(tile_id(*self.players[seat].hand.last().unwrap())),
+            Some((tile, from)) => WinKind::Ron {
+                tile: tile_id(tile),
+                from_seat: ((from + 4 - seat) % 4) as i8,
+            },
+        };
+        let options = self.options(seat, ron.map(|(tile, _)| tile), after_kan, true);
+        calc_win(&self.hand(seat as i8), &options, win, false)
+    }
+
+    fn exhaustive_draw(&mut self) {
+        let tenpai: Vec<bool> = self
+            .players
+            .iter()
+            .map(|p| {
+                // Waits held four times in hand and melds don't count, same as for riichi
+                let (haipai, furo) = split_hand(&p.hand());
+                !find_waits(&tiles_to_34(&haipai), &furo).is_empty()
+            })
+            .collect();
+        pay_tenpai(&mut self.scores, &tenpai);
+        self.finish(RoundEnd::Ryuukyoku, Vec::new(), tenpai);
+    }
+
+    fn finish(&mut self, end: RoundEnd, wins: Vec<RoundWin>, tenpai: Vec<bool>) {
+        pay_wins(
+            &mut self.scores,
+            &mut self.riichi_sticks,
+            self.dealer,
+            self.honba,
+            self.rules.multiple_ron_honba,
+            &wins,
+        );
+
+        self.phase = RoundPhase::Ended;
+        self.result = Some(RoundResult {
+            end,
+            wins,
+            tenpai,
+            deltas: (0..4)
+                .map(|s| self.scores[s] - self.start_scores[s])
+                .collect(),
+            scores: self.scores.clone(),
+            riichi_sticks: self.riichi_sticks,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{Tiles, Yaku};
+    use crate::interfaces::{AbortiveDrawRules, KuikaeRules};
+    use crate::shanten::calc_shanten;
+
+    fn config(seed: u64) -> RoundConfig {
+        RoundConfig {
+            seed,
+            dealer: 0,
+            bakaze: Tiles::E as i8,
+            honba: 1,
+            riichi_sticks: 1,
+            scores: vec![25000, 25000, 25000, 24000],
+            rules: RoundRules {
+                allow_aka: true,
+                allow_kuitan: true,
+                with_kiriage: false,
+                multiple_ron: true,
+                multiple_ron_honba: false,
+                strict_ankan: false,
+                kuikae: KuikaeRules {
+                    forbid_genbutsu: true,
+                    forbid_suji: true,
+                },
+                abortive: AbortiveDrawRules {
+                    kyuushu_kyuuhai: true,
+                    suufon_renda: true,
+                    suucha_riichi: true,
+                    suukaikan: true,
+                    sanchahou: true,
+                },
+                disabled_yaku: vec![],
+                local_yaku_enabled: vec![],
+                all_local_yaku_enabled: false,
+                allow_double_yakuman: false,
+            },
+        }
+    }
+
+    // Wins when possible, calls pon, declares riichi and otherwise discards the tile keeping best shanten
+    fn bot_action(round: &Round, seat: i8) -> RoundAction {
+        let actions = round.legal_actions(seat);
+        for preferred in [RoundAction::Tsumo, RoundAction::Ron, RoundAction::Pon] {
+            if actions.contains(&preferred) {
+                return preferred;
+            }
+        }
+        if let Some(riichi) = actions.iter().find(|a| matches!(a, RoundAction::Riichi(_))) {
+            return riichi.clone();
+        }
+        let closed = round.hand(seat).closed_part;
+        let mut best: Option<(i8, RoundAction)> = None;
+        for action in &actions {
+            if let RoundAction::Discard(tile) = action {
+                let mut tiles = closed.clone();
+                let pos = tiles.iter().position(|t| t == tile).unwrap();
+                tiles.remove(pos);
+                let shanten = calc_shanten(&tiles_to_34(&tiles));
+                if best.as_ref().is_none_or(|(s, _)| shanten < *s) {
+                    best = Some((shanten, action.clone()));
+                }
+            }
+        }
+        best.map(|(_, a)| a).unwrap_or(RoundAction::Pass)
+    }
+
+    // Wall dealing the given tiles to each seat with the dealer at seat 0, then drawing the given tiles;
+    // dead wall tiles are given by their index in it and other tiles follow in order
+    fn wall(hands: [&[u8]; 4], draws: &[u8], dead: &[(usize, u8)]) -> Vec<u8> {
+        let mut positions: Vec<(usize, u8)> = Vec::new();
+        for (seat, hand) in hands.iter().enumerate() {
+            let dealt = (0..3)
+                .flat_map(|block| (0..4).map(move |i| block * 16 + seat * 4 + i))
+                .chain([48 + seat]);
+            positions.extend(dealt.zip(hand.iter().copied()));
+        }
+        positions.extend(draws.iter().enumerate().map(|(i, t)| (52 + i, *t)));
+        positions.extend(dead.iter().map(|(i, t)| (LIVE_WALL + i, *t)));
+        let mut rest = (0..136).filter(|t| !positions.iter().any(|(_, p)| p == t));
+        (0..136)
+            .map(|i| match positions.iter().find(|(p, _)| *p == i) {
+                Some((_, tile)) => *tile,
+                None => rest.next().unwrap(),
+            })
+            .collect()
+    }
+
+    // Everyone discards the drawn tile and passes on calls, the turn is checked before the discard
+    fn play_tsumogiri(round: &mut Round, mut check_turn: impl FnMut(&Round, i8)) {
+        while let RoundPhase::Turn(seat) | RoundPhase::Calls(seat) = round.phase() {
+            if round.phase() == RoundPhase::Turn(seat) {
+                check_turn(round, seat);
+                let tile = tile_id(*round.players[seat as usize].hand.last().unwrap());
+                round.apply(seat, RoundAction::Discard(tile)).unwrap();
+                continue;
+            }
+            pass_calls(round);
+        }
+    }
+
+    // Everyone who may call the last tile passes
+    fn pass_calls(round: &mut Round) {
+        for seat in 0..4 {
+            if matches!(round.phase(), RoundPhase::Calls(_))
+                && !round.legal_actions(seat).is_empty()
+            {
+                round.apply(seat, RoundAction::Pass).unwrap();
+            }
+        }
+    }
+
+    fn play(seed: u64) -> Round {
+        let mut round = Round::new(config(seed)).unwrap();
+        while round.phase() != RoundPhase::Ended {
+            let seat = (0..4)
+                .find(|s| !round.legal_actions(*s).is_empty())
+                .unwrap();
+            let action = bot_action(&round, seat);
+            round.apply(seat, action).unwrap();
+        }
+        round
+    }
+
+    #[test]
+    pub fn should_play_rounds_to_the_end() {
+        for seed in 0..12 {
+            let round = play(seed);
+            let result = round.result().unwrap();
+            let total: i32 = result.scores.iter().sum();
+            assert_eq!(total + result.riichi_sticks as i32 * 1000, 100000);
+            assert_eq!(
+                result.deltas.iter().sum::<i32>(),
+                1000 - result.riichi_sticks as i32 * 1000
+            );
+            if result.end == RoundEnd::Agari {
+                assert!(!result.wins.is_empty());
+                assert_eq!(result.riichi_sticks, 0);
+            }
+        }
+    }
+
+    #[test]
+    pub fn should_be_deterministic() {
+        let first = play(42);
+        let second = play(42);
+        assert_eq!(
+            first.result().unwrap().scores,
+            second.result().unwrap().scores
+        );
+        assert_eq!(first.events().len(), second.events().len());
+        assert_eq!(first.dora_indicators(), second.dora_indicators());
+    }
+
+    #[test]
+    pub fn should_score_tenhou() {
+        // 123m 456p 789s EEE 11p for the dealer: positions 0-3, 16-19, 32-35, 48 and the first draw at 52
+        let hand: Vec<u8> = vec![0, 4, 8, 48, 52, 56, 96, 100, 104, 108, 109, 110, 36, 37];
+        let positions = [0, 1, 2, 3, 16, 17, 18, 19, 32, 33, 34, 35, 48, 52];
+        let mut rest: Vec<u8> = (0..136).filter(|t| !hand.contains(t)).collect();
+        let mut wall: Vec<u8> = Vec::new();
+        for i in 0..136 {
+            match positions.iter().position(|p| *p == i) {
+                Some(pos) => wall.push(hand[pos]),
+                None => wall.push(rest.remove(0)),
+            }
+        }
+
+        let mut round = Round::from_wall(config(0), wall).unwrap();
+        assert_eq!(round.phase(), RoundPhase::Turn(0));
+        assert!(round.legal_actions(0).contains(&RoundAction::Tsumo));
+        assert!(round.legal_actions(1).is_empty());
+        assert!(round.apply(1, RoundAction::Pass).is_err());
+
+        round.apply(0, RoundAction::Tsumo).unwrap();
+        let result = round.result().unwrap();
+        assert_eq!(result.end, RoundEnd::Agari);
+        assert_eq!(result.wins[0].result.yakuman, 1);
+        assert!(
+            result.wins[0]
+                .result
+                .yaku
+                .contains(&(Yaku::Tenhou as i8, 13))
+        );
+        assert_eq!(
+            result.deltas,
+            vec![48000 + 300 + 1000, -16100, -16100, -16100]
+        );
+    }
+
+    #[test]
+    pub fn should_count_red_five_taken_by_ron() {
+        // The dealer holds only the red 5m, 46m 234p 567p 345s 22s for the next player waits on it
+        let dealer: Vec<u8> = vec![16];
+        let hand: Vec<u8> = vec![12, 20, 40, 44, 48, 53, 56, 60, 80, 84, 89, 76, 77];
+        let positions = [4, 5, 6, 7, 20, 21, 22, 23, 36, 37, 38, 39, 49];
+        let mut rest: Vec<u8> = (0..136)
+            .filter(|t| !dealer.contains(t) && !hand.contains(t))
+            .collect();
+        let mut wall: Vec<u8> = Vec::new();
+        for i in 0..136 {
+            match positions.iter().position(|p| *p == i) {
+                Some(pos) => wall.push(hand[pos]),
+                None if i == 0 => wall.push(dealer[0]),
+                None => wall.push(rest.remove(0)),
+            }
+        }
+
+        let mut round = Round::from_wall(config(0), wall).unwrap();
+        round
+            .apply(0, RoundAction::Discard(Tiles::M5 as i8))
+            .unwrap();
+        assert!(round.legal_actions(1).contains(&RoundAction::Ron));
+        round.apply(1, RoundAction::Ron).unwrap();
+        for seat in 2..4 {
+            if !round.legal_actions(seat).is_empty() {
+                round.apply(seat, RoundAction::Pass).unwrap();
+            }
+        }
+
+        let result = round.result().unwrap();
+        assert_eq!(result.end, RoundEnd::Agari);
+        assert_eq!(result.wins[0].seat, 1);
+        assert!(
+            result.wins[0]
+                .result
+                .yaku
+                .contains(&(Yaku::Akadora as i8, 1))
+        );
+    }
+
+    #[test]
+    pub fn should_not_count_tenpai_on_tiles_held_in_melds() {
+        // The next player pons 1m from 111m 234p 567p 789s E and keeps the last 1m as a tanki wait
+        let hand: Vec<u8> = vec![1, 2, 3, 40, 44, 48, 53, 56, 60, 96, 100, 104, 108];
+        let mut round =
+            Round::from_wall(config(0), wall([&[0], &hand, &[], &[]], &[], &[])).unwrap();
+        round
+            .apply(0, RoundAction::Discard(Tiles::M1 as i8))
+            .unwrap();
+        round.apply(1, RoundAction::Pon).unwrap();
+        pass_calls(&mut round);
+        round
+            .apply(1, RoundAction::Discard(Tiles::E as i8))
+            .unwrap();
+        play_tsumogiri(&mut round, |_, _| {});
+
+        let result = round.result().unwrap();
+        assert_eq!(result.end, RoundEnd::Ryuukyoku);
+        let closed = round.hand(1).closed_part;
+        assert_eq!(calc_shanten(&tiles_to_34(&closed)), 0);
+        assert!(!result.tenpai[1]);
+    }
+
+    // 234m 345m 678s 22p 46p: tanyao waiting on 5p
+    const KANCHAN_5P: [u8; 13] = [4, 8, 12, 9, 13, 17, 92, 96, 100, 40, 41, 48, 56];
+    // 555p 234s 345s 678m E: pon or kan on 5p
+    const TRIPLE_5P: [u8; 13] = [52, 54, 55, 76, 80, 84, 81, 85, 89, 20, 24, 28, 108];
+
+    // The dealer discards 5p first
+    fn discard_5p(hands: [&[u8]; 4], dead: &[(usize, u8)]) -> Round {
+        let mut round = Round::from_wall(config(0), wall(hands, &[], dead)).unwrap();
+        round
+            .apply(0, RoundAction::Discard(Tiles::P5 as i8))
+            .unwrap();
+        round
+    }
+
+    #[test]
+    pub fn should_prefer_ron_to_pon_and_kan() {
+        for call in [RoundAction::Pon, RoundAction::Daiminkan] {
+            let mut round = discard_5p([&[53], &KANCHAN_5P, &TRIPLE_5P, &[]], &[]);
+            round.apply(2, call).unwrap();
+            round.apply(1, RoundAction::Ron).unwrap();
+            pass_calls(&mut round);
+
+            let result = round.result().unwrap();
+            assert_eq!(result.end, RoundEnd::Agari);
+            assert_eq!(result.wins.len(), 1);
+            assert_eq!((result.wins[0].seat, result.wins[0].from), (1, 0));
+            assert!(round.players[2].melds.is_empty());
+        }
+    }
+
+    #[test]
+    pub fn should_prefer_pon_to_chi() {
+        let mut round = discard_5p([&[53], &KANCHAN_5P, &TRIPLE_5P, &[]], &[]);
+        let chi = round
+            .legal_actions(1)
+            .into_iter()
+            .find(|a| matches!(a, RoundAction::Chi(_)))
+            .unwrap();
+        round.apply(1, chi).unwrap();
+        round.apply(2, RoundAction::Pon).unwrap();
+        pass_calls(&mut round);
+
+        assert_eq!(round.phase(), RoundPhase::Turn(2));
+        assert!(round.players[1].melds.is_empty());
+        assert_eq!(round.players[2].melds[0].tiles.len(), 3);
+        assert!(matches!(
+            round.events().last(),
+            Some(RoundEvent::Pon {
+                seat: 2,
+                from: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    pub fn should_abort_on_three_rons() {
+        // 234s 345s 678m 88p 34p and 678p 678p 567m 666s 5p wait on 5p too
+        let second: Vec<u8> = vec![76, 80, 84, 81, 85, 89, 20, 24, 28, 64, 65, 44, 49];
+        let third: Vec<u8> = vec![57, 60, 66, 58, 61, 67, 18, 21, 25, 93, 94, 95, 54];
+        let mut round = discard_5p([&[53], &KANCHAN_5P, &second, &third], &[]);
+        for seat in 1..4 {
+            round.apply(seat, RoundAction::Ron).unwrap();
+        }
+
+        let result = round.result().unwrap();
+        assert_eq!(result.end, RoundEnd::Sanchahou);
+        assert!(result.wins.is_empty());
+        assert_eq!(result.deltas, vec![0; 4]);
+    }
+
+    #[test]
+    pub fn should_draw_rinshan_and_reveal_dora_after_daiminkan() {
+        // Rinshan tile is chun, the kan dora indicator is hatsu
+        let dead = [(0, 132), (6, 128)];
+        let mut round = discard_5p([&[53], &[], &TRIPLE_5P, &[]], &dead);
+        let tiles_left = round.tiles_left();
+        round.apply(2, RoundAction::Daiminkan).unwrap();
+        pass_calls(&mut round);
+
+        assert_eq!(round.phase(), RoundPhase::Turn(2));
+        assert_eq!(round.players[2].melds[0].tiles.len(), 4);
+        assert_eq!(round.players[2].hand.last(), Some(&132));
+        assert_eq!(round.dora_indicators()[1..], [Tiles::GD as i8]);
+        assert_eq!(round.tiles_left(), tiles_left - 1);
+    }
+
+    #[test]
+    pub fn should_draw_rinshan_and_reveal_dora_after_ankan() {
+        // The dealer holds four chun; rinshan tile is hatsu, the kan dora indicator is haku
+        let dead = [(0, 128), (6, 124)];
+        let wall = wall([&[132, 133, 134, 135], &[], &[], &[]], &[], &dead);
+        let mut round = Round::from_wall(config(0), wall).unwrap();
+        let tiles_left = round.tiles_left();
+        round.apply(0, RoundAction::Ankan(Tiles::RD as i8)).unwrap();
+
+        assert_eq!(round.phase(), RoundPhase::Turn(0));
+        assert!(!round.players[0].melds[0].open);
+        assert_eq!(round.players[0].hand.last(), Some(&128));
+        assert_eq!(round.dora_indicators()[1..], [Tiles::WD as i8]);
+        assert_eq!(round.tiles_left(), tiles_left - 1);
+    }
+
+    #[test]
+    pub fn should_refuse_riichi_with_less_than_four_tiles_left() {
+        // 123m 456m 789m 123p 9p and 123s 456s 789s EEE S stay tenpai while discarding drawn tiles
+        let first: Vec<u8> = vec![0, 4, 8, 12, 17, 20, 24, 28, 32, 36, 40, 44, 68];
+        let second: Vec<u8> = vec![72, 76, 80, 84, 89, 92, 96, 100, 104, 108, 109, 110, 112];
+        let wall = wall([&[], &first, &second, &[]], &[], &[]);
+        let mut round = Round::from_wall(config(0), wall).unwrap();
+        let mut checked = Vec::new();
+        play_tsumogiri(&mut round, |round, seat| {
+            if seat == 1 || seat == 2 {
+                let riichi = round
+                    .legal_actions(seat)
+                    .iter()
+                    .any(|a| matches!(a, RoundAction::Riichi(_)));
+                assert_eq!(riichi, round.tiles_left() >= 4, "{}", round.tiles_left());
+                checked.push(round.tiles_left());
+            }
+        });
+        assert!(checked.contains(&4) && checked.contains(&3));
+    }
+
+    #[test]
+    pub fn should_reject_wrong_wall() {
+        assert!(Round::from_wall(config(0), (0..135).collect()).is_err());
+        assert!(Round::from_wall(config(0), vec![0; 136]).is_err());
+    }
+}
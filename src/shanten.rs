@@ -1,5 +1,7 @@
 use crate::constants::sum;
-use crate::interfaces::HairiResult;
+use crate::constants::tiles_to_34;
+use crate::interfaces::{HairiResult, HandForm, RiichiHand, ShantenForms};
+use crate::riichi::split_hand;
 
 // Ported from https://github.com/MahjongRepository/mahjong/blob/master/mahjong/shanten.py
 // Implements tenhou.net logic for shanten calculation; hairi calculation taken from https://github.com/takayama-lily/riichi
@@ -9,7 +11,7 @@ const HONOR_INDICES: &[usize] = &[27, 28, 29, 30, 31, 32, 33];
 const TERMINAL_INDICES: &[usize] = &[0, 8, 9, 17, 18, 26];
 
 struct Shanten {
-    tiles: Vec<i8>,
+    tiles: [i8; 34],
     number_melds: i8,
     number_tatsu: i8,
     number_pairs: i8,
@@ -17,12 +19,14 @@ struct Shanten {
     number_characters: i32,
     number_isolated_tiles: i32,
     min_shanten: i8,
+    #[cfg(feature = "shanten-table")]
+    leaves: Option<Vec<(i8, i8, i8, i32)>>,
 }
 
 impl Shanten {
     fn new() -> Self {
         Shanten {
-            tiles: Vec::new(),
+            tiles: [0; 34],
             number_melds: 0,
             number_tatsu: 0,
             number_pairs: 0,
@@ -30,6 +34,8 @@ impl Shanten {
             number_characters: 0,
             number_isolated_tiles: 0,
             min_shanten: 8,
+            #[cfg(feature = "shanten-table")]
+            leaves: None,
         }
     }
 
@@ -39,47 +45,61 @@ impl Shanten {
         use_chiitoitsu: bool,
         use_kokushi: bool,
     ) -> i8 {
-        let mut shanten_results = vec![self.calculate_shanten_for_regular_hand(&tiles_34.to_vec())];
+        #[cfg(feature = "shanten-table")]
+        if let Some(shanten) = crate::shanten_table::shanten(tiles_34, use_chiitoitsu, use_kokushi)
+        {
+            return shanten;
+        }
+
+        let mut shanten = self.calculate_shanten_for_regular_hand(tiles_34);
 
         if use_chiitoitsu {
-            shanten_results.push(self.calculate_shanten_for_chiitoitsu_hand(tiles_34));
+            shanten = shanten.min(self.calculate_shanten_for_chiitoitsu_hand(tiles_34));
         }
         if use_kokushi {
-            shanten_results.push(self.calculate_shanten_for_kokushi_hand(tiles_34));
+            shanten = shanten.min(self.calculate_shanten_for_kokushi_hand(tiles_34));
         }
 
-        *shanten_results.iter().min().unwrap()
+        shanten
     }
 
     fn calculate_shanten_for_chiitoitsu_hand(&self, tiles_34: &[i8]) -> i8 {
-        let pairs = tiles_34.iter().filter(|&&x| x >= 2).count();
+        let mut pairs = 0;
+        let mut kinds = 0;
+        for count in tiles_34 {
+            if *count >= 2 {
+                pairs += 1;
+            }
+            if *count >= 1 {
+                kinds += 1;
+            }
+        }
         if pairs == 7 {
             return AGARI_STATE;
         }
 
-        let kinds = tiles_34.iter().filter(|&&x| x >= 1).count();
-        6 - (pairs as i8) + if kinds < 7 { 7 - (kinds as i8) } else { 0 }
+        6 - pairs + if kinds < 7 { 7 - kinds } else { 0 }
     }
 
     fn calculate_shanten_for_kokushi_hand(&self, tiles_34: &[i8]) -> i8 {
-        let indices: Vec<usize> = TERMINAL_INDICES
-            .iter()
-            .chain(HONOR_INDICES.iter())
-            .cloned()
-            .collect();
-
-        let completed_terminals = indices.iter().filter(|&&i| tiles_34[i] >= 2).count();
-
-        let terminals = indices.iter().filter(|&&i| tiles_34[i] != 0).count();
+        let mut completed_terminals = 0;
+        let mut terminals = 0;
+        for i in TERMINAL_INDICES.iter().chain(HONOR_INDICES.iter()) {
+            if tiles_34[*i] >= 2 {
+                completed_terminals += 1;
+            }
+            if tiles_34[*i] != 0 {
+                terminals += 1;
+            }
+        }
 
-        13 - (terminals as i8) - if completed_terminals > 0 { 1 } else { 0 }
+        13 - terminals - if completed_terminals > 0 { 1 } else { 0 }
     }
 
     fn calculate_shanten_for_regular_hand(&mut self, tiles_34: &[i8]) -> i8 {
-        let tiles = tiles_34.to_vec();
-        self.init(&tiles);
+        self.init(tiles_34);
 
-        let count_of_tiles: i8 = tiles.iter().sum();
+        let count_of_tiles: i8 = tiles_34.iter().sum();
         assert!(count_of_tiles <= 14, "Too many tiles = {}", count_of_tiles);
 
         self.remove_character_tiles(count_of_tiles);
@@ -91,7 +111,7 @@ impl Shanten {
     }
 
     fn init(&mut self, tiles: &[i8]) {
-        self.tiles = tiles.to_vec();
+        self.tiles.copy_from_slice(tiles);
         self.number_melds = 0;
         self.number_tatsu = 0;
         self.number_pairs = 0;
@@ -236,6 +256,17 @@ impl Shanten {
     }
 
     fn update_result(&mut self) {
+        #[cfg(feature = "shanten-table")]
+        if let Some(leaves) = self.leaves.as_mut() {
+            leaves.push((
+                self.number_melds,
+                self.number_tatsu,
+                self.number_pairs,
+                self.number_isolated_tiles,
+            ));
+            return;
+        }
+
         let mut ret_shanten = 8 - self.number_melds * 2 - self.number_tatsu - self.number_pairs;
         let mut n_mentsu_kouho = self.number_melds + self.number_tatsu;
 
@@ -361,18 +392,190 @@ impl Shanten {
     }
 }
 
+// Melds, tatsu, pairs and isolated tiles of every decomposition the search tries for one suit
+#[cfg(feature = "shanten-table")]
+pub(crate) fn suit_leaves(suit: &[i8]) -> Vec<(i8, i8, i8, i32)> {
+    let mut tiles = vec![0; 34];
+    tiles[..9].copy_from_slice(suit);
+    let mut shanten = Shanten::new();
+    shanten.init(&tiles);
+    shanten.leaves = Some(Vec::new());
+    shanten.run(0);
+    shanten.leaves.unwrap()
+}
+
+// Search-based shanten of regular hand, used to check the lookup table
+#[cfg(all(test, feature = "shanten-table"))]
+pub(crate) fn regular_shanten_by_search(tiles_34: &[i8]) -> i8 {
+    Shanten::new().calculate_shanten_for_regular_hand(tiles_34)
+}
+
 pub fn calc_shanten(tiles_34: &[i8]) -> i8 {
     let mut shanten = Shanten::new();
     shanten.calculate_shanten(tiles_34, true, true)
 }
 
+// Shanten of one form only; chiitoitsu and kokushi are not possible with less than 13 closed tiles
+pub fn calc_shanten_for_form(tiles_34: &[i8], form: HandForm) -> Option<i8> {
+    let mut shanten = Shanten::new();
+    match form {
+        HandForm::Regular => Some(shanten.calculate_shanten(tiles_34, false, false)),
+        _ if tiles_34.iter().sum::<i8>() < 13 => None,
+        HandForm::Chiitoitsu => Some(shanten.calculate_shanten_for_chiitoitsu_hand(tiles_34)),
+        HandForm::Kokushi => Some(shanten.calculate_shanten_for_kokushi_hand(tiles_34)),
+    }
+}
+
+pub fn calc_shanten_forms(tiles_34: &[i8]) -> ShantenForms {
+    let regular = calc_shanten_for_form(tiles_34, HandForm::Regular).unwrap();
+    let chiitoitsu = calc_shanten_for_form(tiles_34, HandForm::Chiitoitsu);
+    let kokushi = calc_shanten_for_form(tiles_34, HandForm::Kokushi);
+    let mut best = (HandForm::Regular, regular);
+    for (form, shanten) in [
+        (HandForm::Chiitoitsu, chiitoitsu),
+        (HandForm::Kokushi, kokushi),
+    ] {
+        if let Some(shanten) = shanten.filter(|s| *s < best.1) {
+            best = (form, shanten);
+        }
+    }
+    ShantenForms {
+        regular,
+        chiitoitsu,
+        kokushi,
+        best: best.0,
+        shanten: best.1,
+    }
+}
+
+// Shanten of closed part with melds: chiitoitsu and kokushi are not possible after calls or kans
+pub fn calc_shanten_for_hand(hand: &RiichiHand) -> i8 {
+    let (haipai, _) = split_hand(hand);
+    shanten_with_melds(&tiles_to_34(&haipai))
+}
+
+// Closed part of less than 13 tiles has melds, only the regular form counts then
+pub(crate) fn shanten_with_melds(tiles_34: &[i8]) -> i8 {
+    let closed = tiles_34.iter().sum::<i8>() >= 13;
+    Shanten::new().calculate_shanten(tiles_34, closed, closed)
+}
+
 pub fn hairi(tiles_34: &mut Vec<i8>) -> Option<HairiResult> {
-    let sht = calc_shanten(tiles_34);
+    let forms = HairiForms::Regular {
+        chiitoitsu: true,
+        kokushi: true,
+    };
+    hairi_by(tiles_34, None, forms)
+}
+
+// Hairi aiming for one form only, none if the form is not possible or the hand is complete in it
+pub fn hairi_for_form(tiles_34: &mut Vec<i8>, form: HandForm) -> Option<HairiResult> {
+    calc_shanten_for_form(tiles_34, form)?;
+    let forms = match form {
+        HandForm::Regular => HairiForms::Regular {
+            chiitoitsu: false,
+            kokushi: false,
+        },
+        HandForm::Chiitoitsu => HairiForms::Chiitoitsu,
+        HandForm::Kokushi => HairiForms::Kokushi,
+    };
+    hairi_by(tiles_34, None, forms)
+}
+
+// Hairi of closed part with melds, see calc_shanten_for_hand
+pub fn hairi_for_hand(hand: &RiichiHand) -> Option<HairiResult> {
+    let (haipai, furo) = split_hand(hand);
+    hairi_with_melds(&mut tiles_to_34(&haipai), &furo)
+}
+
+// Tiles held in melds, including kans, can't be waited on
+pub(crate) fn hairi_with_melds(tiles_34: &mut Vec<i8>, furo: &[Vec<i8>]) -> Option<HairiResult> {
+    let mut held = vec![0; 34];
+    for tile in furo.iter().flatten() {
+        held[(tile.abs() - 1) as usize] += 1;
+    }
+    // Same forms as calc_shanten_forms: hairi changes a full closed hand only between 13 and 14 tiles
+    let closed = sum(tiles_34) >= 13;
+    let forms = HairiForms::Regular {
+        chiitoitsu: closed,
+        kokushi: closed,
+    };
+    hairi_by(tiles_34, Some(&held), forms)
+}
+
+// Forms hairi counts shanten for
+#[derive(Clone, Copy)]
+enum HairiForms {
+    Regular { chiitoitsu: bool, kokushi: bool },
+    Chiitoitsu,
+    Kokushi,
+}
+
+// Shanten of hands differing from one hand in a single tile.
+// The lookup table looks up only the suit of the changed tile again.
+struct ChangedShanten {
+    forms: HairiForms,
+    #[cfg(feature = "shanten-table")]
+    hand: Option<crate::shanten_table::TableHand>,
+}
+
+impl ChangedShanten {
+    #[cfg_attr(not(feature = "shanten-table"), allow(unused_variables))]
+    fn new(forms: HairiForms, tiles_34: &[i8]) -> Self {
+        ChangedShanten {
+            forms,
+            #[cfg(feature = "shanten-table")]
+            hand: match forms {
+                HairiForms::Regular { .. } => crate::shanten_table::TableHand::new(tiles_34),
+                _ => None,
+            },
+        }
+    }
+
+    fn shanten(&self, tiles_34: &[i8]) -> i8 {
+        let mut shanten = Shanten::new();
+        match self.forms {
+            HairiForms::Regular {
+                chiitoitsu,
+                kokushi,
+            } => shanten.calculate_shanten(tiles_34, chiitoitsu, kokushi),
+            HairiForms::Chiitoitsu => shanten.calculate_shanten_for_chiitoitsu_hand(tiles_34),
+            HairiForms::Kokushi => shanten.calculate_shanten_for_kokushi_hand(tiles_34),
+        }
+    }
+
+    // Shanten after the count of tile at index changed in tiles_34
+    #[cfg(feature = "shanten-table")]
+    fn changed(&self, tiles_34: &[i8], index: usize) -> i8 {
+        let changed = self.hand.and_then(|hand| hand.changed(tiles_34, index));
+        match (self.forms, changed) {
+            (
+                HairiForms::Regular {
+                    chiitoitsu,
+                    kokushi,
+                },
+                Some(hand),
+            ) => hand.shanten(chiitoitsu, kokushi),
+            _ => self.shanten(tiles_34),
+        }
+    }
+
+    #[cfg(not(feature = "shanten-table"))]
+    fn changed(&self, tiles_34: &[i8], _index: usize) -> i8 {
+        self.shanten(tiles_34)
+    }
+}
+
+// Held tiles are counted with closed ones to skip waits on the fifth copy
+fn hairi_by(tiles_34: &mut Vec<i8>, held: Option<&[i8]>, forms: HairiForms) -> Option<HairiResult> {
+    let hand = ChangedShanten::new(forms, tiles_34);
+    let sht = hand.shanten(tiles_34);
 
     let mut res: HairiResult = HairiResult {
         now: sht,
         wait: Vec::new(),
         waits_after_discard: Vec::new(),
+        riichi_after_discard: Vec::new(),
     };
 
     if sht == -1 {
@@ -380,13 +583,19 @@ pub fn hairi(tiles_34: &mut Vec<i8>) -> Option<HairiResult> {
     }
 
     let calc_hairi = |tiles_34: &mut Vec<i8>, current_index: i8| -> Vec<i8> {
+        let hand = ChangedShanten::new(forms, tiles_34);
         let mut waits: Vec<i8> = Vec::new();
         for i in 0..34 {
             if i == current_index {
                 continue;
             }
+            // Fifth copy can't be drawn
+            let held_count = held.map_or(0, |held| held[i as usize]);
+            if tiles_34[i as usize] + held_count >= 4 {
+                continue;
+            }
             tiles_34[i as usize] += 1;
-            if calc_shanten(tiles_34) < sht {
+            if hand.changed(tiles_34, i as usize) < sht {
                 waits.push(i);
             }
             tiles_34[i as usize] -= 1;
@@ -407,7 +616,7 @@ pub fn hairi(tiles_34: &mut Vec<i8>) -> Option<HairiResult> {
             continue;
         }
         tiles_34[i] -= 1;
-        if calc_shanten(tiles_34) == sht {
+        if hand.changed(tiles_34, i) == sht {
             waits_after_discard.push((i as i8, calc_hairi(tiles_34, i as i8)));
         }
         tiles_34[i] += 1;
@@ -420,8 +629,17 @@ pub fn hairi(tiles_34: &mut Vec<i8>) -> Option<HairiResult> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::Tiles;
     use crate::constants::Tiles::*;
 
+    fn tiles_to_34(tiles: Vec<Tiles>) -> Vec<i8> {
+        let mut result = vec![0; 34];
+        for tile in tiles {
+            result[(tile as usize) - 1] += 1;
+        }
+        result
+    }
+
     #[test]
     pub fn shanten_13tiles_works() {
         // Hand with 13 tiles but not tenpai
@@ -1577,6 +1795,20 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn hairi_skips_fifth_copy() {
+        // 1111m 234p 567s 789s is not tenpai: the fifth 1m is not counted as its wait
+        let hand = [
+            4, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 1, 1, 1, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 1, 1, 2, 1, 1, //
+            0, 0, 0, 0, 0, 0, 0,
+        ];
+        let res = hairi(Vec::from(hand).as_mut()).unwrap();
+        assert_eq!(res.now, 1);
+        assert_eq!(res.wait, (1..34).collect::<Vec<i8>>());
+    }
+
     #[test]
     pub fn hairi_partial_hand() {
         let hand = [
@@ -1594,4 +1826,92 @@ mod tests {
             [(0, vec![1, 4]), (2, vec![0, 3, 22]), (3, vec![1, 4])]
         );
     }
+
+    #[test]
+    pub fn shanten_forms_works() {
+        // Six pairs and east: chiitoitsu tenpai, far from regular hand and kokushi
+        let tiles = tiles_to_34(vec![M1, M1, M9, M9, P2, P2, P8, P8, S3, S3, S7, S7, E]);
+        let forms = calc_shanten_forms(&tiles);
+        assert_eq!(forms.regular, 3);
+        assert_eq!(forms.chiitoitsu, Some(0));
+        assert_eq!(forms.kokushi, Some(9));
+        assert_eq!(forms.best, HandForm::Chiitoitsu);
+        assert_eq!(forms.shanten, calc_shanten(&tiles));
+
+        let kokushi = calc_shanten_forms(&tiles_to_34(vec![
+            M1, M9, P1, P9, S1, S9, E, S, W, N, WD, GD, RD,
+        ]));
+        assert_eq!(kokushi.best, HandForm::Kokushi);
+        assert_eq!(kokushi.shanten, 0);
+
+        // Ten tiles with a meld: only regular hand
+        let open = calc_shanten_forms(&tiles_to_34(vec![M1, M1, M9, M9, P2, P2, P8, P8, S3, S3]));
+        assert_eq!(open.chiitoitsu, None);
+        assert_eq!(open.kokushi, None);
+        assert_eq!(open.best, HandForm::Regular);
+        assert_eq!(open.shanten, 2);
+    }
+
+    #[test]
+    pub fn hairi_for_form_works() {
+        let mut tiles = tiles_to_34(vec![M1, M1, M9, M9, P2, P2, P8, P8, S3, S3, S7, S7, E]);
+        let chiitoitsu = hairi_for_form(&mut tiles, HandForm::Chiitoitsu).unwrap();
+        assert_eq!(chiitoitsu.now, 0);
+        assert_eq!(chiitoitsu.wait, [27]);
+        let regular = hairi_for_form(&mut tiles, HandForm::Regular).unwrap();
+        assert_eq!(regular.now, 3);
+        assert!(!regular.wait.contains(&27));
+
+        // Discards keeping chiitoitsu tenpai: any of the two singles
+        tiles[(N as usize) - 1] += 1;
+        let discards = hairi_for_form(&mut tiles, HandForm::Chiitoitsu).unwrap();
+        assert_eq!(discards.now, 0);
+        assert_eq!(
+            discards.waits_after_discard,
+            [(27, vec![30]), (30, vec![27])]
+        );
+        let mut short = tiles_to_34(vec![M1, M1, M1, M2]);
+        assert!(hairi_for_form(&mut short, HandForm::Kokushi).is_none());
+    }
+
+    #[test]
+    pub fn shanten_for_hand_works() {
+        // Ten terminals and honors with a pon: kokushi is not possible anymore
+        let hand = RiichiHand {
+            closed_part: [M1, M9, P1, P9, S1, S9, E, S, W, N]
+                .into_iter()
+                .map(|t| t as i8)
+                .collect(),
+            open_part: vec![(true, vec![M5 as i8, M5 as i8, M5 as i8])],
+        };
+        assert_eq!(
+            calc_shanten(&tiles_to_34(vec![M1, M9, P1, P9, S1, S9, E, S, W, N])),
+            3
+        );
+        assert_eq!(calc_shanten_for_hand(&hand), 6);
+        assert_eq!(hairi_for_hand(&hand).unwrap().now, 6);
+    }
+
+    #[test]
+    pub fn hairi_for_hand_works() {
+        // Penchan on 3m, but all of them are in own closed kan
+        let mut hand = RiichiHand {
+            closed_part: [M1, M2, P4, P5, P6, S7, S8, S9, E, E]
+                .into_iter()
+                .map(|t| t as i8)
+                .collect(),
+            open_part: vec![(false, vec![M3 as i8; 4])],
+        };
+        let closed = &mut tiles_to_34(vec![M1, M2, P4, P5, P6, S7, S8, S9, E, E]);
+        assert_eq!(hairi(closed).unwrap().wait, [2]);
+        let result = hairi_for_hand(&hand).unwrap();
+        assert_eq!(result.now, 0);
+        assert!(result.wait.is_empty());
+
+        // With 14 tiles: only west keeps tenpai, still without waits
+        hand.closed_part.push(W as i8);
+        let result = hairi_for_hand(&hand).unwrap();
+        assert_eq!(result.now, 0);
+        assert_eq!(result.waits_after_discard, [(29, vec![])]);
+    }
 }
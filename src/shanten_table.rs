@@ -0,0 +1,419 @@
+use crate::shanten::suit_leaves;
+use std::sync::OnceLock;
+
+// Lookup-table shanten for regular hands, gives the same results as the search in shanten.rs.
+//
+// The search goes through suits one by one and choices in one suit don't depend on others,
+// so every result is a combination of per-suit decompositions. With M melds, S tatsu + pairs
+// and h = 1 if there is a pair, the search gives 8 - min(2M + S, M + 4 + h), plus one shanten
+// if there's no pair and all isolated tiles are fourth copies of tiles in hand. So for every
+// suit pattern it's enough to keep 2m + s, m, pair and isolated tiles of its decompositions.
+// They are precomputed with the search itself and only non-dominated ones are kept.
+
+// Isolated tiles of a decomposition
+const NO_ISOLATED: u16 = 0;
+const ISOLATED_FOURTH: u16 = 1; // all isolated tiles are the fourth copies
+const ISOLATED: u16 = 2;
+
+// Patterns of one suit with up to 14 tiles; they are numbered densely in base 5 order
+const SUIT_PATTERNS: usize = 405350;
+
+// Non-dominated outcomes of a pattern never exceed five, so they are stored inline:
+// 10-bit outcomes with a presence bit each
+const OUTCOME_BITS: u64 = 11;
+const OUTCOME_PRESENT: u64 = 1 << 10;
+const MAX_OUTCOMES: usize = 5;
+
+struct SuitTable {
+    // Patterns numbered before the one having this count of a tile, by tile and count of higher
+    // tiles of the suit; zero after 14 tiles, such hands are rejected anyway
+    ranks: [[[u32; 5]; 33]; 9],
+    // By pattern number, outcome is melds | (2 * melds + tatsu + pairs) << 3 | has pair << 7 | isolated << 8
+    outcomes: Vec<u64>,
+}
+
+fn pack(melds: i8, value: i8, pair: bool, isolated: u16) -> u16 {
+    melds as u16 | (value as u16) << 3 | (pair as u16) << 7 | isolated << 8
+}
+
+// Larger values are better for every field; for isolated tiles only fourth copies can add a shanten
+fn dominates(a: u16, b: u16) -> bool {
+    let rank = |o: u16| [1, 0, 2][(o >> 8) as usize];
+    (a & 7) >= (b & 7)
+        && (a >> 3 & 15) >= (b >> 3 & 15)
+        && (a >> 7 & 1) >= (b >> 7 & 1)
+        && rank(a) >= rank(b)
+}
+
+fn suit_outcomes(suit: &[i8]) -> Vec<u16> {
+    let fourth: i32 = (0..9).filter(|i| suit[*i] == 4).map(|i| 1 << i).sum();
+    let mut outcomes: Vec<u16> = Vec::new();
+    for (melds, tatsu, pairs, isolated) in suit_leaves(suit) {
+        let isolated = match isolated {
+            0 => NO_ISOLATED,
+            _ if isolated & !fourth == 0 => ISOLATED_FOURTH,
+            _ => ISOLATED,
+        };
+        // Other suits give at least twice their melds, so more than melds + 5 never matters
+        let value = (2 * melds + tatsu + pairs).min(melds + 5);
+        let outcome = pack(melds, value, pairs > 0, isolated);
+        if outcomes.iter().any(|o| dominates(*o, outcome)) {
+            continue;
+        }
+        outcomes.retain(|o| !dominates(outcome, *o));
+        outcomes.push(outcome);
+    }
+    outcomes
+}
+
+fn build_table() -> SuitTable {
+    // Count of patterns of first k tiles with at most r tiles
+    let mut patterns = [[0u32; 15]; 10];
+    patterns[0] = [1; 15];
+    for k in 1..10 {
+        for r in 0..15 {
+            patterns[k][r] = (0..=r.min(4)).map(|count| patterns[k - 1][r - count]).sum();
+        }
+    }
+    let mut ranks = [[[0u32; 5]; 33]; 9];
+    for (i, by_used) in ranks.iter_mut().enumerate() {
+        for (left, by_count) in by_used.iter_mut().take(15).rev().enumerate() {
+            for count in 1..5 {
+                by_count[count] = by_count[count - 1]
+                    + if count <= left + 1 {
+                        patterns[i][left + 1 - count]
+                    } else {
+                        0
+                    };
+            }
+        }
+    }
+
+    let mut outcomes: Vec<u64> = Vec::with_capacity(SUIT_PATTERNS);
+    let mut suit = [0i8; 9];
+    for index in 0..5usize.pow(9) {
+        let mut rest = index;
+        for count in suit.iter_mut() {
+            *count = (rest % 5) as i8;
+            rest /= 5;
+        }
+        // More than 14 tiles of one suit are never looked up
+        if suit.iter().sum::<i8>() > 14 {
+            continue;
+        }
+        let front = suit_outcomes(&suit);
+        assert!(front.len() <= MAX_OUTCOMES);
+        let packed = front.iter().enumerate().fold(0, |packed, (i, outcome)| {
+            packed | (*outcome as u64 | OUTCOME_PRESENT) << (OUTCOME_BITS * i as u64)
+        });
+        outcomes.push(packed);
+    }
+    assert_eq!(outcomes.len(), SUIT_PATTERNS);
+    SuitTable { ranks, outcomes }
+}
+
+fn table() -> &'static SuitTable {
+    static TABLE: OnceLock<SuitTable> = OnceLock::new();
+    TABLE.get_or_init(build_table)
+}
+
+// Builds the table in advance; otherwise it's built on the first shanten calculation
+pub fn init_shanten_table() {
+    table();
+}
+
+// Shanten with chiitoitsu and kokushi counted in the same pass.
+// None for fifth copies, which hairi tries too: they are left to the search.
+pub(crate) fn shanten(tiles_34: &[i8], use_chiitoitsu: bool, use_kokushi: bool) -> Option<i8> {
+    Some(TableHand::new(tiles_34)?.shanten(use_chiitoitsu, use_kokushi))
+}
+
+// Tiles, kinds and pairs of one suit or of honors, for chiitoitsu and kokushi
+#[derive(Clone, Copy, Default)]
+struct Counts {
+    tiles: i8,
+    kinds: i8,
+    pairs: i8,
+    terminals: i8,
+    terminal_pairs: i8,
+}
+
+impl Counts {
+    fn add(&mut self, count: i8, terminal: bool) {
+        self.tiles += count;
+        self.kinds += (count > 0) as i8;
+        self.pairs += (count >= 2) as i8;
+        if terminal {
+            self.terminals += (count > 0) as i8;
+            self.terminal_pairs += (count >= 2) as i8;
+        }
+    }
+}
+
+// Hand looked up by suits and honors separately: after one tile changes,
+// hairi looks up only its suit again and reuses the others
+#[derive(Clone, Copy)]
+pub(crate) struct TableHand {
+    fronts: [u64; 3],
+    honors: Honors,
+    counts: [Counts; 4],
+}
+
+impl TableHand {
+    pub(crate) fn new(tiles_34: &[i8]) -> Option<Self> {
+        let tiles: &[i8; 34] = tiles_34.try_into().ok()?;
+        let table = table();
+        let mut hand = TableHand {
+            fronts: [0; 3],
+            honors: Honors::default(),
+            counts: [Counts::default(); 4],
+        };
+        for part in 0..4 {
+            hand.look_up(table, tiles, part)?;
+        }
+        Some(hand)
+    }
+
+    // Same hand after the count of tile at index changed in tiles_34
+    pub(crate) fn changed(&self, tiles_34: &[i8], index: usize) -> Option<Self> {
+        let mut hand = *self;
+        hand.look_up(table(), tiles_34.try_into().ok()?, index / 9)?;
+        Some(hand)
+    }
+
+    // Part is a suit or 3 for honors
+    fn look_up(&mut self, table: &SuitTable, tiles: &[i8; 34], part: usize) -> Option<()> {
+        let mut counts = Counts::default();
+        if part == 3 {
+            // Honors don't form sequences, same rules as in the search
+            let mut honors = Honors::default();
+            for count in &tiles[27..34] {
+                if *count as u8 > 4 {
+                    return None;
+                }
+                counts.add(*count, true);
+                match count {
+                    4 => {
+                        honors.melds += 1;
+                        honors.jidahai += 1;
+                        honors.flags |= ISOLATED_FOURTH << 1;
+                    }
+                    3 => honors.melds += 1,
+                    2 => {
+                        honors.pairs += 1;
+                        honors.flags |= 1;
+                    }
+                    1 => honors.flags |= ISOLATED << 1,
+                    _ => {}
+                }
+            }
+            self.honors = honors;
+        } else {
+            let mut rank = 0;
+            let mut used = 0;
+            for i in (0..9).rev() {
+                let count = tiles[part * 9 + i];
+                if count as u8 > 4 {
+                    return None;
+                }
+                counts.add(count, i == 0 || i == 8);
+                rank += table.ranks[i][used][count as usize] as usize;
+                used += count as usize;
+            }
+            self.fronts[part] = table.outcomes[rank];
+        }
+        self.counts[part] = counts;
+        Some(())
+    }
+
+    pub(crate) fn shanten(&self, use_chiitoitsu: bool, use_kokushi: bool) -> i8 {
+        let total = self
+            .counts
+            .iter()
+            .fold(Counts::default(), |total, counts| Counts {
+                tiles: total.tiles + counts.tiles,
+                kinds: total.kinds + counts.kinds,
+                pairs: total.pairs + counts.pairs,
+                terminals: total.terminals + counts.terminals,
+                terminal_pairs: total.terminal_pairs + counts.terminal_pairs,
+            });
+        assert!(total.tiles <= 14, "Too many tiles = {}", total.tiles);
+        let mut honors = self.honors;
+        honors.melds += (14 - total.tiles) / 3;
+        if honors.jidahai > 0 && total.tiles % 3 == 2 {
+            honors.jidahai -= 1;
+        }
+
+        let mut shanten = regular_shanten(&honors, self.fronts);
+        if use_chiitoitsu {
+            let chiitoitsu = match total.pairs {
+                7 => -1,
+                pairs => 6 - pairs + if total.kinds < 7 { 7 - total.kinds } else { 0 },
+            };
+            shanten = shanten.min(chiitoitsu);
+        }
+        if use_kokushi {
+            shanten = shanten.min(13 - total.terminals - (total.terminal_pairs > 0) as i8);
+        }
+        shanten
+    }
+}
+
+// Honor tiles and melds declared outside of closed tiles
+#[derive(Clone, Copy, Default)]
+struct Honors {
+    melds: i8,
+    pairs: i8,
+    jidahai: i8,
+    flags: u16, // has pair | isolated << 1
+}
+
+// Melds, 2 * melds + tatsu + pairs and pair with isolated tiles flags of the next outcome
+// Left out of line at opt-level "z", which makes the lookup twice slower
+#[inline(always)]
+fn next_outcome(packed: &mut u64) -> Option<(i8, i8, u16)> {
+    if *packed == 0 {
+        return None;
+    }
+    let outcome = *packed as u16;
+    *packed >>= OUTCOME_BITS;
+    Some((
+        (outcome & 7) as i8,
+        (outcome >> 3 & 15) as i8,
+        outcome >> 7 & 7,
+    ))
+}
+
+fn regular_shanten(honors: &Honors, [mut man, pin, sou]: [u64; 3]) -> i8 {
+    let mut min_shanten = 8;
+    while let Some((melds, value, flags)) = next_outcome(&mut man) {
+        let (melds, value) = (
+            honors.melds + melds,
+            2 * honors.melds + honors.pairs + value,
+        );
+        // Flags are merged by bitwise or: any pair, any isolated fourth copy, any other isolated tile
+        let flags = honors.flags | flags;
+        let mut pin = pin;
+        while let Some((pin_melds, pin_value, pin_flags)) = next_outcome(&mut pin) {
+            let (melds, value, flags) = (melds + pin_melds, value + pin_value, flags | pin_flags);
+            let mut sou = sou;
+            while let Some((sou_melds, sou_value, sou_flags)) = next_outcome(&mut sou) {
+                let flags = flags | sou_flags;
+                let has_pair = flags & 1;
+                let mut shanten =
+                    8 - (value + sou_value).min(melds + sou_melds + 4 + has_pair as i8);
+                // No pair and the only isolated tiles are fourth copies: they can't become a pair
+                if flags == ISOLATED_FOURTH << 1 {
+                    shanten += 1;
+                }
+                if shanten != -1 && shanten < honors.jidahai {
+                    shanten = honors.jidahai;
+                }
+                min_shanten = min_shanten.min(shanten);
+            }
+        }
+    }
+    min_shanten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shanten::regular_shanten_by_search;
+
+    #[test]
+    pub fn table_matches_search_for_all_suit_patterns() {
+        // Every pattern of one suit, alone and next to some honors
+        let honors = [
+            [0, 0, 0, 0, 0, 0, 0],
+            [4, 1, 0, 0, 0, 0, 0],
+            [4, 0, 0, 0, 0, 0, 0],
+        ];
+        let mut tiles = vec![0; 34];
+        for index in 0..5usize.pow(9) {
+            let mut rest = index;
+            for count in tiles[9..18].iter_mut() {
+                *count = (rest % 5) as i8;
+                rest /= 5;
+            }
+            let suit_count: i8 = tiles[9..18].iter().sum();
+            for honor in &honors {
+                if suit_count + honor.iter().sum::<i8>() > 14 {
+                    continue;
+                }
+                tiles[27..34].copy_from_slice(honor);
+                assert_eq!(
+                    shanten(&tiles, false, false),
+                    Some(regular_shanten_by_search(&tiles)),
+                    "{:?}",
+                    tiles
+                );
+            }
+            tiles[27..34].fill(0);
+        }
+    }
+
+    #[test]
+    pub fn table_matches_search_for_random_hands() {
+        let mut seed: u64 = 42;
+        let mut random = |n: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % n
+        };
+        for i in 0..200000 {
+            let mut tiles = vec![0; 34];
+            let mut count = [14, 13, 11, 8][i % 4];
+            while count > 0 {
+                // Fewer kinds to get more pairs, sets and fourth copies
+                let tile = if i % 3 == 0 {
+                    random(12) * 3 % 34
+                } else {
+                    random(34)
+                };
+                if tiles[tile] < 4 {
+                    tiles[tile] += 1;
+                    count -= 1;
+                }
+            }
+            assert_eq!(
+                shanten(&tiles, false, false),
+                Some(regular_shanten_by_search(&tiles)),
+                "{:?}",
+                tiles
+            );
+        }
+    }
+
+    #[test]
+    pub fn changed_hand_matches_full_look_up() {
+        let mut tiles = vec![
+            1, 1, 1, 0, 2, 0, 0, 0, 3, //
+            0, 4, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0, //
+            0, 0, 1, 0, 0, 0, 0,
+        ];
+        let hand = TableHand::new(&tiles).unwrap();
+        for i in 0..34 {
+            for change in [1, -1] {
+                if tiles[i] + change < 0 {
+                    continue;
+                }
+                tiles[i] += change;
+                let changed = hand.changed(&tiles, i);
+                if tiles[i] > 4 {
+                    assert!(changed.is_none());
+                } else {
+                    assert_eq!(
+                        changed.map(|hand| hand.shanten(true, true)),
+                        shanten(&tiles, true, true),
+                        "{:?}",
+                        tiles
+                    );
+                }
+                tiles[i] -= change;
+            }
+        }
+    }
+}
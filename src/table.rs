@@ -0,0 +1,164 @@
+use crate::furiten::check_furiten;
+use crate::interfaces::{FuritenOptions, KuikaeRules, RiichiHand, RiichiOptions, RoundRules};
+use crate::kuikae::check_kuikae;
+
+// Live wall size: draws including rinshan can't exceed it
+pub(crate) const LIVE_TILES: i8 = 70;
+pub(crate) const RED_FIVES: [u8; 3] = [16, 52, 88];
+
+pub(crate) fn error<T>(msg: &str) -> Result<T, String> {
+    Err(msg.parse().unwrap())
+}
+
+// Table tiles are in 136 format: tile is index / 4 + 1,
+// red fives (if allowed) are the first copies of 5m, 5p and 5s.
+pub(crate) fn tile_id(tile: u8) -> i8 {
+    (tile / 4) as i8 + 1
+}
+
+pub fn dora_from_indicator(indicator: i8) -> i8 {
+    match indicator {
+        9 | 18 | 27 => indicator - 8,
+        31 => 28,
+        34 => 32,
+        _ => indicator + 1,
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Meld {
+    pub(crate) open: bool,
+    pub(crate) tiles: Vec<u8>,
+}
+
+// Hand before chi or pon, to check kuikae on the next discard
+pub(crate) struct CallInfo {
+    pub(crate) hand: RiichiHand,
+    pub(crate) meld: Vec<i8>,
+    pub(crate) called: i8,
+}
+
+// Round state shared by all players, to score their hands
+pub(crate) struct TableInfo<'a> {
+    pub(crate) rules: &'a RoundRules,
+    pub(crate) bakaze: i8,
+    pub(crate) dealer: usize,
+    pub(crate) dora_indicators: &'a [u8],
+    pub(crate) no_calls: bool, // first go-around is not interrupted
+    pub(crate) tiles_left: i8,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Player {
+    pub(crate) hand: Vec<u8>, // drawn tile is the last one
+    pub(crate) melds: Vec<Meld>,
+    pub(crate) discards: Vec<i8>, // including tiles called by others
+    pub(crate) riichi: bool,
+    pub(crate) double_riichi: bool,
+    pub(crate) ippatsu: bool,
+    pub(crate) passed_tiles: Vec<i8>,
+    pub(crate) passed_since_riichi: Vec<i8>,
+}
+
+impl Player {
+    pub(crate) fn hand(&self) -> RiichiHand {
+        RiichiHand {
+            closed_part: self.hand.iter().map(|t| tile_id(*t)).collect(),
+            open_part: self
+                .melds
+                .iter()
+                .map(|m| (m.open, m.tiles.iter().map(|t| tile_id(*t)).collect()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn kans(&self) -> i8 {
+        self.melds.iter().filter(|m| m.tiles.len() == 4).count() as i8
+    }
+
+    pub(crate) fn is_furiten(&self) -> bool {
+        check_furiten(
+            &self.hand(),
+            &FuritenOptions {
+                discards: self.discards.clone(),
+                passed_tiles: self.passed_tiles.clone(),
+                riichi: self.riichi,
+                passed_since_riichi: self.passed_since_riichi.clone(),
+            },
+        )
+        .map(|r| r.is_furiten)
+        .unwrap_or(false)
+    }
+
+    // Chi or pon is not allowed if kuikae forbids every discard after it
+    pub(crate) fn can_discard_after_call(
+        &self,
+        meld: &[i8],
+        called: i8,
+        kuikae: &KuikaeRules,
+    ) -> bool {
+        let hand = self.hand();
+        let mut rest = hand.closed_part.clone();
+        let mut taken = false;
+        for tile in meld {
+            if *tile == called && !taken {
+                taken = true;
+            } else if let Some(pos) = rest.iter().position(|t| t == tile) {
+                rest.remove(pos);
+            }
+        }
+        rest.iter()
+            .any(|t| check_kuikae(&hand, meld, called, *t, kuikae).unwrap_or(false))
+    }
+
+    // Options to score the hand of the player at the seat, with the tile taken for ron if any.
+    // Uradora are counted as dora if the player is in riichi.
+    pub(crate) fn options(
+        &self,
+        seat: usize,
+        table: &TableInfo,
+        ron_tile: Option<u8>,
+        after_kan: bool,
+        uradora: &[u8],
+    ) -> RiichiOptions {
+        let rules = table.rules;
+        let mut dora: Vec<i8> = table
+            .dora_indicators
+            .iter()
+            .map(|t| dora_from_indicator(tile_id(*t)))
+            .collect();
+        if self.riichi {
+            dora.extend(uradora.iter().map(|t| dora_from_indicator(tile_id(*t))));
+        }
+        let aka_count = self
+            .hand
+            .iter()
+            .chain(self.melds.iter().flat_map(|m| m.tiles.iter()))
+            .chain(ron_tile.iter())
+            .filter(|t| rules.allow_aka && RED_FIVES.contains(t))
+            .count() as i8;
+
+        RiichiOptions {
+            dora,
+            aka_count,
+            first_take: table.no_calls && self.discards.is_empty(),
+            riichi: self.riichi,
+            ippatsu: self.ippatsu,
+            double_riichi: self.double_riichi,
+            open_riichi: false,
+            open_riichi_deal_in: false,
+            last_tile: table.tiles_left == 0 && !after_kan,
+            after_kan,
+            tile_discarded_by_someone: ron_tile.map_or(-1, tile_id),
+            bakaze: table.bakaze,
+            jikaze: 28 + ((seat + 4 - table.dealer) % 4) as i8,
+            allow_aka: rules.allow_aka,
+            allow_kuitan: rules.allow_kuitan,
+            with_kiriage: rules.with_kiriage,
+            disabled_yaku: rules.disabled_yaku.clone(),
+            local_yaku_enabled: rules.local_yaku_enabled.clone(),
+            all_local_yaku_enabled: rules.all_local_yaku_enabled,
+            allow_double_yakuman: rules.allow_double_yakuman,
+        }
+    }
+}
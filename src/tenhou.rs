@@ -0,0 +1,1144 @@
+use crate::constants::Yaku;
+use crate::interfaces::{
+    LogAgari, LogEvent, LogGame, LogRound, LogRyuukyoku, RiichiHand, RiichiOptions, RoundEnd,
+    WinKind,
+};
+use crate::table::{LIVE_TILES, RED_FIVES, dora_from_indicator, error, tile_id};
+use serde_json::Value;
+
+// Yaku by tenhou id with names used in JSON logs
+static TENHOU_YAKU: [(Yaku, &str); 55] = [
+    (Yaku::Menzentsumo, "門前清自摸和"),
+    (Yaku::Riichi, "立直"),
+    (Yaku::Ippatsu, "一発"),
+    (Yaku::Chankan, "槍槓"),
+    (Yaku::Rinshan, "嶺上開花"),
+    (Yaku::Haitei, "海底摸月"),
+    (Yaku::Houtei, "河底撈魚"),
+    (Yaku::Pinfu, "平和"),
+    (Yaku::Tanyao, "断幺九"),
+    (Yaku::Iipeikou, "一盃口"),
+    (Yaku::OwnWindEast, "自風 東"),
+    (Yaku::OwnWindSouth, "自風 南"),
+    (Yaku::OwnWindWest, "自風 西"),
+    (Yaku::OwnWindNorth, "自風 北"),
+    (Yaku::RoundWindEast, "場風 東"),
+    (Yaku::RoundWindSouth, "場風 南"),
+    (Yaku::RoundWindWest, "場風 西"),
+    (Yaku::RoundWindNorth, "場風 北"),
+    (Yaku::Haku, "役牌 白"),
+    (Yaku::Hatsu, "役牌 發"),
+    (Yaku::Chun, "役牌 中"),
+    (Yaku::DaburuRiichi, "両立直"),
+    (Yaku::Chiitoitsu, "七対子"),
+    (Yaku::Chanta, "混全帯幺九"),
+    (Yaku::Ittsu, "一気通貫"),
+    (Yaku::Sanshoku, "三色同順"),
+    (Yaku::SanshokuDoukou, "三色同刻"),
+    (Yaku::Sankantsu, "三槓子"),
+    (Yaku::Toitoi, "対々和"),
+    (Yaku::Sanankou, "三暗刻"),
+    (Yaku::Shosangen, "小三元"),
+    (Yaku::Honroutou, "混老頭"),
+    (Yaku::Ryanpeikou, "二盃口"),
+    (Yaku::Junchan, "純全帯幺九"),
+    (Yaku::Honitsu, "混一色"),
+    (Yaku::Chinitsu, "清一色"),
+    (Yaku::Renhou, "人和"),
+    (Yaku::Tenhou, "天和"),
+    (Yaku::Chihou, "地和"),
+    (Yaku::Daisangen, "大三元"),
+    (Yaku::Suuankou, "四暗刻"),
+    (Yaku::SuuankouTanki, "四暗刻単騎"),
+    (Yaku::Tsuuiisou, "字一色"),
+    (Yaku::Ryuuiisou, "緑一色"),
+    (Yaku::Chinroutou, "清老頭"),
+    (Yaku::Chuurenpoto, "九蓮宝燈"),
+    (Yaku::Chuurenpoto9Sides, "純正九蓮宝燈"),
+    (Yaku::Kokushimusou, "国士無双"),
+    (Yaku::Kokushimusou13Sides, "国士無双１３面"),
+    (Yaku::Daisuushi, "大四喜"),
+    (Yaku::Shosuushi, "小四喜"),
+    (Yaku::Suukantsu, "四槓子"),
+    (Yaku::Dora, "ドラ"),
+    (Yaku::Uradora, "裏ドラ"),
+    (Yaku::Akadora, "赤ドラ"),
+];
+
+// Tile from JSON log code: 11-19 man, 21-29 pin, 31-39 sou, 41-47 honors, 51-53 red fives
+fn json_tile(code: i64) -> Result<u8, String> {
+    let (suit, value) = (code / 10, code % 10);
+    let (id, copy) = match (suit, value) {
+        (1..=3, 1..=9) => ((suit - 1) * 9 + value, if value == 5 { 1 } else { 0 }),
+        (4, 1..=7) => (27 + value, 0),
+        (5, 1..=3) => ((value - 1) * 9 + 5, 0),
+        _ => return error("Incorrect tile"),
+    };
+    Ok(((id - 1) * 4 + copy) as u8)
+}
+
+fn json_int(value: &Value) -> Result<i64, String> {
+    value
+        .as_i64()
+        .map_or_else(|| error("Incorrect log format"), Ok)
+}
+
+fn json_array(value: &Value) -> Result<&Vec<Value>, String> {
+    value
+        .as_array()
+        .map_or_else(|| error("Incorrect log format"), Ok)
+}
+
+fn json_tiles(value: &Value) -> Result<Vec<u8>, String> {
+    json_array(value)?
+        .iter()
+        .map(|v| json_tile(json_int(v)?))
+        .collect()
+}
+
+// Call string from JSON log, like "c275226" or "25p2525": marker, its position and tile codes
+fn json_call(call: &str) -> Result<(char, usize, Vec<u8>, u8), String> {
+    let pos = match call.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(pos) => pos,
+        None => return error("Incorrect call"),
+    };
+    let marker = call[pos..].chars().next().unwrap();
+    let digits: String = call.chars().filter(|c| c.is_ascii_digit()).collect();
+    if !digits.len().is_multiple_of(2) || !pos.is_multiple_of(2) || pos + 3 > call.len() {
+        return error("Incorrect call");
+    }
+    let tiles = (0..digits.len() / 2)
+        .map(|i| json_tile(digits[i * 2..i * 2 + 2].parse::<i64>().unwrap()))
+        .collect::<Result<Vec<u8>, String>>()?;
+    let called = json_tile(call[pos + 1..pos + 3].parse::<i64>().unwrap_or(0))?;
+    Ok((marker, pos, tiles, called))
+}
+
+// Seat the call is made from, by marker position: kamicha is the leftmost one
+fn json_call_from(seat: usize, marker: char, pos: usize) -> i8 {
+    let relative = match (marker, pos) {
+        ('c', _) | (_, 0) => 3,
+        (_, 2) => 2,
+        _ => 1,
+    };
+    ((seat + relative) % 4) as i8
+}
+
+fn json_call_event(seat: usize, call: &str) -> Result<LogEvent, String> {
+    let (marker, pos, tiles, called) = json_call(call)?;
+    let from = json_call_from(seat, marker, pos);
+    let seat = seat as i8;
+    match marker {
+        'c' if tiles.len() == 3 => Ok(LogEvent::Chi {
+            seat,
+            from,
+            tiles,
+            called,
+        }),
+        'p' if tiles.len() == 3 => Ok(LogEvent::Pon {
+            seat,
+            from,
+            tiles,
+            called,
+        }),
+        'm' if tiles.len() == 4 => Ok(LogEvent::Daiminkan {
+            seat,
+            from,
+            tiles,
+            called,
+        }),
+        'a' if tiles.len() == 4 => Ok(LogEvent::Ankan { seat, tiles }),
+        // Added tile is not known exactly, it is resolved against the pon while replaying
+        'k' if tiles.len() == 4 => Ok(LogEvent::Shouminkan { seat, tile: called }),
+        _ => error("Incorrect call"),
+    }
+}
+
+// Merge draw and discard columns of all players into single timeline
+fn json_events(
+    draws: &[&Vec<Value>],
+    discards: &[&Vec<Value>],
+    dealer: usize,
+) -> Result<Vec<LogEvent>, String> {
+    let mut events: Vec<LogEvent> = Vec::new();
+    let mut next_draw = [0; 4];
+    let mut next_discard = [0; 4];
+    let mut last_draw = [0u8; 4];
+    let mut seat = dealer;
+
+    'turns: while next_draw[seat] < draws[seat].len() {
+        let entry = &draws[seat][next_draw[seat]];
+        next_draw[seat] += 1;
+        if let Some(call) = entry.as_str() {
+            let event = json_call_event(seat, call)?;
+            let kan = matches!(event, LogEvent::Daiminkan { .. });
+            events.push(event);
+            if kan {
+                // Daiminkan takes a slot in discards column
+                next_discard[seat] += 1;
+                continue;
+            }
+        } else {
+            let tile = json_tile(json_int(entry)?)?;
+            last_draw[seat] = tile;
+            events.push(LogEvent::Draw {
+                seat: seat as i8,
+                tile,
+            });
+        }
+
+        // No discard: tsumo or abortive draw
+        if next_discard[seat] >= discards[seat].len() {
+            break;
+        }
+        let entry = &discards[seat][next_discard[seat]];
+        next_discard[seat] += 1;
+        let (code, riichi) = match entry.as_str() {
+            Some(call) if call.starts_with('r') => match call[1..].parse::<i64>() {
+                Ok(code) => (code, true),
+                Err(_) => return error("Incorrect discard"),
+            },
+            Some(call) => {
+                // Closed or added kan, then rinshan draw
+                events.push(json_call_event(seat, call)?);
+                continue 'turns;
+            }
+            None => (json_int(entry)?, false),
+        };
+        let tsumogiri = code == 60;
+        let tile = if tsumogiri {
+            last_draw[seat]
+        } else {
+            json_tile(code)?
+        };
+        events.push(LogEvent::Discard {
+            seat: seat as i8,
+            tile,
+            tsumogiri,
+            riichi,
+        });
+
+        // Next turn goes to the caller (pon and kan come before chi) or to the next player
+        let mut caller: Option<usize> = None;
+        for marker in ['p', 'm', 'c'] {
+            for other in (1..4).map(|i| (seat + i) % 4) {
+                let call = draws[other].get(next_draw[other]).and_then(|v| v.as_str());
+                if let Some(Ok((m, pos, _, called))) = call.map(json_call) {
+                    if m == marker
+                        && json_call_from(other, m, pos) == seat as i8
+                        && tile_id(called) == tile_id(tile)
+                        && caller.is_none()
+                    {
+                        caller = Some(other);
+                    }
+                }
+            }
+        }
+        seat = caller.unwrap_or((seat + 1) % 4);
+    }
+
+    Ok(events)
+}
+
+// Points string like "30符1飜1000点", "満貫2000-4000点" or "40符2飜1300点∀"
+fn json_points(points: &str, seat: usize, dealer: usize, tsumo: bool) -> (i32, i32) {
+    let fu = points
+        .find('符')
+        .and_then(|pos| points[..pos].parse::<i32>().ok())
+        .unwrap_or(0);
+    let value = points.split('点').next().unwrap_or("");
+    let digits: String = value
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit() || *c == '-')
+        .collect::<Vec<char>>()
+        .into_iter()
+        .rev()
+        .collect();
+    let parts: Vec<i32> = digits.split('-').filter_map(|v| v.parse().ok()).collect();
+    let ten = match parts.as_slice() {
+        [ko, oya] => ko * 2 + oya,
+        [all] if tsumo && seat == dealer => all * 3,
+        [ron] => *ron,
+        _ => 0,
+    };
+    (fu, ten)
+}
+
+fn json_agari(
+    deltas: &Value,
+    details: &Value,
+    dealer: usize,
+    dora_indicators: &[u8],
+    uradora_indicators: &[u8],
+) -> Result<LogAgari, String> {
+    let details = json_array(details)?;
+    if details.len() < 4 {
+        return error("Incorrect agari");
+    }
+    let seat = json_int(&details[0])? as i8;
+    let from = json_int(&details[1])? as i8;
+    let pao = json_int(&details[2])? as i8;
+    let (fu, ten) = json_points(
+        details[3].as_str().unwrap_or(""),
+        seat as usize,
+        dealer,
+        seat == from,
+    );
+
+    let mut han = 0;
+    let mut yakuman = 0;
+    let mut yaku: Vec<(i8, i8)> = Vec::new();
+    for entry in &details[4..] {
+        let entry = entry.as_str().unwrap_or("");
+        let (name, count) = entry.split_once('(').unwrap_or((entry, ""));
+        let id = match TENHOU_YAKU.iter().find(|(_, n)| *n == name) {
+            Some((y, _)) => y.clone() as i8,
+            None => return error("Unknown yaku"),
+        };
+        if count.starts_with("役満") {
+            yakuman += 1;
+            yaku.push((id, 13));
+        } else {
+            let count: i8 = count.trim_end_matches("飜)").parse().unwrap_or(0);
+            if count > 0 {
+                han += count as i32;
+                yaku.push((id, count));
+            }
+        }
+    }
+
+    Ok(LogAgari {
+        seat,
+        from,
+        pao,
+        han: if yakuman > 0 { 0 } else { han },
+        fu,
+        ten,
+        yakuman,
+        yaku,
+        dora_indicators: dora_indicators.to_vec(),
+        uradora_indicators: uradora_indicators.to_vec(),
+        deltas: json_array(deltas)?
+            .iter()
+            .map(|v| json_int(v).map(|d| d as i32))
+            .collect::<Result<Vec<i32>, String>>()?,
+    })
+}
+
+fn json_round(log: &Value) -> Result<LogRound, String> {
+    let log = json_array(log)?;
+    if log.len() < 17 {
+        return error("Incorrect log format");
+    }
+    let info = json_array(&log[0])?;
+    if info.len() < 3 {
+        return error("Incorrect log format");
+    }
+    let kyoku = json_int(&info[0])?;
+    let dealer = (kyoku % 4) as usize;
+    let dora_indicators = json_tiles(&log[2])?;
+    let uradora_indicators = json_tiles(&log[3])?;
+
+    let mut haipai = Vec::new();
+    let mut draws = Vec::new();
+    let mut discards = Vec::new();
+    for seat in 0..4 {
+        haipai.push(json_tiles(&log[4 + seat * 3])?);
+        draws.push(json_array(&log[5 + seat * 3])?);
+        discards.push(json_array(&log[6 + seat * 3])?);
+    }
+
+    let mut round = LogRound {
+        bakaze: 28 + (kyoku / 4) as i8,
+        dealer: dealer as i8,
+        honba: json_int(&info[1])? as i8,
+        riichi_sticks: json_int(&info[2])? as i8,
+        scores: json_array(&log[1])?
+            .iter()
+            .map(|v| json_int(v).map(|s| s as i32))
+            .collect::<Result<Vec<i32>, String>>()?,
+        haipai,
+        dora_indicators: dora_indicators.clone(),
+        events: json_events(&draws, &discards, dealer)?,
+        agari: Vec::new(),
+        ryuukyoku: None,
+    };
+
+    let result = json_array(&log[16])?;
+    let name = result.first().and_then(|v| v.as_str()).unwrap_or("");
+    if name == "和了" {
+        let mut i = 1;
+        while i + 1 < result.len() {
+            round.agari.push(json_agari(
+                &result[i],
+                &result[i + 1],
+                dealer,
+                &dora_indicators,
+                &uradora_indicators,
+            )?);
+            i += 2;
+        }
+    } else {
+        round.ryuukyoku = Some(LogRyuukyoku {
+            end: match name {
+                "九種九牌" => RoundEnd::KyuushuKyuuhai,
+                "四風連打" => RoundEnd::SuufonRenda,
+                "四家立直" => RoundEnd::SuuchaRiichi,
+                "四槓散了" => RoundEnd::Suukaikan,
+                "三家和了" => RoundEnd::Sanchahou,
+                _ => RoundEnd::Ryuukyoku,
+            },
+            deltas: match result.get(1) {
+                Some(deltas) => json_array(deltas)?
+                    .iter()
+                    .map(|v| json_int(v).map(|d| d as i32))
+                    .collect::<Result<Vec<i32>, String>>()?,
+                None => vec![0; 4],
+            },
+        });
+    }
+    Ok(round)
+}
+
+// Parse game log in tenhou.net/6 JSON format
+pub fn parse_tenhou_json(json: &str) -> Result<LogGame, String> {
+    let root: Value = match serde_json::from_str(json) {
+        Ok(root) => root,
+        Err(e) => return Err(e.to_string()),
+    };
+    let players = match root.get("name").and_then(|v| v.as_array()) {
+        Some(names) => names
+            .iter()
+            .map(|n| n.as_str().unwrap_or("").to_string())
+            .collect(),
+        None => Vec::new(),
+    };
+    // Red fives are enabled unless the rule says otherwise
+    let aka = match root.get("rule") {
+        Some(rule) => ["aka", "aka51", "aka52", "aka53"]
+            .iter()
+            .any(|key| rule.get(key).and_then(|v| v.as_i64()).unwrap_or(0) > 0),
+        None => true,
+    };
+    let rounds = match root.get("log").and_then(|v| v.as_array()) {
+        Some(logs) => logs
+            .iter()
+            .map(json_round)
+            .collect::<Result<Vec<LogRound>, String>>()?,
+        None => return error("Incorrect log format"),
+    };
+
+    Ok(LogGame {
+        players,
+        aka,
+        rounds,
+    })
+}
+
+// Tag of mjlog XML: name and attributes
+type XmlTag<'a> = (&'a str, Vec<(&'a str, &'a str)>);
+
+fn xml_tags(xml: &str) -> Result<Vec<XmlTag<'_>>, String> {
+    let mut tags = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => return error("Incorrect XML"),
+        };
+        let tag = rest[..end].trim_end_matches('/').trim();
+        rest = &rest[end + 1..];
+        if tag.starts_with('/') || tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let mut attributes = Vec::new();
+        let mut attrs = tag[name_end..].trim_start();
+        while let Some(eq) = attrs.find("=\"") {
+            let key = attrs[..eq].trim();
+            let value_end = match attrs[eq + 2..].find('"') {
+                Some(pos) => eq + 2 + pos,
+                None => return error("Incorrect XML"),
+            };
+            attributes.push((key, &attrs[eq + 2..value_end]));
+            attrs = attrs[value_end + 1..].trim_start();
+        }
+        tags.push((&tag[..name_end], attributes));
+    }
+    Ok(tags)
+}
+
+fn xml_attr<'a>(attributes: &[(&str, &'a str)], key: &str) -> Option<&'a str> {
+    attributes.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn xml_numbers(value: Option<&str>) -> Result<Vec<i32>, String> {
+    match value {
+        Some(value) if !value.is_empty() => value
+            .split(',')
+            .map(|v| v.trim().parse::<i32>())
+            .collect::<Result<Vec<i32>, _>>()
+            .or_else(|_| error("Incorrect XML")),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn xml_tiles(value: Option<&str>) -> Result<Vec<u8>, String> {
+    let tiles = xml_numbers(value)?;
+    if tiles.iter().any(|t| !(0..136).contains(t)) {
+        return error("Incorrect tile");
+    }
+    Ok(tiles.into_iter().map(|t| t as u8).collect())
+}
+
+// Scores and deltas in hundreds: "250,-10,250,10,..."
+fn xml_deltas(value: Option<&str>) -> Result<Vec<i32>, String> {
+    Ok(xml_numbers(value)?
+        .chunks(2)
+        .take(4)
+        .map(|pair| pair.get(1).copied().unwrap_or(0) * 100)
+        .collect())
+}
+
+// Player names are URL-encoded
+fn xml_name(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&value[i + 1..i + 3], 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// Decode meld from mjlog "m" attribute
+pub(crate) fn decode_meld(seat: i8, m: i32) -> LogEvent {
+    let from = (seat + (m & 3) as i8) % 4;
+    if m & 0x4 != 0 {
+        // chi
+        let mut t = m >> 10;
+        let called = (t % 3) as usize;
+        t /= 3;
+        let base = (t / 7) * 9 + t % 7;
+        let tiles: Vec<u8> = (0..3)
+            .map(|i| ((base + i) * 4 + ((m >> (3 + 2 * i)) & 3)) as u8)
+            .collect();
+        LogEvent::Chi {
+            seat,
+            from,
+            called: tiles[called],
+            tiles,
+        }
+    } else if m & 0x18 != 0 {
+        // pon or added kan
+        let unused = (m >> 5) & 3;
+        let mut t = m >> 9;
+        let called = (t % 3) as usize;
+        t /= 3;
+        if m & 0x10 != 0 {
+            return LogEvent::Shouminkan {
+                seat,
+                tile: (t * 4 + unused) as u8,
+            };
+        }
+        let tiles: Vec<u8> = (0..4)
+            .filter(|i| *i != unused)
+            .map(|i| (t * 4 + i) as u8)
+            .collect();
+        LogEvent::Pon {
+            seat,
+            from,
+            called: tiles[called],
+            tiles,
+        }
+    } else {
+        let called = (m >> 8) as u8;
+        let tiles: Vec<u8> = (0..4).map(|i| called / 4 * 4 + i).collect();
+        if m & 3 == 0 {
+            LogEvent::Ankan { seat, tiles }
+        } else {
+            LogEvent::Daiminkan {
+                seat,
+                from,
+                tiles,
+                called,
+            }
+        }
+    }
+}
+
+fn xml_agari(attributes: &[(&str, &str)]) -> Result<LogAgari, String> {
+    let ten = xml_numbers(xml_attr(attributes, "ten"))?;
+    let seat = xml_numbers(xml_attr(attributes, "who"))?;
+    let from = xml_numbers(xml_attr(attributes, "fromWho"))?;
+    if ten.len() < 2 || seat.is_empty() || from.is_empty() {
+        return error("Incorrect agari");
+    }
+
+    let mut han = 0;
+    let mut yaku: Vec<(i8, i8)> = Vec::new();
+    for pair in xml_numbers(xml_attr(attributes, "yaku"))?.chunks(2) {
+        if pair.len() < 2 || pair[1] == 0 {
+            continue;
+        }
+        match TENHOU_YAKU.get(pair[0] as usize) {
+            Some((y, _)) => yaku.push((y.clone() as i8, pair[1] as i8)),
+            None => return error("Unknown yaku"),
+        }
+        han += pair[1];
+    }
+    let yakuman_list = xml_numbers(xml_attr(attributes, "yakuman"))?;
+    for id in &yakuman_list {
+        match TENHOU_YAKU.get(*id as usize) {
+            Some((y, _)) => yaku.push((y.clone() as i8, 13)),
+            None => return error("Unknown yaku"),
+        }
+    }
+
+    Ok(LogAgari {
+        seat: seat[0] as i8,
+        from: from[0] as i8,
+        pao: xml_numbers(xml_attr(attributes, "paoWho"))?
+            .first()
+            .map_or(seat[0] as i8, |p| *p as i8),
+        han: if yakuman_list.is_empty() { han } else { 0 },
+        fu: ten[0],
+        ten: ten[1],
+        yakuman: yakuman_list.len() as i8,
+        yaku,
+        dora_indicators: xml_tiles(xml_attr(attributes, "doraHai"))?,
+        uradora_indicators: xml_tiles(xml_attr(attributes, "doraHaiUra"))?,
+        deltas: xml_deltas(xml_attr(attributes, "sc"))?,
+    })
+}
+
+// Parse game log in tenhou mjlog XML format
+pub fn parse_mjlog(xml: &str) -> Result<LogGame, String> {
+    let mut game = LogGame {
+        players: Vec::new(),
+        aka: true,
+        rounds: Vec::new(),
+    };
+    let mut round: Option<LogRound> = None;
+    let mut last_draw = [0u8; 4];
+    let mut riichi = [false; 4];
+
+    for (name, attributes) in xml_tags(xml)? {
+        let first = name.chars().next().unwrap_or(' ');
+        let number = name[first.len_utf8()..].parse::<u8>();
+        if let (Some(current), Ok(tile)) = (round.as_mut(), number) {
+            if let Some(seat) = "TUVW".find(first) {
+                last_draw[seat] = tile;
+                current.events.push(LogEvent::Draw {
+                    seat: seat as i8,
+                    tile,
+                });
+                continue;
+            }
+            if let Some(seat) = "DEFG".find(first) {
+                current.events.push(LogEvent::Discard {
+                    seat: seat as i8,
+                    tile,
+                    tsumogiri: tile == last_draw[seat],
+                    riichi: riichi[seat],
+                });
+                riichi[seat] = false;
+                continue;
+            }
+        }
+
+        match name {
+            "GO" => {
+                let kind = xml_numbers(xml_attr(&attributes, "type"))?;
+                game.aka = kind.first().is_none_or(|k| k & 0x02 == 0);
+            }
+            "UN" => {
+                if game.players.is_empty() {
+                    game.players = (0..4)
+                        .map(|i| {
+                            xml_attr(&attributes, &format!("n{}", i))
+                                .map_or(String::new(), xml_name)
+                        })
+                        .collect();
+                }
+            }
+            "INIT" => {
+                if let Some(finished) = round.take() {
+                    game.rounds.push(finished);
+                }
+                let seed = xml_numbers(xml_attr(&attributes, "seed"))?;
+                let dealer = xml_numbers(xml_attr(&attributes, "oya"))?;
+                if seed.len() < 6 || dealer.is_empty() {
+                    return error("Incorrect XML");
+                }
+                let mut haipai = Vec::new();
+                for seat in 0..4 {
+                    haipai.push(xml_tiles(xml_attr(&attributes, &format!("hai{}", seat)))?);
+                }
+                riichi = [false; 4];
+                round = Some(LogRound {
+                    bakaze: 28 + (seed[0] / 4) as i8,
+                    dealer: dealer[0] as i8,
+                    honba: seed[1] as i8,
+                    riichi_sticks: seed[2] as i8,
+                    scores: xml_numbers(xml_attr(&attributes, "ten"))?
+                        .iter()
+                        .map(|s| s * 100)
+                        .collect(),
+                    haipai,
+                    dora_indicators: vec![seed[5] as u8],
+                    events: Vec::new(),
+                    agari: Vec::new(),
+                    ryuukyoku: None,
+                });
+            }
+            _ => {
+                let current = match round.as_mut() {
+                    Some(current) => current,
+                    None => continue,
+                };
+                let seat = xml_numbers(xml_attr(&attributes, "who"))?
+                    .first()
+                    .map_or(0, |s| *s as i8);
+                match name {
+                    "N" => {
+                        let m = xml_numbers(xml_attr(&attributes, "m"))?;
+                        current
+                            .events
+                            .push(decode_meld(seat, m.first().copied().unwrap_or(0)));
+                    }
+                    "REACH" if xml_attr(&attributes, "step") == Some("1") => {
+                        riichi[seat as usize] = true;
+                    }
+                    "DORA" => {
+                        let tile = xml_tiles(xml_attr(&attributes, "hai"))?;
+                        if let Some(indicator) = tile.first() {
+                            current.dora_indicators.push(*indicator);
+                            current.events.push(LogEvent::Dora {
+                                indicator: *indicator,
+                            });
+                        }
+                    }
+                    "AGARI" => current.agari.push(xml_agari(&attributes)?),
+                    "RYUUKYOKU" => {
+                        current.ryuukyoku = Some(LogRyuukyoku {
+                            end: match xml_attr(&attributes, "type") {
+                                Some("yao9") => RoundEnd::KyuushuKyuuhai,
+                                Some("kaze4") => RoundEnd::SuufonRenda,
+                                Some("reach4") => RoundEnd::SuuchaRiichi,
+                                Some("kan4") => RoundEnd::Suukaikan,
+                                Some("ron3") => RoundEnd::Sanchahou,
+                                _ => RoundEnd::Ryuukyoku,
+                            },
+                            deltas: xml_deltas(xml_attr(&attributes, "sc"))?,
+                        })
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if let Some(finished) = round.take() {
+        game.rounds.push(finished);
+    }
+    Ok(game)
+}
+
+#[derive(Clone, Default)]
+struct LogPlayer {
+    hand: Vec<u8>, // drawn tile is the last one
+    melds: Vec<(bool, Vec<u8>)>,
+    discards: usize,
+    riichi: bool,
+    double_riichi: bool,
+    ippatsu: bool,
+    rinshan: bool, // last draw was after kan
+    kan: bool,     // next draw is rinshan
+}
+
+impl LogPlayer {
+    // Remove exact tile, or other copy of it
+    fn take(&mut self, tile: u8) -> Result<u8, String> {
+        let pos = self
+            .hand
+            .iter()
+            .position(|t| *t == tile)
+            .or_else(|| self.hand.iter().position(|t| tile_id(*t) == tile_id(tile)));
+        match pos {
+            Some(pos) => Ok(self.hand.remove(pos)),
+            None => error("Tile is not in hand"),
+        }
+    }
+}
+
+// Replay the round and build hand, options and winning tile for every agari, in the same order as in the round.
+// Rules are the ones used on tenhou: open tanyao, no kiriage mangan and double yakuman, uradora are counted as dora.
+pub fn agari_hands(
+    round: &LogRound,
+    allow_aka: bool,
+) -> Result<Vec<(RiichiHand, RiichiOptions, WinKind)>, String> {
+    if round.haipai.len() != 4 {
+        return error("Incorrect number of players");
+    }
+    let mut players: Vec<LogPlayer> = round
+        .haipai
+        .iter()
+        .map(|hand| LogPlayer {
+            hand: hand.clone(),
+            ..LogPlayer::default()
+        })
+        .collect();
+    let mut no_calls = true;
+    let mut draws = 0;
+    // Last tile which may be called for ron, and if it was added to kan
+    let mut last_tile: Option<(u8, bool)> = None;
+
+    for event in &round.events {
+        match event {
+            LogEvent::Draw { seat, tile } => {
+                let player = &mut players[*seat as usize];
+                player.hand.push(*tile);
+                player.rinshan = player.kan;
+                player.kan = false;
+                draws += 1;
+            }
+            LogEvent::Discard {
+                seat, tile, riichi, ..
+            } => {
+                let player = &mut players[*seat as usize];
+                let taken = player.take(*tile)?;
+                player.ippatsu = false;
+                if *riichi {
+                    player.riichi = true;
+                    player.double_riichi = no_calls && player.discards == 0;
+                    player.ippatsu = true;
+                }
+                player.discards += 1;
+                last_tile = Some((taken, false));
+            }
+            LogEvent::Chi {
+                seat,
+                tiles,
+                called,
+                ..
+            }
+            | LogEvent::Pon {
+                seat,
+                tiles,
+                called,
+                ..
+            }
+            | LogEvent::Daiminkan {
+                seat,
+                tiles,
+                called,
+                ..
+            } => {
+                let player = &mut players[*seat as usize];
+                let mut own = tiles.clone();
+                if let Some(pos) = own.iter().position(|t| tile_id(*t) == tile_id(*called)) {
+                    own.remove(pos);
+                }
+                let mut meld = vec![*called];
+                for tile in own {
+                    meld.push(player.take(tile)?);
+                }
+                meld.sort();
+                player.kan = meld.len() == 4;
+                player.melds.push((true, meld));
+                no_calls = false;
+            }
+            LogEvent::Ankan { seat, tiles } => {
+                let player = &mut players[*seat as usize];
+                let meld = tiles
+                    .iter()
+                    .map(|t| player.take(*t))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                player.melds.push((false, meld));
+                player.kan = true;
+                no_calls = false;
+            }
+            LogEvent::Shouminkan { seat, tile } => {
+                let player = &mut players[*seat as usize];
+                let taken = player.take(*tile)?;
+                match player
+                    .melds
+                    .iter_mut()
+                    .find(|(open, m)| *open && m.len() == 3 && tile_id(m[0]) == tile_id(taken))
+                {
+                    Some((_, meld)) => meld.push(taken),
+                    None => return error("Pon is not found"),
+                }
+                player.kan = true;
+                no_calls = false;
+                last_tile = Some((taken, true));
+            }
+            LogEvent::Dora { .. } => {}
+        }
+        if !matches!(
+            event,
+            LogEvent::Draw { .. } | LogEvent::Discard { .. } | LogEvent::Dora { .. }
+        ) {
+            for player in players.iter_mut() {
+                player.ippatsu = false;
+            }
+        }
+    }
+
+    let mut hands = Vec::new();
+    for agari in &round.agari {
+        if !(0..4).contains(&agari.seat) {
+            return error("Incorrect agari");
+        }
+        let player = &players[agari.seat as usize];
+        let is_tsumo = agari.seat == agari.from;
+        let (ron_tile, after_kan) = if is_tsumo {
+            (None, player.rinshan)
+        } else {
+            match last_tile {
+                Some((tile, chankan)) => (Some(tile), chankan),
+                None => return error("Winning tile is not found"),
+            }
+        };
+        let win = match ron_tile {
+            Some(tile) => WinKind::Ron {
+                tile: tile_id(tile),
+                from_seat: (agari.from - agari.seat + 4) % 4,
+            },
+            None => match player.hand.last() {
+                Some(tile) => WinKind::Tsumo(tile_id(*tile)),
+                None => return error("Winning tile is not found"),
+            },
+        };
+
+        let to_ids = |tiles: &[u8]| tiles.iter().map(|t| tile_id(*t)).collect::<Vec<i8>>();
+        let hand = RiichiHand {
+            closed_part: to_ids(&player.hand),
+            open_part: player
+                .melds
+                .iter()
+                .map(|(open, tiles)| (*open, to_ids(tiles)))
+                .collect(),
+        };
+
+        let aka_count = player
+            .hand
+            .iter()
+            .chain(player.melds.iter().flat_map(|(_, tiles)| tiles.iter()))
+            .chain(ron_tile.iter())
+            .filter(|t| RED_FIVES.contains(t))
+            .count() as i8;
+        let mut dora: Vec<i8> = agari
+            .dora_indicators
+            .iter()
+            .map(|t| dora_from_indicator(tile_id(*t)))
+            .collect();
+        if player.riichi {
+            dora.extend(
+                agari
+                    .uradora_indicators
+                    .iter()
+                    .map(|t| dora_from_indicator(tile_id(*t))),
+            );
+        }
+
+        let options = RiichiOptions {
+            dora,
+            aka_count: if allow_aka { aka_count } else { 0 },
+            first_take: no_calls && player.discards == 0,
+            riichi: player.riichi,
+            ippatsu: player.ippatsu,
+            double_riichi: player.double_riichi,
+            open_riichi: false,
+            open_riichi_deal_in: false,
+            last_tile: draws == LIVE_TILES as i32 && !after_kan,
+            after_kan,
+            tile_discarded_by_someone: ron_tile.map_or(-1, tile_id),
+            bakaze: round.bakaze,
+            jikaze: 28 + ((agari.seat - round.dealer + 4) % 4),
+            allow_aka,
+            allow_kuitan: true,
+            with_kiriage: false,
+            disabled_yaku: vec![],
+            local_yaku_enabled: vec![],
+            all_local_yaku_enabled: false,
+            allow_double_yakuman: false,
+        };
+        hands.push((hand, options, win));
+    }
+    Ok(hands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::riichi::calc_win;
+
+    // Dealer discards haku, it is called by the next player, then riichi and ron on the ippatsu turn
+    static JSON_LOG: &str = r#"{
+        "title": ["", ""],
+        "name": ["A", "B", "C", "D"],
+        "rule": {"disp": "般南喰赤", "aka": 1},
+        "log": [[
+            [0, 0, 0],
+            [25000, 25000, 25000, 25000],
+            [42],
+            [47],
+            [11, 12, 13, 14, 15, 16, 17, 18, 19, 41, 41, 42, 45], [22], [45],
+            [21, 21, 23, 24, 25, 31, 32, 33, 45, 45, 37, 38, 39], ["p454545"], [23],
+            [13, 13, 14, 15, 16, 26, 27, 28, 34, 35, 36, 29, 29], [44], ["r44"],
+            [31, 31, 31, 32, 34, 42, 43, 44, 46, 46, 47, 47, 47], [29], [60],
+            ["和了", [0, 0, 3600, -2600], [2, 3, 2, "40符2飜2600点", "立直(1飜)", "一発(1飜)"]]
+        ]]
+    }"#;
+
+    static MJLOG: &str = r#"<mjloggm ver="2.3"><SHUFFLE seed="" ref=""/><GO type="169" lobby="0"/>
+        <UN n0="%41" n1="B" n2="C" n3="D" dan="0,0,0,0" rate="1500,1500,1500,1500" sx="M,M,M,M"/>
+        <TAIKYOKU oya="0"/>
+        <INIT seed="0,0,0,0,0,114" ten="250,250,250,250" oya="0"
+            hai0="0,4,8,12,17,20,24,28,32,108,109,112,124"
+            hai1="36,37,44,48,53,72,76,80,125,126,96,100,104"
+            hai2="9,10,13,18,21,56,60,64,84,89,92,68,69"
+            hai3="73,74,75,77,85,113,116,120,128,129,132,133,134"/>
+        <T40/><D124/><N who="1" m="47723"/><E44/>
+        <V121/><REACH who="2" step="1"/><F121/><REACH who="2" ten="250,250,240,250" step="2"/>
+        <W70/><G70/>
+        <AGARI ba="0,1" hai="9,10,13,18,21,56,60,64,84,89,92,68,69,70" machi="70" ten="40,2600,0"
+            yaku="1,1,2,1,52,0,53,0" doraHai="114" doraHaiUra="135" who="2" fromWho="3"
+            sc="250,0,250,0,240,36,250,-26" owari="250,0.0,250,0.0,276,0.0,224,0.0"/>
+        </mjloggm>"#;
+
+    fn check_round(game: &LogGame) {
+        assert_eq!(game.rounds.len(), 1);
+        let round = &game.rounds[0];
+        assert_eq!(round.dealer, 0);
+        assert_eq!(round.bakaze, 28);
+        assert_eq!(round.scores, vec![25000; 4]);
+        assert_eq!(round.events.len(), 8);
+        assert!(matches!(
+            round.events[2],
+            LogEvent::Pon {
+                seat: 1,
+                from: 0,
+                ..
+            }
+        ));
+        assert!(matches!(
+            round.events[5],
+            LogEvent::Discard {
+                seat: 2,
+                riichi: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            round.events[7],
+            LogEvent::Discard {
+                seat: 3,
+                tsumogiri: true,
+                ..
+            }
+        ));
+
+        let agari = &round.agari[0];
+        assert_eq!((agari.seat, agari.from, agari.han, agari.fu), (2, 3, 2, 40));
+        assert_eq!(agari.ten, 2600);
+        assert_eq!(
+            agari.yaku,
+            vec![(Yaku::Riichi as i8, 1), (Yaku::Ippatsu as i8, 1)]
+        );
+        assert_eq!(agari.deltas, vec![0, 0, 3600, -2600]);
+
+        let hands = agari_hands(round, game.aka).unwrap();
+        let (hand, options, win) = hands[0].clone();
+        assert_eq!(hand.closed_part.len(), 13);
+        assert!(options.riichi && options.ippatsu && !options.double_riichi);
+        assert_eq!(
+            win,
+            WinKind::Ron {
+                tile: 18,
+                from_seat: 1
+            }
+        );
+        let result = calc_win(&hand, &options, win, false).unwrap();
+        assert_eq!((result.han, result.fu, result.ten), (2, 40, 2600));
+    }
+
+    #[test]
+    pub fn should_parse_json_log() {
+        let game = parse_tenhou_json(JSON_LOG).unwrap();
+        assert_eq!(game.players, vec!["A", "B", "C", "D"]);
+        assert!(game.aka);
+        check_round(&game);
+    }
+
+    #[test]
+    pub fn should_parse_mjlog() {
+        let game = parse_mjlog(MJLOG).unwrap();
+        assert_eq!(game.players, vec!["A", "B", "C", "D"]);
+        assert!(game.aka);
+        check_round(&game);
+    }
+
+    #[test]
+    pub fn should_parse_calls() {
+        assert_eq!(
+            json_call_event(1, "c275226").unwrap(),
+            LogEvent::Chi {
+                seat: 1,
+                from: 0,
+                tiles: vec![json_tile(27).unwrap(), 52, json_tile(26).unwrap()],
+                called: json_tile(27).unwrap(),
+            }
+        );
+        assert!(matches!(
+            json_call_event(1, "25p2525").unwrap(),
+            LogEvent::Pon { from: 3, .. }
+        ));
+        assert!(matches!(
+            json_call_event(1, "2525p25").unwrap(),
+            LogEvent::Pon { from: 2, .. }
+        ));
+        assert!(matches!(
+            json_call_event(0, "393939m39").unwrap(),
+            LogEvent::Daiminkan { from: 1, .. }
+        ));
+        assert!(matches!(
+            json_call_event(0, "262626a26").unwrap(),
+            LogEvent::Ankan { seat: 0, .. }
+        ));
+        assert!(json_call_event(0, "p25").is_err());
+
+        // chi 1s2s3s called from kamicha
+        assert_eq!(
+            decode_meld(1, 43303),
+            LogEvent::Chi {
+                seat: 1,
+                from: 0,
+                tiles: vec![72, 77, 82],
+                called: 72,
+            }
+        );
+        assert_eq!(
+            decode_meld(0, (40 << 8) | 2),
+            LogEvent::Daiminkan {
+                seat: 0,
+                from: 2,
+                tiles: vec![40, 41, 42, 43],
+                called: 40,
+            }
+        );
+    }
+}
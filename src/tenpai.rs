@@ -0,0 +1,254 @@
+use crate::agari::find_waits;
+use crate::constants::tiles_to_34;
+use crate::interfaces::{
+    RiichiDeclaration, RiichiDeclarationWin, RiichiHand, RiichiOptions, RiichiResult, TenpaiResult,
+    TenpaiWait,
+};
+use crate::riichi::{score_win, split_hand};
+
+// Unseen copies of the tile: not in own hand (including melds) and not in visible tiles
+pub(crate) fn count_remaining(tile: i8, haipai: &[i8], furo: &[Vec<i8>], visible: &[i8]) -> i8 {
+    let used = haipai.iter().filter(|t| **t == tile).count()
+        + furo.iter().flatten().filter(|t| t.abs() == tile).count()
+        + visible.iter().filter(|t| **t == tile).count();
+    (4 - used as i8).max(0)
+}
+
+// Value of the win on the wait, nothing if there is no yaku
+fn score_wait(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    tile: i8,
+    is_tsumo: bool,
+) -> Result<Option<RiichiResult>, String> {
+    let result = if is_tsumo {
+        let mut hand = hand.clone();
+        hand.closed_part.push(tile);
+        score_win(&hand, options, tile, true, false)
+    } else {
+        score_win(hand, options, tile, false, false)
+    };
+    match result {
+        Ok(result) => Ok(Some(result)),
+        Err(e) if e == "no yaku" => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Check every wait of a tenpai hand for yaku and value on ron and tsumo.
+// Furiten is not checked here, see check_furiten for this.
+pub fn analyze_tenpai(
+    hand: &RiichiHand,
+    options: &RiichiOptions,
+    visible: &[i8],
+) -> Result<TenpaiResult, String> {
+    let (haipai, furo) = split_hand(hand);
+
+    if haipai.len() % 3 != 1 || haipai.len() + furo.len() * 3 > 13 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+
+    let mut result = TenpaiResult {
+        waits: Vec::new(),
+        remaining: 0,
+        ron_ev: 0.0,
+        tsumo_ev: 0.0,
+    };
+
+    let mut ron_total = 0.0;
+    let mut tsumo_total = 0.0;
+    for tile in find_waits(&tiles_to_34(&haipai), &furo) {
+        let wait = TenpaiWait {
+            tile,
+            remaining: count_remaining(tile, &haipai, &furo, visible),
+            ron: score_wait(hand, options, tile, false)?,
+            tsumo: score_wait(hand, options, tile, true)?,
+        };
+        result.remaining += wait.remaining;
+        ron_total += wait.remaining as f32 * wait.ron.as_ref().map_or(0, |r| r.ten) as f32;
+        tsumo_total += wait.remaining as f32 * wait.tsumo.as_ref().map_or(0, |r| r.ten) as f32;
+        result.waits.push(wait);
+    }
+
+    if result.remaining > 0 {
+        result.ron_ev = ron_total / result.remaining as f32;
+        result.tsumo_ev = tsumo_total / result.remaining as f32;
+    }
+
+    Ok(result)
+}
+
+// Value of riichi after every discard which keeps closed 14-tile hand in tenpai.
+// Discards and waits are tile indices, same as in HairiResult.
+pub(crate) fn calc_riichi_declarations(
+    haipai: &[i8],
+    furo: &[Vec<i8>],
+    options: &RiichiOptions,
+    waits_after_discard: &[(i8, Vec<i8>)],
+) -> Vec<RiichiDeclaration> {
+    let open_part = furo
+        .iter()
+        .map(|meld| (meld[0] > 0, meld.iter().map(|t| t.abs()).collect()))
+        .collect::<Vec<(bool, Vec<i8>)>>();
+
+    let mut with_riichi = options.clone();
+    with_riichi.riichi = true;
+    let mut without_riichi = options.clone();
+    without_riichi.riichi = false;
+    without_riichi.double_riichi = false;
+    without_riichi.open_riichi = false;
+    without_riichi.ippatsu = false;
+
+    waits_after_discard
+        .iter()
+        .filter_map(|(discard, _)| {
+            let mut closed_part = haipai.to_vec();
+            let pos = closed_part.iter().position(|t| *t == discard + 1)?;
+            closed_part.remove(pos);
+            let hand = RiichiHand {
+                closed_part,
+                open_part: open_part.clone(),
+            };
+
+            let tenpai = analyze_tenpai(&hand, &with_riichi, &[]).ok()?;
+            let damaten = analyze_tenpai(&hand, &without_riichi, &[]).ok()?;
+            if tenpai.waits.is_empty() {
+                return None;
+            }
+
+            Some(RiichiDeclaration {
+                discard: *discard,
+                waits: tenpai.waits.iter().map(|w| w.tile - 1).collect(),
+                live_tiles: tenpai.remaining,
+                has_yaku_without_riichi: damaten.waits.iter().all(|w| w.ron.is_some()),
+                values: tenpai
+                    .waits
+                    .iter()
+                    .map(|w| RiichiDeclarationWin {
+                        tile: w.tile - 1,
+                        ron_han: w.ron.as_ref().map_or(0, |r| r.han),
+                        ron_ten: w.ron.as_ref().map_or(0, |r| r.ten),
+                        tsumo_han: w.tsumo.as_ref().map_or(0, |r| r.han),
+                        tsumo_ten: w.tsumo.as_ref().map_or(0, |r| r.ten),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{Tiles, Yaku};
+
+    fn options() -> RiichiOptions {
+        RiichiOptions {
+            jikaze: Tiles::W as i8,
+            ..RiichiOptions::for_tests()
+        }
+    }
+
+    #[test]
+    pub fn should_detect_yakunashi_waits() {
+        let res = analyze_tenpai(
+            &RiichiHand {
+                closed_part: vec![
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::M4 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P6 as i8,
+                    Tiles::P7 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::WD as i8,
+                    Tiles::WD as i8,
+                ],
+                open_part: vec![(true, vec![Tiles::N as i8, Tiles::N as i8, Tiles::N as i8])],
+            },
+            &options(),
+            &[Tiles::WD as i8],
+        )
+        .unwrap();
+
+        assert_eq!(res.waits.len(), 2);
+
+        let s5 = &res.waits[0];
+        assert_eq!(s5.tile, Tiles::S5 as i8);
+        assert_eq!(s5.remaining, 2);
+        assert!(s5.ron.is_none());
+        assert!(s5.tsumo.is_none());
+
+        let haku = &res.waits[1];
+        assert_eq!(haku.tile, Tiles::WD as i8);
+        assert_eq!(haku.remaining, 1);
+        let ron = haku.ron.as_ref().unwrap();
+        assert_eq!(ron.yaku, vec![(Yaku::Haku as i8, 1)]);
+        assert_eq!(ron.ten, 1000);
+        let tsumo = haku.tsumo.as_ref().unwrap();
+        assert_eq!(tsumo.fu, 40);
+        assert_eq!(tsumo.ten, 1500);
+
+        assert_eq!(res.remaining, 3);
+        assert_eq!(res.ron_ev, 1000.0 / 3.0);
+        assert_eq!(res.tsumo_ev, 500.0);
+    }
+
+    #[test]
+    pub fn should_return_no_waits_for_non_tenpai_hand() {
+        let res = analyze_tenpai(
+            &RiichiHand {
+                closed_part: vec![
+                    Tiles::M1 as i8,
+                    Tiles::M4 as i8,
+                    Tiles::M7 as i8,
+                    Tiles::P2 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P8 as i8,
+                    Tiles::S3 as i8,
+                    Tiles::S6 as i8,
+                    Tiles::S9 as i8,
+                    Tiles::E as i8,
+                    Tiles::S as i8,
+                    Tiles::W as i8,
+                    Tiles::N as i8,
+                ],
+                open_part: vec![],
+            },
+            &options(),
+            &[],
+        )
+        .unwrap();
+
+        assert!(res.waits.is_empty());
+        assert_eq!(res.ron_ev, 0.0);
+    }
+
+    #[test]
+    pub fn should_return_scoring_errors() {
+        // Chankan on a tile held in hand is not a missing yaku, it is incorrect input
+        let mut options = options();
+        options.after_kan = true;
+        let res = analyze_tenpai(
+            &RiichiHand {
+                closed_part: vec![
+                    Tiles::M2 as i8,
+                    Tiles::M3 as i8,
+                    Tiles::M4 as i8,
+                    Tiles::P5 as i8,
+                    Tiles::P6 as i8,
+                    Tiles::P7 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::S5 as i8,
+                    Tiles::WD as i8,
+                    Tiles::WD as i8,
+                ],
+                open_part: vec![(true, vec![Tiles::N as i8, Tiles::N as i8, Tiles::N as i8])],
+            },
+            &options,
+            &[],
+        );
+        assert_eq!(res.err().unwrap(), "Chankan tile is in hand");
+    }
+}
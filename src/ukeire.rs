@@ -0,0 +1,468 @@
+use crate::constants::tiles_to_34;
+use crate::interfaces::{
+    DiscardEvaluation, HandEvaluation, HandForm, RiichiHand, TenpaiShape, UkeireDiscard,
+    UkeireResult, UkeireTile, WaitQuality,
+};
+use crate::riichi::split_hand;
+use crate::shanten::{calc_shanten_for_form, shanten_with_melds};
+use crate::tenpai::count_remaining;
+use std::collections::HashMap;
+
+// Closed part in 34 format and unseen copies of every tile
+pub(crate) fn unseen_tiles(hand: &RiichiHand, visible: &[i8]) -> (Vec<i8>, Vec<i8>) {
+    let (haipai, furo) = split_hand(hand);
+    let unseen = (1..=34)
+        .map(|tile| count_remaining(tile, &haipai, &furo, visible))
+        .collect();
+    (tiles_to_34(&haipai), unseen)
+}
+
+pub(crate) fn ukeire(closed34: &mut [i8], unseen: &[i8]) -> UkeireResult {
+    ukeire_by(closed34, unseen, shanten_with_melds)
+}
+
+fn ukeire_by(
+    closed34: &mut [i8],
+    unseen: &[i8],
+    calc_shanten: impl Fn(&[i8]) -> i8,
+) -> UkeireResult {
+    let shanten = calc_shanten(closed34);
+    let mut tiles: Vec<UkeireTile> = Vec::new();
+    for i in 0..34 {
+        if closed34[i] >= 4 {
+            continue;
+        }
+        closed34[i] += 1;
+        if calc_shanten(closed34) < shanten {
+            tiles.push(UkeireTile {
+                tile: i as i8 + 1,
+                remaining: unseen[i],
+            });
+        }
+        closed34[i] -= 1;
+    }
+    UkeireResult {
+        shanten,
+        total: tiles.iter().map(|t| t.remaining as i32).sum(),
+        tiles,
+    }
+}
+
+// Tiles improving 13-tile hand and how many of them are left.
+// Visible tiles are all discards, melds of other players and dora indicators.
+pub fn calc_ukeire(hand: &RiichiHand, visible: &[i8]) -> Result<UkeireResult, String> {
+    let (mut closed34, unseen) = unseen_tiles(hand, visible);
+    if closed34.iter().sum::<i8>() % 3 != 1 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    Ok(ukeire(&mut closed34, &unseen))
+}
+
+// Tiles improving 13-tile hand towards one form only
+pub fn calc_ukeire_for_form(
+    hand: &RiichiHand,
+    visible: &[i8],
+    form: HandForm,
+) -> Result<UkeireResult, String> {
+    let (mut closed34, unseen) = unseen_tiles(hand, visible);
+    if closed34.iter().sum::<i8>() % 3 != 1 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    if calc_shanten_for_form(&closed34, form).is_none() {
+        return Err(format!("{:?} is not possible with melds", form));
+    }
+    Ok(ukeire_by(&mut closed34, &unseen, |tiles| {
+        calc_shanten_for_form(tiles, form).unwrap()
+    }))
+}
+
+// All discards of 14-tile hand: lowest shanten first, then by total acceptance
+pub fn rank_discards(hand: &RiichiHand, visible: &[i8]) -> Result<Vec<UkeireDiscard>, String> {
+    // Discarded tile becomes visible, so unseen copies stay the same
+    let (mut closed34, unseen) = unseen_tiles(hand, visible);
+    if closed34.iter().sum::<i8>() % 3 != 2 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    let mut discards: Vec<UkeireDiscard> = Vec::new();
+    for i in 0..34 {
+        if closed34[i] == 0 {
+            continue;
+        }
+        closed34[i] -= 1;
+        discards.push(UkeireDiscard {
+            discard: i as i8 + 1,
+            result: ukeire(&mut closed34, &unseen),
+        });
+        closed34[i] += 1;
+    }
+    discards.sort_by_key(|d| (d.result.shanten, -d.result.total));
+    Ok(discards)
+}
+
+// Good shape waits on more than one tile kind, but shanpon (two pairs) is still a poor one
+fn wait_quality(closed34: &[i8], waits: &[UkeireTile]) -> WaitQuality {
+    let shanpon = waits.len() == 2 && waits.iter().all(|w| closed34[(w.tile - 1) as usize] == 2);
+    if waits.len() > 1 && !shanpon {
+        WaitQuality::Good
+    } else {
+        WaitQuality::Poor
+    }
+}
+
+// Discard of 14-tile hand keeping given shanten with the largest acceptance
+fn best_discard(closed34: &mut [i8], unseen: &[i8], shanten: i8) -> Option<(i8, UkeireResult)> {
+    let mut best: Option<(i8, UkeireResult)> = None;
+    for i in 0..34 {
+        if closed34[i] == 0 {
+            continue;
+        }
+        closed34[i] -= 1;
+        if shanten_with_melds(closed34) == shanten {
+            let result = ukeire(closed34, unseen);
+            if best.as_ref().is_none_or(|(_, b)| result.total > b.total) {
+                best = Some((i as i8 + 1, result));
+            }
+        }
+        closed34[i] += 1;
+    }
+    best
+}
+
+// Chance to get tenpai within given draws, taking the best discard after every improvement.
+// Draws are independent and weighted by unseen copies, useless tiles are discarded right away.
+fn tenpai_chance(
+    closed34: &mut [i8],
+    unseen: &[i8],
+    pool: i32,
+    draws: i8,
+    memo: &mut HashMap<(Vec<i8>, i8), f32>,
+) -> f32 {
+    let shanten = shanten_with_melds(closed34);
+    if shanten <= 0 {
+        return 1.0;
+    }
+    if draws < shanten || pool <= 0 {
+        return 0.0;
+    }
+    let key = (closed34.to_vec(), draws);
+    if let Some(chance) = memo.get(&key) {
+        return *chance;
+    }
+
+    let mut chance = 0.0;
+    let mut useless = 1.0;
+    for tile in ukeire(closed34, unseen).tiles {
+        if tile.remaining <= 0 {
+            continue;
+        }
+        let draw = (tile.tile - 1) as usize;
+        let probability = tile.remaining as f32 / pool as f32;
+        useless -= probability;
+        closed34[draw] += 1;
+        if let Some((discard, _)) = best_discard(closed34, unseen, shanten - 1) {
+            closed34[(discard - 1) as usize] -= 1;
+            chance += probability * tenpai_chance(closed34, unseen, pool, draws - 1, memo);
+            closed34[(discard - 1) as usize] += 1;
+        }
+        closed34[draw] -= 1;
+    }
+    chance += useless.max(0.0) * tenpai_chance(closed34, unseen, pool, draws - 1, memo);
+
+    memo.insert(key, chance);
+    chance
+}
+
+fn evaluate(closed34: &mut [i8], unseen: &[i8], draws: i8) -> HandEvaluation {
+    let first = ukeire(closed34, unseen);
+    let mut ukeire2 = 0;
+    let mut tenpai_shapes: Vec<TenpaiShape> = Vec::new();
+    let mut good_shape_tiles = 0;
+
+    if first.shanten == 0 {
+        if wait_quality(closed34, &first.tiles) == WaitQuality::Good {
+            good_shape_tiles = first.total;
+        }
+    } else {
+        for tile in &first.tiles {
+            let draw = (tile.tile - 1) as usize;
+            closed34[draw] += 1;
+            if let Some((discard, next)) = best_discard(closed34, unseen, first.shanten - 1) {
+                ukeire2 += tile.remaining.max(0) as i32 * next.total;
+                if next.shanten == 0 {
+                    closed34[(discard - 1) as usize] -= 1;
+                    let quality = wait_quality(closed34, &next.tiles);
+                    closed34[(discard - 1) as usize] += 1;
+                    if quality == WaitQuality::Good {
+                        good_shape_tiles += tile.remaining.max(0) as i32;
+                    }
+                    tenpai_shapes.push(TenpaiShape {
+                        draw: tile.tile,
+                        discard,
+                        waits: next.tiles.iter().map(|w| w.tile).collect(),
+                        remaining: next.tiles.iter().map(|w| w.remaining).sum(),
+                        quality,
+                    });
+                }
+            }
+            closed34[draw] -= 1;
+        }
+    }
+
+    let pool = unseen.iter().map(|c| *c as i32).sum();
+    HandEvaluation {
+        shanten: first.shanten,
+        ukeire: first.total,
+        ukeire2,
+        tenpai_shapes,
+        good_shape_tiles,
+        tenpai_probability: tenpai_chance(closed34, unseen, pool, draws, &mut HashMap::new()),
+    }
+}
+
+// Deeper evaluation of 13-tile hand: acceptance after the next improvement, tenpai wait shapes
+// and chance to reach tenpai within given number of draws
+pub fn evaluate_hand(
+    hand: &RiichiHand,
+    visible: &[i8],
+    draws: i8,
+) -> Result<HandEvaluation, String> {
+    let (mut closed34, unseen) = unseen_tiles(hand, visible);
+    if closed34.iter().sum::<i8>() % 3 != 1 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    Ok(evaluate(&mut closed34, &unseen, draws))
+}
+
+// All discards of 14-tile hand: lowest shanten first, then by ukeire2, tenpai chance and ukeire.
+// Ukeire2 counts acceptance after the improvement, so draws into good shapes weigh more.
+pub fn evaluate_discards(
+    hand: &RiichiHand,
+    visible: &[i8],
+    draws: i8,
+) -> Result<Vec<DiscardEvaluation>, String> {
+    let (mut closed34, unseen) = unseen_tiles(hand, visible);
+    if closed34.iter().sum::<i8>() % 3 != 2 {
+        return Err("Incorrect number of tiles".parse().unwrap());
+    }
+    let mut discards: Vec<DiscardEvaluation> = Vec::new();
+    for i in 0..34 {
+        if closed34[i] == 0 {
+            continue;
+        }
+        closed34[i] -= 1;
+        discards.push(DiscardEvaluation {
+            discard: i as i8 + 1,
+            evaluation: evaluate(&mut closed34, &unseen, draws),
+        });
+        closed34[i] += 1;
+    }
+    discards.sort_by(|a, b| {
+        let (a, b) = (&a.evaluation, &b.evaluation);
+        a.shanten
+            .cmp(&b.shanten)
+            .then(b.ukeire2.cmp(&a.ukeire2))
+            .then(b.tenpai_probability.total_cmp(&a.tenpai_probability))
+            .then(b.ukeire.cmp(&a.ukeire))
+    });
+    Ok(discards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::Tiles::*;
+
+    #[test]
+    pub fn should_count_remaining_tiles() {
+        // Waiting on 3-6p, two 3p are discarded and one 6p is dora indicator
+        let hand = RiichiHand::for_tests(vec![M1, M2, M3, M4, M5, M6, P4, P5, S7, S8, S9, E, E]);
+        let result = calc_ukeire(&hand, &[P3 as i8, P3 as i8, P6 as i8]).unwrap();
+        assert_eq!(result.shanten, 0);
+        assert_eq!(
+            result.tiles,
+            vec![
+                UkeireTile {
+                    tile: P3 as i8,
+                    remaining: 2
+                },
+                UkeireTile {
+                    tile: P6 as i8,
+                    remaining: 3
+                },
+            ]
+        );
+        assert_eq!(result.total, 5);
+    }
+
+    #[test]
+    pub fn should_count_own_melds() {
+        // Tanki wait on east with the pon of east
+        let result = calc_ukeire(
+            &RiichiHand {
+                closed_part: vec![E as i8],
+                open_part: vec![
+                    (true, vec![E as i8, E as i8, E as i8]),
+                    (true, vec![M1 as i8, M2 as i8, M3 as i8]),
+                    (true, vec![P1 as i8, P2 as i8, P3 as i8]),
+                    (false, vec![S9 as i8, S9 as i8, S9 as i8, S9 as i8]),
+                ],
+            },
+            &[],
+        )
+        .unwrap();
+        assert_eq!(result.total, 0);
+        assert_eq!(result.tiles.len(), 1);
+        assert!(calc_ukeire(&RiichiHand::for_tests(vec![M1, M1, M1]), &[]).is_err());
+    }
+
+    #[test]
+    pub fn should_skip_kokushi_with_melds() {
+        // Ten terminals and honors would be 3 shanten to kokushi without the pon
+        let mut hand = RiichiHand::for_tests(vec![M1, M9, P1, P9, S1, S9, E, S, W, N]);
+        hand.open_part = vec![(true, vec![P5 as i8, P5 as i8, P5 as i8])];
+        let result = calc_ukeire(&hand, &[]).unwrap();
+        assert_eq!(result.shanten, 6);
+        assert_eq!(result.shanten, crate::hairi_for_hand(&hand).unwrap().now);
+
+        // Discard after the draw keeps the regular shanten
+        hand.closed_part.push(M2 as i8);
+        let discards = rank_discards(&hand, &[]).unwrap();
+        assert!(discards.iter().all(|d| d.result.shanten >= 5));
+    }
+
+    #[test]
+    pub fn should_rank_discards() {
+        // Only isolated north keeps tenpai
+        let hand = RiichiHand::for_tests(vec![M1, M2, M3, M4, M5, M6, P2, P4, S2, S3, S4, E, E, N]);
+        let discards = rank_discards(&hand, &[]).unwrap();
+        assert_eq!(discards[0].discard, N as i8);
+        assert_eq!(discards[0].result.shanten, 0);
+        assert_eq!(discards[0].result.total, 4);
+        assert!(discards[1..].iter().all(|d| d.result.shanten == 1));
+        assert!(discards[1].result.total >= discards[2].result.total);
+        assert_eq!(discards.len(), 13);
+        assert!(
+            rank_discards(&hand.clone(), &[N as i8; 4])
+                .unwrap()
+                .iter()
+                .all(|d| d.result.tiles.iter().all(|t| t.remaining >= 0))
+        );
+    }
+
+    #[test]
+    pub fn should_classify_waits() {
+        // Ryanmen, kanchan and shanpon waits of the same hand
+        let ryanmen = evaluate_hand(
+            &RiichiHand::for_tests(vec![M1, M2, M3, M4, M5, M6, P4, P5, S7, S8, S9, E, E]),
+            &[],
+            1,
+        )
+        .unwrap();
+        assert_eq!(ryanmen.shanten, 0);
+        assert_eq!(ryanmen.good_shape_tiles, 8);
+        assert_eq!(ryanmen.tenpai_probability, 1.0);
+        let kanchan = evaluate_hand(
+            &RiichiHand::for_tests(vec![M1, M2, M3, M4, M5, M6, P4, P6, S7, S8, S9, E, E]),
+            &[],
+            1,
+        )
+        .unwrap();
+        assert_eq!(kanchan.good_shape_tiles, 0);
+        let shanpon = evaluate_hand(
+            &RiichiHand::for_tests(vec![M1, M2, M3, M4, M5, M6, P4, P4, S7, S8, S9, E, E]),
+            &[],
+            1,
+        )
+        .unwrap();
+        assert_eq!(shanpon.ukeire, 4);
+        assert_eq!(shanpon.good_shape_tiles, 0);
+    }
+
+    #[test]
+    pub fn should_evaluate_iishanten() {
+        // Pairing 1p or 9p gives ryanmen on 2-5m, completing 34m leaves tanki
+        let hand = RiichiHand::for_tests(vec![M1, M2, M3, M3, M4, P1, P4, P5, P6, P9, S7, S8, S9]);
+        let result = evaluate_hand(&hand, &[], 3).unwrap();
+        assert_eq!(result.shanten, 1);
+        assert_eq!(result.ukeire, 13);
+        assert_eq!(result.tenpai_shapes.len(), 4);
+        assert_eq!(result.good_shape_tiles, 6);
+        assert_eq!(result.ukeire2, (3 + 4) * 3 + (3 + 3) * 7);
+        let shape = result
+            .tenpai_shapes
+            .iter()
+            .find(|s| s.draw == P1 as i8)
+            .unwrap();
+        assert_eq!(shape.discard, P9 as i8);
+        assert_eq!(shape.waits, vec![M2 as i8, M5 as i8]);
+        assert_eq!(shape.remaining, 7);
+        assert_eq!(shape.quality, WaitQuality::Good);
+        // 13 of 123 unseen tiles lower shanten on every draw
+        let expected = 1.0 - (110.0f32 / 123.0).powi(3);
+        assert!((result.tenpai_probability - expected).abs() < 1e-5);
+        let longer = evaluate_hand(&hand, &[], 6).unwrap();
+        assert!(longer.tenpai_probability > result.tenpai_probability);
+        assert_eq!(
+            evaluate_hand(&hand, &[], 0).unwrap().tenpai_probability,
+            0.0
+        );
+    }
+
+    #[test]
+    pub fn should_prefer_good_shapes() {
+        // Cutting 6m accepts more tiles, but after cutting 1m most improvements give good shape tenpai
+        let hand =
+            RiichiHand::for_tests(vec![M1, M2, M5, M5, M5, M6, P3, P4, P5, S1, S2, S3, S6, S7]);
+        assert_eq!(rank_discards(&hand, &[]).unwrap()[0].discard, M6 as i8);
+        let discards = evaluate_discards(&hand, &[], 4).unwrap();
+        let (best, raw) = (
+            &discards[0].evaluation,
+            &discards
+                .iter()
+                .find(|d| d.discard == M6 as i8)
+                .unwrap()
+                .evaluation,
+        );
+        assert_eq!(discards[0].discard, M1 as i8);
+        assert_eq!(
+            (best.ukeire, best.ukeire2, best.good_shape_tiles),
+            (22, 200, 22)
+        );
+        assert_eq!(
+            (raw.ukeire, raw.ukeire2, raw.good_shape_tiles),
+            (24, 136, 10)
+        );
+        assert!(
+            discards
+                .windows(2)
+                .all(|w| w[0].evaluation.shanten <= w[1].evaluation.shanten)
+        );
+        assert!(evaluate_hand(&hand, &[], 4).is_err());
+    }
+
+    #[test]
+    pub fn should_count_ukeire_for_form() {
+        let hand = RiichiHand::for_tests(vec![M1, M1, M9, M9, P2, P2, P8, P8, S3, S3, S7, S7, E]);
+        let chiitoitsu = calc_ukeire_for_form(&hand, &[E as i8], HandForm::Chiitoitsu).unwrap();
+        assert_eq!(chiitoitsu.shanten, 0);
+        assert_eq!(
+            chiitoitsu.tiles,
+            vec![UkeireTile {
+                tile: E as i8,
+                remaining: 2
+            }]
+        );
+        let regular = calc_ukeire_for_form(&hand, &[], HandForm::Regular).unwrap();
+        assert_eq!(regular.shanten, 3);
+        assert!(regular.total > chiitoitsu.total);
+
+        let open = RiichiHand {
+            closed_part: vec![
+                M1 as i8, M1 as i8, M9 as i8, M9 as i8, E as i8, E as i8, P1 as i8,
+            ],
+            open_part: vec![(true, vec![S1 as i8, S2 as i8, S3 as i8])],
+        };
+        assert!(calc_ukeire_for_form(&open, &[], HandForm::Chiitoitsu).is_err());
+        assert!(calc_ukeire_for_form(&open, &[], HandForm::Regular).is_ok());
+    }
+}
@@ -1,15 +1,15 @@
-use crate::agari::{check7, check13};
+use crate::agari::{SetTiles, check7, check13};
 use crate::constants::{
     CHI_START, GREENS, HONORS, SIMPLE_TILES, Suit, TERMINALS, TERMINALS_AND_HONORS, Tiles, WINDS,
-    is19, slice_by_suit, sum,
+    Yaku, is19,
 };
-use std::collections::HashSet;
+use crate::hand34::Hand34;
 
 pub struct YakuCheckInput<'t> {
     pub(crate) haipai: &'t Vec<i8>,
-    pub(crate) haipai34: &'t Vec<i8>,
+    pub(crate) haipai34: Hand34,
     pub(crate) furo: &'t Vec<Vec<i8>>,
-    pub(crate) current_pattern: &'t Vec<Vec<i8>>,
+    pub(crate) current_pattern: &'t [SetTiles],
     pub(crate) taken_tile: i8,
     pub(crate) is_tsumo: bool,
     pub(crate) jikaze: i8,
@@ -17,6 +17,8 @@ pub struct YakuCheckInput<'t> {
     pub(crate) first_take: bool,
     pub(crate) riichi: bool,
     pub(crate) double_riichi: bool,
+    pub(crate) open_riichi: bool,
+    pub(crate) open_riichi_deal_in: bool,
     pub(crate) ippatsu: bool,
     pub(crate) after_kan: bool,
     pub(crate) last_tile: bool,
@@ -24,6 +26,7 @@ pub struct YakuCheckInput<'t> {
 }
 
 pub struct YakuCheck {
+    pub(crate) id: i8,
     pub(crate) is_local: bool,
     pub(crate) yakuman: i8,
     pub(crate) han: i8,
@@ -32,7 +35,7 @@ pub struct YakuCheck {
     pub(crate) check: fn(&YakuCheckInput) -> bool,
 }
 
-fn check_allowed(haipai: &Vec<i8>, furo: &Vec<Vec<i8>>, allowed: &Vec<i8>) -> bool {
+fn check_allowed(haipai: &Vec<i8>, furo: &Vec<Vec<i8>>, allowed: &[i8]) -> bool {
     for v in haipai {
         if !allowed.contains(v) {
             return false;
@@ -50,26 +53,20 @@ fn check_allowed(haipai: &Vec<i8>, furo: &Vec<Vec<i8>>, allowed: &Vec<i8>) -> bo
     true
 }
 
-fn to_hand(pattern: &Vec<Vec<i8>>) -> Vec<i8> {
-    let occurrences = pattern.iter().flatten().collect::<Vec<&i8>>();
-    let mut hand = vec![
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0, 0, 0, //
-        0, 0, 0, 0, 0, 0, 0,
-    ];
-    for tile in occurrences {
-        hand[(tile.abs() - 1) as usize] += 1;
+fn to_hand(pattern: &[SetTiles]) -> Hand34 {
+    let mut hand = Hand34::new();
+    for tile in pattern.iter().flat_map(|set| set.iter()) {
+        hand.add((tile.abs() - 1) as usize, 1);
     }
     hand
 }
 
 // Get same suit index or -1 if there are more than one suit in hand or hand consists of HONORS
-fn get_same_suit(current_pattern: &Vec<Vec<i8>>, exclude_honors: bool) -> i8 {
+fn get_same_suit(current_pattern: &[SetTiles], exclude_honors: bool) -> i8 {
     let mut found_suit: i8 = -1;
-    let slices = slice_by_suit(&to_hand(current_pattern));
+    let hand = to_hand(current_pattern);
     for i in 0..4 {
-        if sum(&slices[i]) == 0 {
+        if hand.suit_count(i) == 0 {
             continue;
         }
         if exclude_honors && i == Suit::Honor as usize {
@@ -86,7 +83,7 @@ fn get_same_suit(current_pattern: &Vec<Vec<i8>>, exclude_honors: bool) -> i8 {
 }
 
 // Simple 64-bit digest for positive numbers not more than 7-bit length (<63)
-fn digest_simple(input: &Vec<i8>) -> i64 {
+fn digest_simple(input: &[i8]) -> i64 {
     let mut output: i64 = 0;
     for i in 0..input.len() {
         output += (input[i] as i64) << 7 * i;
@@ -94,7 +91,7 @@ fn digest_simple(input: &Vec<i8>) -> i64 {
     output
 }
 
-fn check_chanta_like(current_pattern: &Vec<Vec<i8>>, allow: &Vec<i8>) -> bool {
+fn check_chanta_like(current_pattern: &[SetTiles], allow: &[i8]) -> bool {
     let mut has_jyuntsu = false;
     for v in current_pattern {
         if v.len() <= 2 || v[0] == v[1] {
@@ -118,7 +115,7 @@ fn check_chanta_like(current_pattern: &Vec<Vec<i8>>, allow: &Vec<i8>) -> bool {
     has_jyuntsu
 }
 
-fn check_yakuhai(current_pattern: &Vec<Vec<i8>>, jikaze: i8, bakaze: i8, which: i8) -> bool {
+fn check_yakuhai(current_pattern: &[SetTiles], jikaze: i8, bakaze: i8, which: i8) -> bool {
     for v in current_pattern {
         if v[0].abs() == which && [jikaze, bakaze, 32, 33, 34].contains(&v[0].abs()) && v.len() >= 3
         {
@@ -131,7 +128,7 @@ fn check_yakuhai(current_pattern: &Vec<Vec<i8>>, jikaze: i8, bakaze: i8, which:
 // Certain yaku checkers
 
 fn yaku_check_kokushimusou_13_sides(i: &YakuCheckInput) -> bool {
-    check13(i.haipai34)
+    check13(&i.haipai34)
         && i.haipai.iter().fold(
             0,
             |total, v| {
@@ -141,7 +138,7 @@ fn yaku_check_kokushimusou_13_sides(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_kokusimusou(i: &YakuCheckInput) -> bool {
-    check13(i.haipai34)
+    check13(&i.haipai34)
         && i.haipai.iter().fold(
             0,
             |total, v| {
@@ -151,31 +148,27 @@ fn yaku_check_kokusimusou(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_chuurenpoto_9_sides(i: &YakuCheckInput) -> bool {
-    let slices = slice_by_suit(i.haipai34);
     let found_suit = get_same_suit(i.current_pattern, false);
-    if found_suit == -1
-        || slices[found_suit as usize][0] < 3
-        || slices[found_suit as usize][8] < 3
-        || slices[found_suit as usize].contains(&0)
-    {
+    if found_suit == -1 {
         return false;
     }
-    i.taken_tile != -1
-        && [2, 4].contains(&slices[found_suit as usize][((i.taken_tile - 1) % 9) as usize])
+    let suit = i.haipai34.suit(found_suit as usize);
+    if suit[0] < 3 || suit[8] < 3 || suit.contains(&0) {
+        return false;
+    }
+    i.taken_tile != -1 && [2, 4].contains(&suit[((i.taken_tile - 1) % 9) as usize])
 }
 
 fn yaku_check_chuurenpoto(i: &YakuCheckInput) -> bool {
-    let slices = slice_by_suit(i.haipai34);
     let found_suit = get_same_suit(i.current_pattern, false);
-    if found_suit == -1
-        || slices[found_suit as usize][0] < 3
-        || slices[found_suit as usize][8] < 3
-        || slices[found_suit as usize].contains(&0)
-    {
+    if found_suit == -1 {
+        return false;
+    }
+    let suit = i.haipai34.suit(found_suit as usize);
+    if suit[0] < 3 || suit[8] < 3 || suit.contains(&0) {
         return false;
     }
-    i.taken_tile != -1
-        && [1, 3].contains(&slices[found_suit as usize][((i.taken_tile - 1) % 9) as usize])
+    i.taken_tile != -1 && [1, 3].contains(&suit[((i.taken_tile - 1) % 9) as usize])
 }
 
 fn yaku_check_suuankou_tanki(i: &YakuCheckInput) -> bool {
@@ -254,15 +247,15 @@ fn yaku_check_daisangen(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_tsuuiisou(i: &YakuCheckInput) -> bool {
-    check_allowed(i.haipai, i.furo, &Vec::from(HONORS))
+    check_allowed(i.haipai, i.furo, &HONORS)
 }
 
 fn yaku_check_ryuuiisou(i: &YakuCheckInput) -> bool {
-    check_allowed(i.haipai, i.furo, &Vec::from(GREENS))
+    check_allowed(i.haipai, i.furo, &GREENS)
 }
 
 fn yaku_check_chinroutou(i: &YakuCheckInput) -> bool {
-    check_allowed(i.haipai, i.furo, &Vec::from(TERMINALS))
+    check_allowed(i.haipai, i.furo, &TERMINALS)
 }
 
 fn yaku_check_suukantsu(i: &YakuCheckInput) -> bool {
@@ -304,7 +297,9 @@ fn yaku_check_ryanpeikou(i: &YakuCheckInput) -> bool {
         return false;
     }
 
-    let mut set: HashSet<i64> = HashSet::new();
+    // Shuntsu without a pair yet
+    let mut unpaired = [0i64; 4];
+    let mut unpaired_count = 0;
     let mut shuntsu_pairs_count = 0;
     let mut shuntsu_count = 0;
 
@@ -315,11 +310,13 @@ fn yaku_check_ryanpeikou(i: &YakuCheckInput) -> bool {
         }
         if v.len() == 3 {
             shuntsu_count += 1;
-            if set.contains(&dig) {
+            if let Some(pos) = unpaired[..unpaired_count].iter().position(|d| *d == dig) {
                 shuntsu_pairs_count += 1;
-                set.remove(&dig);
-            } else {
-                set.insert(dig);
+                unpaired_count -= 1;
+                unpaired[pos] = unpaired[unpaired_count];
+            } else if unpaired_count < unpaired.len() {
+                unpaired[unpaired_count] = dig;
+                unpaired_count += 1;
             }
         }
     }
@@ -328,11 +325,11 @@ fn yaku_check_ryanpeikou(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_junchan(i: &YakuCheckInput) -> bool {
-    check_chanta_like(i.current_pattern, &Vec::from(TERMINALS))
+    check_chanta_like(i.current_pattern, &TERMINALS)
 }
 
 fn yaku_check_chanta(i: &YakuCheckInput) -> bool {
-    check_chanta_like(i.current_pattern, &Vec::from(TERMINALS_AND_HONORS)) && !yaku_check_junchan(i)
+    check_chanta_like(i.current_pattern, &TERMINALS_AND_HONORS) && !yaku_check_junchan(i)
 }
 
 fn yaku_check_toitoi(i: &YakuCheckInput) -> bool {
@@ -346,7 +343,7 @@ fn yaku_check_toitoi(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_honroutou(i: &YakuCheckInput) -> bool {
-    check_allowed(i.haipai, i.furo, &Vec::from(TERMINALS_AND_HONORS))
+    check_allowed(i.haipai, i.furo, &TERMINALS_AND_HONORS)
 }
 
 fn yaku_check_sankantsu(i: &YakuCheckInput) -> bool {
@@ -360,7 +357,7 @@ fn yaku_check_sankantsu(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_shosangen(i: &YakuCheckInput) -> bool {
-    let need = vec![Tiles::WD as i8, Tiles::GD as i8, Tiles::RD as i8];
+    let need = [Tiles::WD as i8, Tiles::GD as i8, Tiles::RD as i8];
     let mut kotsu_or_toitsu = 0;
     for v in i.current_pattern {
         if need.contains(&v[0].abs()) && v[0] == v[1] {
@@ -371,7 +368,7 @@ fn yaku_check_shosangen(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_sanshoku_doukou(i: &YakuCheckInput) -> bool {
-    let mut res = vec![0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut res = [0; 9];
     for v in i.current_pattern {
         let abs = v[0].abs();
         if v.len() >= 3 && v[0] == v[1] && !HONORS.contains(&abs) {
@@ -384,35 +381,31 @@ fn yaku_check_sanshoku_doukou(i: &YakuCheckInput) -> bool {
 fn yaku_check_sanankou(i: &YakuCheckInput) -> bool {
     let mut kotsu = 0;
 
-    // keep here all tiles that are not closed kantsu
-    let mut open_kotsu: Vec<i8> = Vec::new();
-    for v in i.furo {
-        if v[0] > 0 && v[0] == v[1] {
-            open_kotsu.push(v[0]);
-        }
-    }
-
-    let closed_part = i
-        .current_pattern
-        .iter()
-        .filter(|set| {
-            i.furo
-                .iter()
-                .find(|meld| digest_simple(set) == digest_simple(meld))
-                == None
-        })
-        .collect::<Vec<&Vec<i8>>>();
+    // open kotsu, closed kantsu have negative values
+    let is_open_kotsu = |tile: i8| {
+        i.furo
+            .iter()
+            .any(|v| v[0] > 0 && v[0] == v[1] && v[0] == tile)
+    };
+    let is_closed = |set: &SetTiles| {
+        !i.furo
+            .iter()
+            .any(|meld| digest_simple(set) == digest_simple(meld))
+    };
 
     for v in i.current_pattern {
-        if open_kotsu.contains(&v[0])
+        if is_open_kotsu(v[0])
             || (v.len() >= 3
                 && !i.is_tsumo
                 && i.taken_tile != -1
                 && v[0] == v[1]
                 && v.contains(&i.taken_tile)
-                && closed_part.iter().find(|set| {
-                    i.taken_tile == v[0] && set[0] != set[1] && set.contains(&&i.taken_tile)
-                }) == None)
+                && !i.current_pattern.iter().any(|set| {
+                    is_closed(set)
+                        && i.taken_tile == v[0]
+                        && set[0] != set[1]
+                        && set.contains(&i.taken_tile)
+                }))
         {
             continue;
         }
@@ -425,27 +418,22 @@ fn yaku_check_sanankou(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_chiitoitsu(i: &YakuCheckInput) -> bool {
-    check7(i.haipai34) && !yaku_check_ryanpeikou(i)
+    check7(&i.haipai34) && !yaku_check_ryanpeikou(i)
 }
 
 // Note: order of double riichi should be checked externally;
 // We assume that closed kan is done after riichi.
+fn is_double_riichi(i: &YakuCheckInput) -> bool {
+    i.double_riichi && !i.furo.iter().any(|m| m[0] > 0)
+}
+
 fn yaku_check_daburu_riichi(i: &YakuCheckInput) -> bool {
-    i.double_riichi
-        && i.furo
-            .iter()
-            .filter(|m| m[0] > 0)
-            .collect::<Vec<&Vec<i8>>>()
-            .len()
-            == 0
+    is_double_riichi(i) && !i.open_riichi
 }
 
 fn yaku_check_ittsu(i: &YakuCheckInput) -> bool {
-    let mut res: Vec<Vec<i8>> = vec![
-        vec![0, 0, 0], // man shuntsu
-        vec![0, 0, 0], // pin shuntsu
-        vec![0, 0, 0], // sou shuntsu
-    ];
+    // man, pin and sou shuntsu
+    let mut res = [[0; 3]; 3];
 
     for v in i.current_pattern {
         if v[0] == v[1] {
@@ -461,11 +449,7 @@ fn yaku_check_ittsu(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_sanshoku(i: &YakuCheckInput) -> bool {
-    let mut res: Vec<Vec<i8>> = vec![
-        vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
-        vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
-        vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
-    ];
+    let mut res = [[0; 9]; 3];
 
     for v in i.current_pattern {
         if v[0] == v[1] {
@@ -488,7 +472,7 @@ fn yaku_check_tanyao(i: &YakuCheckInput) -> bool {
     if i.furo.iter().any(|s| s[0] > 0) && !i.allow_kuitan {
         return false;
     }
-    check_allowed(i.haipai, i.furo, &Vec::from(SIMPLE_TILES))
+    check_allowed(i.haipai, i.furo, &SIMPLE_TILES)
 }
 
 fn yaku_check_pinfu(i: &YakuCheckInput) -> bool {
@@ -591,7 +575,7 @@ fn yaku_check_menzentsumo(i: &YakuCheckInput) -> bool {
 }
 
 fn yaku_check_riichi(i: &YakuCheckInput) -> bool {
-    i.riichi && !yaku_check_daburu_riichi(i)
+    i.riichi && !is_double_riichi(i) && !i.open_riichi
 }
 
 fn yaku_check_ippatsu(i: &YakuCheckInput) -> bool {
@@ -673,13 +657,27 @@ fn yaku_check_chun(i: &YakuCheckInput) -> bool {
     check_yakuhai(i.current_pattern, i.jikaze, i.bakaze, Tiles::RD as i8)
 }
 
+fn yaku_check_open_riichi(i: &YakuCheckInput) -> bool {
+    i.open_riichi && i.riichi && !is_double_riichi(i)
+}
+
+fn yaku_check_open_daburu_riichi(i: &YakuCheckInput) -> bool {
+    i.open_riichi && is_double_riichi(i)
+}
+
+// Non-riichi player could see the waits and still dealt in
+fn yaku_check_open_riichi_deal_in(i: &YakuCheckInput) -> bool {
+    i.open_riichi && (i.riichi || i.double_riichi) && i.open_riichi_deal_in && !i.is_tsumo
+}
+
 // Yaku settings aggregate
 
-// Note: order MUST be the same as in Yaku enum
-// to be able to reference the settings by enum
-pub static YAKU_SETTINGS: [YakuCheck; 53] = [
+// Every setting carries its yaku id. Dora, uradora and akadora have no checks,
+// they are counted separately after yaku. Yakuman go first: regular yaku are skipped once one is found.
+pub static YAKU_SETTINGS: [YakuCheck; 56] = [
     // 'kokushimusou 13 sides':
     YakuCheck {
+        id: Yaku::Kokushimusou13Sides as i8,
         is_local: false,
         yakuman: 2,
         han: 0,
@@ -689,6 +687,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // kokushimusou:
     YakuCheck {
+        id: Yaku::Kokushimusou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -698,6 +697,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'chuurenpoto 9 sides':
     YakuCheck {
+        id: Yaku::Chuurenpoto9Sides as i8,
         is_local: false,
         yakuman: 2,
         han: 0,
@@ -707,6 +707,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chuurenpoto:
     YakuCheck {
+        id: Yaku::Chuurenpoto as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -716,6 +717,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'suuankou tanki':
     YakuCheck {
+        id: Yaku::SuuankouTanki as i8,
         is_local: false,
         yakuman: 2,
         han: 0,
@@ -725,6 +727,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // suuankou:
     YakuCheck {
+        id: Yaku::Suuankou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -734,6 +737,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // daisuushi:
     YakuCheck {
+        id: Yaku::Daisuushi as i8,
         is_local: false,
         yakuman: 2,
         han: 0,
@@ -743,6 +747,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // shosuushi:
     YakuCheck {
+        id: Yaku::Shosuushi as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -752,6 +757,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // daisangen:
     YakuCheck {
+        id: Yaku::Daisangen as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -761,6 +767,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // tsuuiisou:
     YakuCheck {
+        id: Yaku::Tsuuiisou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -770,6 +777,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // ryuuiisou:
     YakuCheck {
+        id: Yaku::Ryuuiisou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -779,6 +787,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chinroutou:
     YakuCheck {
+        id: Yaku::Chinroutou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -788,6 +797,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // suukantsu:
     YakuCheck {
+        id: Yaku::Suukantsu as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -797,6 +807,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // tenhou:
     YakuCheck {
+        id: Yaku::Tenhou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -806,6 +817,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chihou:
     YakuCheck {
+        id: Yaku::Chihou as i8,
         is_local: false,
         yakuman: 1,
         han: 0,
@@ -815,6 +827,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // renhou:
     YakuCheck {
+        id: Yaku::Renhou as i8,
         is_local: true,
         yakuman: 1,
         han: 0,
@@ -824,6 +837,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // daisharin:
     YakuCheck {
+        id: Yaku::Daisharin as i8,
         is_local: true,
         yakuman: 1,
         han: 0,
@@ -831,8 +845,19 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
         is_furo_minus: false,
         check: yaku_check_daisharin,
     },
+    // 'open riichi deal-in':
+    YakuCheck {
+        id: Yaku::OpenRiichiDealIn as i8,
+        is_local: true,
+        yakuman: 1,
+        han: 0,
+        is_menzen_only: true,
+        is_furo_minus: false,
+        check: yaku_check_open_riichi_deal_in,
+    },
     // chinitsu:
     YakuCheck {
+        id: Yaku::Chinitsu as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -842,6 +867,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // honitsu:
     YakuCheck {
+        id: Yaku::Honitsu as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -851,6 +877,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // ryanpeikou:
     YakuCheck {
+        id: Yaku::Ryanpeikou as i8,
         is_local: false,
         yakuman: 0,
         han: 3,
@@ -860,6 +887,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // junchan:
     YakuCheck {
+        id: Yaku::Junchan as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -869,6 +897,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chanta:
     YakuCheck {
+        id: Yaku::Chanta as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -878,6 +907,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // toitoi:
     YakuCheck {
+        id: Yaku::Toitoi as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -887,6 +917,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // honroutou:
     YakuCheck {
+        id: Yaku::Honroutou as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -896,6 +927,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // sankantsu:
     YakuCheck {
+        id: Yaku::Sankantsu as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -905,6 +937,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // shosangen:
     YakuCheck {
+        id: Yaku::Shosangen as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -914,6 +947,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'sanshoku doukou':
     YakuCheck {
+        id: Yaku::SanshokuDoukou as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -923,6 +957,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // sanankou:
     YakuCheck {
+        id: Yaku::Sanankou as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -932,6 +967,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chiitoitsu:
     YakuCheck {
+        id: Yaku::Chiitoitsu as i8,
         is_local: false,
         yakuman: 0,
         han: 2,
@@ -941,6 +977,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'daburu riichi':
     YakuCheck {
+        id: Yaku::DaburuRiichi as i8,
         is_local: false,
         yakuman: 0,
         han: 2,
@@ -950,6 +987,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // ittsu:
     YakuCheck {
+        id: Yaku::Ittsu as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -959,6 +997,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // sanshoku:
     YakuCheck {
+        id: Yaku::Sanshoku as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -968,6 +1007,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // tanyao:
     YakuCheck {
+        id: Yaku::Tanyao as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -977,6 +1017,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // pinfu:
     YakuCheck {
+        id: Yaku::Pinfu as i8,
         is_local: false,
         yakuman: 0,
         han: 1,
@@ -986,6 +1027,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // iipeikou:
     YakuCheck {
+        id: Yaku::Iipeikou as i8,
         is_local: false,
         yakuman: 0,
         han: 1,
@@ -995,6 +1037,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // menzentsumo:
     YakuCheck {
+        id: Yaku::Menzentsumo as i8,
         is_local: false,
         yakuman: 0,
         han: 1,
@@ -1004,6 +1047,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // riichi:
     YakuCheck {
+        id: Yaku::Riichi as i8,
         is_local: false,
         yakuman: 0,
         han: 1,
@@ -1013,6 +1057,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // ippatsu:
     YakuCheck {
+        id: Yaku::Ippatsu as i8,
         is_local: false,
         yakuman: 0,
         han: 1,
@@ -1022,6 +1067,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // rinshan:
     YakuCheck {
+        id: Yaku::Rinshan as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1031,6 +1077,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chankan:
     YakuCheck {
+        id: Yaku::Chankan as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1040,6 +1087,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // haitei:
     YakuCheck {
+        id: Yaku::Haitei as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1049,6 +1097,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // houtei:
     YakuCheck {
+        id: Yaku::Houtei as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1058,6 +1107,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'round wind east':
     YakuCheck {
+        id: Yaku::RoundWindEast as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1067,6 +1117,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'round wind south':
     YakuCheck {
+        id: Yaku::RoundWindSouth as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1076,6 +1127,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'round wind west':
     YakuCheck {
+        id: Yaku::RoundWindWest as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1085,6 +1137,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'round wind north':
     YakuCheck {
+        id: Yaku::RoundWindNorth as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1094,6 +1147,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'own wind east':
     YakuCheck {
+        id: Yaku::OwnWindEast as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1103,6 +1157,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'own wind south':
     YakuCheck {
+        id: Yaku::OwnWindSouth as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1112,6 +1167,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'own wind west':
     YakuCheck {
+        id: Yaku::OwnWindWest as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1121,6 +1177,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // 'own wind north':
     YakuCheck {
+        id: Yaku::OwnWindNorth as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1130,6 +1187,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // haku:
     YakuCheck {
+        id: Yaku::Haku as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1139,6 +1197,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // hatsu:
     YakuCheck {
+        id: Yaku::Hatsu as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1148,6 +1207,7 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
     },
     // chun:
     YakuCheck {
+        id: Yaku::Chun as i8,
         is_local: false,
         yakuman: 0,
         is_menzen_only: false,
@@ -1155,4 +1215,24 @@ pub static YAKU_SETTINGS: [YakuCheck; 53] = [
         is_furo_minus: false,
         check: yaku_check_chun,
     },
+    // 'open riichi':
+    YakuCheck {
+        id: Yaku::OpenRiichi as i8,
+        is_local: false,
+        yakuman: 0,
+        han: 2,
+        is_menzen_only: true,
+        is_furo_minus: false,
+        check: yaku_check_open_riichi,
+    },
+    // 'open daburu riichi':
+    YakuCheck {
+        id: Yaku::OpenDaburuRiichi as i8,
+        is_local: false,
+        yakuman: 0,
+        han: 3,
+        is_menzen_only: true,
+        is_furo_minus: false,
+        check: yaku_check_open_daburu_riichi,
+    },
 ];